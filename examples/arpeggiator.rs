@@ -4,7 +4,7 @@ use mumuse::music::scale::Scale;
 
 fn main() {
     // Open Midi output port connection
-    let mut conn_out = midi::get_output_connection("Virtual Midi Bus 1".to_string());
+    let mut conn_out = midi::get_output_connection("Virtual Midi Bus 1".to_string()).unwrap();
 
     // Create root note
     let root = Note::try_from("C3").unwrap();
@@ -1,5 +1,5 @@
 use mumuse::music::chord::Chord;
-use mumuse::music::common::Interval;
+use mumuse::music::interval::Interval;
 use mumuse::music::note::Note;
 
 fn main() {
@@ -8,7 +8,7 @@ fn main() {
     println!("From str vector : {}", chord_from_str);
 
     // Same chord, but from a Vector Note
-    let notes = vec!["C4", "E4", "G4"]
+    let notes = ["C4", "E4", "G4"]
         .iter()
         .filter_map(|x| Note::try_from(*x).ok())
         .collect();
@@ -17,7 +17,7 @@ fn main() {
 
     // Chord from Note
     let note = Note::try_from("C3").unwrap();
-    let chord = note.chord("major");
+    let chord = note.chord("maj").unwrap(); // unknown chord types are errors
     println!("Note to Chord : {chord}");
 
     // Inversion
@@ -27,8 +27,8 @@ fn main() {
     println!("First inversion : {}", second_inv);
 
     // Transposition
-    let chord_one_fifth = chord_from_str.clone() + Interval::Fifth; // Chord is not Copy
-    let chord_one_octave_down = chord_from_str.clone() - Interval::Octave;
+    let chord_one_fifth = chord_from_str.clone() + Interval::FIFTH; // Chord is not Copy
+    let chord_one_octave_down = chord_from_str.clone() - Interval::OCTAVE;
     println!("Fifth up : {}", chord_one_fifth);
     println!("Octave down : {}", chord_one_octave_down);
 }
@@ -8,12 +8,12 @@ fn main() {
     let scales = [Scale::major(root), Scale::minor(root)];
 
     // Play them through midi
-    // midi::show_output_ports(); // show output ports
-    let mut conn_out = midi::get_output_connection("Virtual Midi Bus 1".to_string());
+    // midi::show_output_ports().unwrap(); // show output ports
+    let mut conn_out = midi::get_output_connection("Virtual Midi Bus 1".to_string()).unwrap();
 
     // Play chords in major key, then in minor
     for scale in scales {
-        let chords = vec![scale.two(3), scale.five(3), scale.one(3)];
+        let chords = [scale.two(3), scale.five(3), scale.one(3)];
         chords
             .iter()
             .for_each(|chord| chord.send_midi_with_duration(&mut conn_out, 500, 64));
@@ -1,4 +1,5 @@
-use mumuse::music::common::{Interval, Letter};
+use mumuse::music::common::Letter;
+use mumuse::music::interval::Interval;
 use mumuse::music::note::Note;
 
 fn main() {
@@ -10,10 +11,10 @@ fn main() {
     println!("Note : {}", n);
 
     // Transpose up by one Fifth
-    let fifth = n + Interval::Fifth;
+    let fifth = n + Interval::FIFTH;
     println!("Fifth up : {}", fifth);
 
     // Transpose down by one Octave
-    let octave = n - Interval::Octave;
+    let octave = n - Interval::OCTAVE;
     println!("Octave down : {}", octave);
 }
@@ -1,4 +1,4 @@
-use mumuse::music::common::Interval;
+use mumuse::music::interval::Interval;
 use mumuse::music::note::Note;
 use mumuse::music::scale::Scale;
 
@@ -7,7 +7,7 @@ fn main() {
     let root = Note::try_from("C0").unwrap();
 
     // Declare vector of intervals
-    let intervals = vec![Interval::MajorSecond, Interval::Fifth];
+    let intervals = vec![Interval::MAJOR_SECOND, Interval::FIFTH];
 
     // Declare scale from root and intervals and print it
     let scale = Scale::new(root, intervals);
@@ -0,0 +1,33 @@
+use mumuse::music::duration::Duration;
+use mumuse::music::note::Note;
+use mumuse::music::scale::Scale;
+use mumuse::music::stream::Stream;
+use mumuse::music::tempo::TempoMap;
+use mumuse::music::time::Time;
+use mumuse::smf::Format;
+
+fn main() {
+    let mut stream: Stream = Stream::new();
+    stream.tempo = TempoMap::new(100.0).unwrap();
+
+    // Arpeggiate a two five one progression in eighth notes
+    let root = Note::try_from("C3").unwrap();
+    let chords = [
+        Scale::major(root).two(4),
+        Scale::major(root).five(4),
+        Scale::major(root).one(4),
+    ];
+    let mut time = Time::new(1, 8, 1);
+    let duration = Duration::new(8, 1);
+    for chord in chords {
+        for note in chord.notes.iter().chain(chord.notes.iter().rev()) {
+            stream.add_note(*note, time, duration);
+            time += duration;
+        }
+    }
+
+    // Write a format 1 file, tempo map in first track and notes in the second
+    let smf = stream.to_smf(Format::MultiTrack);
+    smf.save("two_five_one.mid")
+        .expect("Could not write midi file.");
+}
@@ -0,0 +1,22 @@
+use mumuse::midi;
+use mumuse::music::tuning::{Temperament, Tuning, TuningSystem};
+use mumuse::sysex::ALL_CALL;
+
+fn main() {
+    // midi::show_output_ports().unwrap();
+    let mut conn_out = midi::get_output_connection("Virtual Midi Bus 1".to_string()).unwrap();
+
+    // Quarter comma meantone, A4 at 415 Hz
+    let meantone = TuningSystem::with_reference(Temperament::meantone(0.25), Tuning::new(415.0));
+    for key in [60, 64, 67, 72] {
+        println!("Key {} : {:.2} Hz", key, meantone.frequency(key).unwrap());
+    }
+
+    // Retune the whole keyboard of a MIDI Tuning Standard compatible synth
+    let dump = meantone.to_tuning_dump(ALL_CALL, 0, "Meantone");
+    midi::send_sysex(&mut conn_out, &dump.to_sysex()).unwrap();
+
+    // Scala files can be loaded as well
+    // let scale = Temperament::load_scl("pelog.scl").unwrap();
+    // let mapping = KeyboardMapping::load_kbm("pelog.kbm").unwrap();
+}
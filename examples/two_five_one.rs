@@ -1,23 +1,23 @@
 use mumuse::midi::{self, MidiSend};
 use mumuse::music::chord::Chord;
-use mumuse::music::note::Note;
-use mumuse::music::scale::Scale;
+use mumuse::music::key::Key;
+use mumuse::music::roman::RomanNumeral;
 
 fn main() {
-    // Declare a root note
-    let root = Note::try_from("C4").unwrap();
+    // Declare a key
+    let key = Key::try_from("C major").unwrap();
 
-    // Fill with chords
-    let chords: Vec<Chord> = vec![
-        Scale::major(root).two(4),
-        Scale::major(root).five(4),
-        Scale::major(root).one(4),
-    ];
+    // Fill with chords from their roman numerals
+    let chords: Vec<Chord> = ["ii7", "V7", "Imaj7"]
+        .iter()
+        .map(|numeral| RomanNumeral::try_from(*numeral).unwrap().realize(&key))
+        .collect();
 
     // Play them through midi
-    // midi::show_output_ports(); // show output ports
-    let mut conn_out = midi::get_output_connection("Virtual Midi Bus 1".to_string());
+    // midi::show_output_ports().unwrap(); // show output ports
+    let mut conn_out = midi::get_output_connection("Virtual Midi Bus 1".to_string()).unwrap();
     for chord in chords {
+        println!("{} : {}", key.analyze(&chord).unwrap(), chord);
         chord.send_midi_with_duration(&mut conn_out, 500, 64);
     }
 }
@@ -1,6 +1,6 @@
 use itertools::Itertools;
 use mumuse::midi::{self, MidiSend};
-use mumuse::music::common::Interval;
+use mumuse::music::interval::Interval;
 use mumuse::music::note::Note;
 
 fn main() {
@@ -10,14 +10,14 @@ fn main() {
     // Compute whole tone scale
     let whole_tone = (0..12)
         .scan(root, |s, _| {
-            *s = *s + Interval::MajorSecond;
+            *s = *s + Interval::MAJOR_SECOND;
             Some(*s)
         })
         .collect_vec();
 
     // Play them through midi
-    // midi::show_output_ports(); // show output ports
-    let mut conn_out = midi::get_output_connection("Virtual Midi Bus 1".to_string());
+    // midi::show_output_ports().unwrap(); // show output ports
+    let mut conn_out = midi::get_output_connection("Virtual Midi Bus 1".to_string()).unwrap();
     whole_tone
         .iter()
         .for_each(|note| note.send_midi_with_duration(&mut conn_out, 100, 64));
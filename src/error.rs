@@ -0,0 +1,125 @@
+//! Error type shared by the crate
+
+use crate::messages::{Data, DecodeError};
+use crate::smf;
+use std::{fmt, io};
+
+/// Result alias using the crate `Error`
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors returned by fallible operations of the crate
+#[derive(Debug)]
+pub enum Error {
+    /// `input` could not be parsed, `expected` being missing at char `position`
+    Parse {
+        input: String,
+        position: usize,
+        expected: &'static str,
+    },
+    /// Pitch outside of the representable range, as a semitone offset from C0
+    PitchOutOfRange(i32),
+    /// MIDI key number above 127
+    KeyOutOfRange(u8),
+    /// Frequency that is not a positive finite number of Hz
+    InvalidFrequency(f64),
+    /// Tempo that is not a positive finite number of beats per minute
+    InvalidTempo(f64),
+    /// Quality that can't qualify the interval number, like a perfect third
+    InvalidInterval(String),
+    /// Chord quality that is not known
+    UnknownChordQuality(String),
+    /// Time signature without beats, or with a denominator that is not a power of two
+    InvalidTimeSignature(String),
+    /// Temperament without any scale degree
+    InvalidTemperament(String),
+    /// Midi data of an unexpected kind
+    UnexpectedData(Data),
+    /// No midi port with this name
+    PortNotFound(String),
+    /// Midi device could not be opened or connected to
+    Connection(String),
+    /// Input or output failure
+    Io(io::Error),
+    /// Malformed Standard MIDI File
+    Smf(smf::Error),
+    /// Malformed midi byte stream
+    Decode(DecodeError),
+}
+
+impl Error {
+    /// Creates a `Parse` error for `input`
+    pub(crate) fn parse(input: &str, position: usize, expected: &'static str) -> Self {
+        Error::Parse {
+            input: input.to_string(),
+            position,
+            expected,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse {
+                input,
+                position,
+                expected,
+            } => write!(
+                f,
+                "could not parse \"{}\": expected {} at position {}",
+                input, expected, position
+            ),
+            Error::PitchOutOfRange(semitones) => {
+                write!(f, "pitch {} semitones from C0 is out of range", semitones)
+            }
+            Error::KeyOutOfRange(key) => write!(f, "key number {} is above 127", key),
+            Error::InvalidFrequency(frequency) => write!(f, "invalid frequency {} Hz", frequency),
+            Error::InvalidTempo(bpm) => write!(f, "invalid tempo {} bpm", bpm),
+            Error::InvalidInterval(interval) => write!(f, "invalid interval {}", interval),
+            Error::UnknownChordQuality(quality) => {
+                write!(f, "unknown chord quality \"{}\"", quality)
+            }
+            Error::InvalidTimeSignature(signature) => {
+                write!(f, "invalid time signature {}", signature)
+            }
+            Error::InvalidTemperament(temperament) => {
+                write!(f, "invalid temperament {}", temperament)
+            }
+            Error::UnexpectedData(data) => write!(f, "unexpected midi data {:?}", data),
+            Error::PortNotFound(name) => write!(f, "midi port \"{}\" not found", name),
+            Error::Connection(reason) => write!(f, "midi connection failed: {}", reason),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Smf(e) => write!(f, "{}", e),
+            Error::Decode(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Smf(e) => Some(e),
+            Error::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<smf::Error> for Error {
+    fn from(e: smf::Error) -> Self {
+        Error::Smf(e)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Self {
+        Error::Decode(e)
+    }
+}
@@ -4,9 +4,14 @@ extern crate num;
 extern crate num_derive;
 
 pub mod conversions;
+pub mod error;
 pub mod messages;
 pub mod midi;
 pub mod music;
+pub mod smf;
+pub mod sysex;
+
+pub use error::{Error, Result};
 
 #[cfg(test)]
 pub mod tests;
@@ -1,11 +1,13 @@
 //! Raw midi parsing and display
 
 use crate::conversions::encode_hex;
-use crate::music::common::KEYBOARD;
 use crate::music::note::Note;
 use colored::Colorize;
 use std::fmt;
 
+/// Highest velocity (and data byte) value
+pub const MAX_VELOCITY: u8 = 0x7F;
+
 /// Raw message contains bytes values
 #[derive(Debug)]
 pub struct Raw {
@@ -25,7 +27,17 @@ impl Raw {
     }
 
     // Parse Raw message into Midi message
+    //
+    // Messages missing data bytes are parsed with an `Unknown` status.
     pub fn parse(&self) -> Midi {
+        if let Some(len) = data_length(self.status) {
+            if self.data.len() < len {
+                return Midi {
+                    stamp: self.stamp,
+                    ..Default::default()
+                };
+            }
+        }
         let status_hex = &encode_hex(&[self.status])[..];
         match &status_hex[0..1] {
             "8" => Midi {
@@ -49,48 +61,48 @@ impl Raw {
                     Data::PressureAmount(self.data[1]),
                 ],
             },
-            "b" => match &encode_hex(&[self.data[1]])[..] {
+            "b" => match &encode_hex(&[self.data[0]])[..] {
                 "79" => Midi {
-                    channel: 16,
+                    channel: u8::from_str_radix(&status_hex[1..], 16).unwrap(),
                     stamp: self.stamp,
                     status: Status::ControlChange,
-                    data: [Data::ResetAllControllers, Data::None],
+                    data: [Data::ResetAllControllers(self.data[1]), Data::None],
                 },
                 "7a" => Midi {
-                    channel: 16,
+                    channel: u8::from_str_radix(&status_hex[1..], 16).unwrap(),
                     stamp: self.stamp,
                     status: Status::ControlChange,
-                    data: [Data::LocalControl(self.data[2]), Data::None],
+                    data: [Data::LocalControl(self.data[1]), Data::None],
                 },
                 "7b" => Midi {
-                    channel: 16,
+                    channel: u8::from_str_radix(&status_hex[1..], 16).unwrap(),
                     stamp: self.stamp,
                     status: Status::ControlChange,
-                    data: [Data::AllNotesOff, Data::None],
+                    data: [Data::AllNotesOff(self.data[1]), Data::None],
                 },
                 "7c" => Midi {
-                    channel: 16,
+                    channel: u8::from_str_radix(&status_hex[1..], 16).unwrap(),
                     stamp: self.stamp,
                     status: Status::ControlChange,
-                    data: [Data::OmniModeOff, Data::None],
+                    data: [Data::OmniModeOff(self.data[1]), Data::None],
                 },
                 "7d" => Midi {
-                    channel: 16,
+                    channel: u8::from_str_radix(&status_hex[1..], 16).unwrap(),
                     stamp: self.stamp,
                     status: Status::ControlChange,
-                    data: [Data::OmniModeOn, Data::None],
+                    data: [Data::OmniModeOn(self.data[1]), Data::None],
                 },
                 "7e" => Midi {
-                    channel: 16,
+                    channel: u8::from_str_radix(&status_hex[1..], 16).unwrap(),
                     stamp: self.stamp,
                     status: Status::ControlChange,
-                    data: [Data::MonoModeOn, Data::None],
+                    data: [Data::MonoModeOn(self.data[1]), Data::None],
                 },
                 "7f" => Midi {
-                    channel: 16,
+                    channel: u8::from_str_radix(&status_hex[1..], 16).unwrap(),
                     stamp: self.stamp,
                     status: Status::ControlChange,
-                    data: [Data::PolyModeOn, Data::None],
+                    data: [Data::PolyModeOn(self.data[1]), Data::None],
                 },
                 _ => Midi {
                     channel: u8::from_str_radix(&status_hex[1..], 16).unwrap(),
@@ -118,26 +130,41 @@ impl Raw {
                 channel: u8::from_str_radix(&status_hex[1..], 16).unwrap(),
                 stamp: self.stamp,
                 status: Status::PitchBend,
-                data: [Data::MSB(self.data[0]), Data::LSB(self.data[1])],
+                data: [Data::LSB(self.data[0]), Data::MSB(self.data[1])],
             },
             "f" => match &status_hex[1..] {
+                "0" => Midi {
+                    channel: 16,
+                    stamp: self.stamp,
+                    status: Status::SystemExclusive,
+                    data: [
+                        Data::Exclusive(
+                            self.data
+                                .iter()
+                                .copied()
+                                .take_while(|b| *b != 0xF7)
+                                .collect(),
+                        ),
+                        Data::None,
+                    ],
+                },
                 "1" => Midi {
                     channel: 16,
                     stamp: self.stamp,
                     status: Status::MidiTimingCode,
-                    data: [Data::Generic(self.data[1]), Data::None],
+                    data: [Data::Generic(self.data[0]), Data::None],
                 },
                 "2" => Midi {
                     channel: 16,
                     stamp: self.stamp,
                     status: Status::SongPositionPointer,
-                    data: [Data::Generic(self.data[1]), Data::Generic(self.data[2])],
+                    data: [Data::Generic(self.data[0]), Data::Generic(self.data[1])],
                 },
                 "3" => Midi {
                     channel: 16,
                     stamp: self.stamp,
                     status: Status::SongSelect,
-                    data: [Data::Generic(self.data[1]), Data::None],
+                    data: [Data::Generic(self.data[0]), Data::None],
                 },
                 "6" => Midi {
                     stamp: self.stamp,
@@ -191,7 +218,7 @@ impl Raw {
 }
 
 /// Midi message contains custom type events
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Midi {
     channel: u8,
     stamp: u64,
@@ -236,17 +263,246 @@ impl fmt::Display for Midi {
 }
 
 impl Midi {
+    /// Creates a `Midi` message from its `channel`, `status` and `data`.
+    ///
+    /// Prefer the dedicated constructors, which build the same data as `Raw::parse`.
+    pub fn new(channel: u8, status: Status, data: [Data; 2]) -> Self {
+        Midi {
+            channel,
+            stamp: 0,
+            status,
+            data,
+        }
+    }
+
+    /// Note on message
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::messages::Midi;
+    /// let m = Midi::note_on(1, 60, 100);
+    /// assert_eq!(m.to_bytes(), vec![0x91, 60, 100]);
+    /// ```
+    pub fn note_on(channel: u8, key: u8, velocity: u8) -> Self {
+        Self::channel_message(Status::NoteOn, channel, &[key, velocity])
+    }
+
+    /// Note off message
+    pub fn note_off(channel: u8, key: u8, velocity: u8) -> Self {
+        Self::channel_message(Status::NoteOff, channel, &[key, velocity])
+    }
+
+    /// Polyphonic key pressure (aftertouch) message
+    pub fn polyphonic_key_pressure(channel: u8, key: u8, pressure: u8) -> Self {
+        Self::channel_message(Status::PolyphonicKeyPressure, channel, &[key, pressure])
+    }
+
+    /// Control change message
+    ///
+    /// Controllers 0x79 to 0x7F are channel mode messages and get their dedicated `Data`,
+    /// holding the value.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::messages::{Data, Midi};
+    /// let m = Midi::control_change(0, 7, 127); // Channel volume
+    /// assert_eq!(m.to_bytes(), vec![0xB0, 7, 127]);
+    /// let mono = Midi::control_change(0, 0x7E, 4); // Mono mode on four channels
+    /// assert_eq!(mono.data()[0], Data::MonoModeOn(4));
+    /// assert_eq!(mono.to_bytes(), vec![0xB0, 0x7E, 4]);
+    /// ```
+    pub fn control_change(channel: u8, controller: u8, value: u8) -> Self {
+        Self::channel_message(Status::ControlChange, channel, &[controller, value])
+    }
+
+    /// Program change message
+    pub fn program_change(channel: u8, program: u8) -> Self {
+        Self::channel_message(Status::ProgramChange, channel, &[program])
+    }
+
+    /// Channel pressure (aftertouch) message
+    pub fn channel_pressure(channel: u8, pressure: u8) -> Self {
+        Self::channel_message(Status::ChannelPressure, channel, &[pressure])
+    }
+
+    /// Pitch bend message from a signed `value`, 0 being the center
+    ///
+    /// The value is clamped to the 14 bits range -8192..=8191.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::messages::Midi;
+    /// let m = Midi::pitch_bend(0, -8192);
+    /// assert_eq!(m.to_bytes(), vec![0xE0, 0x00, 0x00]);
+    /// assert_eq!(m.pitch_bend_value(), Some(-8192));
+    /// ```
+    pub fn pitch_bend(channel: u8, value: i16) -> Self {
+        let value = (value.clamp(-8192, 8191) + 8192) as u16;
+        let lsb = (value & 0x7F) as u8;
+        let msb = (value >> 7) as u8;
+        Self::channel_message(Status::PitchBend, channel, &[lsb, msb])
+    }
+
+    /// MIDI time code quarter frame message
+    pub fn timing_code(value: u8) -> Self {
+        Self::system_message(Status::MidiTimingCode, &[value])
+    }
+
+    /// Song position pointer message, `position` counting sixteenth notes on 14 bits
+    pub fn song_position_pointer(position: u16) -> Self {
+        let lsb = (position & 0x7F) as u8;
+        let msb = ((position >> 7) & 0x7F) as u8;
+        Self::system_message(Status::SongPositionPointer, &[lsb, msb])
+    }
+
+    /// Song select message
+    pub fn song_select(song: u8) -> Self {
+        Self::system_message(Status::SongSelect, &[song])
+    }
+
+    /// System Exclusive message from its `payload`, without the 0xF0 and 0xF7 markers
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::messages::Midi;
+    /// let m = Midi::system_exclusive(vec![0x7E, 0x7F, 0x09, 0x01]); // General MIDI on
+    /// assert_eq!(m.to_bytes(), vec![0xF0, 0x7E, 0x7F, 0x09, 0x01, 0xF7]);
+    /// ```
+    pub fn system_exclusive(payload: Vec<u8>) -> Self {
+        Self::system_message(Status::SystemExclusive, &payload)
+    }
+
+    /// Single byte system message (tune request and real time messages)
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::messages::{Midi, Status};
+    /// let m = Midi::system(Status::TimingClock);
+    /// assert_eq!(m.to_bytes(), vec![0xF8]);
+    /// ```
+    pub fn system(status: Status) -> Self {
+        Self::system_message(status, &[])
+    }
+
+    /// Builds a channel message through `Raw::parse`
+    fn channel_message(status: Status, channel: u8, data: &[u8]) -> Self {
+        let status = status as u8 | (channel & 0x0F);
+        Raw::new(0, status, data.iter().map(|d| d & 0x7F).collect()).parse()
+    }
+
+    /// Builds a system message through `Raw::parse`
+    fn system_message(status: Status, data: &[u8]) -> Self {
+        Raw::new(0, status as u8, data.iter().map(|d| d & 0x7F).collect()).parse()
+    }
+
+    /// Encodes the message into its MIDI bytes, status byte first.
+    ///
+    /// Messages with an `Unknown` status encode to an empty vector.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::messages::{Midi, Raw};
+    /// let m = Midi::control_change(3, 0x7B, 0); // All notes off
+    /// let bytes = m.to_bytes();
+    /// assert_eq!(Raw::new(0, bytes[0], bytes[1..].to_vec()).parse(), m);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let channel = self.channel & 0x0F;
+        let status = self.status as u8;
+        let value = |i: usize| self.data[i].value().unwrap_or(0) & 0x7F;
+        match self.status {
+            Status::NoteOff
+            | Status::NoteOn
+            | Status::PolyphonicKeyPressure
+            | Status::PitchBend => vec![status | channel, value(0), value(1)],
+            Status::ProgramChange | Status::ChannelPressure => vec![status | channel, value(0)],
+            Status::ControlChange => {
+                let (controller, value) = match self.data[0] {
+                    Data::ResetAllControllers(v) => (0x79, v & 0x7F),
+                    Data::LocalControl(v) => (0x7A, v & 0x7F),
+                    Data::AllNotesOff(v) => (0x7B, v & 0x7F),
+                    Data::OmniModeOff(v) => (0x7C, v & 0x7F),
+                    Data::OmniModeOn(v) => (0x7D, v & 0x7F),
+                    Data::MonoModeOn(v) => (0x7E, v & 0x7F),
+                    Data::PolyModeOn(v) => (0x7F, v & 0x7F),
+                    _ => (value(0), value(1)),
+                };
+                vec![status | channel, controller, value]
+            }
+            Status::SystemExclusive => {
+                let mut bytes = vec![status];
+                if let Data::Exclusive(payload) = &self.data[0] {
+                    bytes.extend(payload.iter().map(|b| b & 0x7F));
+                }
+                bytes.push(0xF7);
+                bytes
+            }
+            Status::MidiTimingCode | Status::SongSelect => vec![status, value(0)],
+            Status::SongPositionPointer => vec![status, value(0), value(1)],
+            Status::Unknown => vec![],
+            _ => vec![status],
+        }
+    }
+
+    /// Channel of the message, 16 for system messages
+    pub fn channel(&self) -> u8 {
+        self.channel
+    }
+
+    /// Reception time stamp of the message
+    pub fn stamp(&self) -> u64 {
+        self.stamp
+    }
+
+    /// Status of the message
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Data of the message
+    pub fn data(&self) -> &[Data; 2] {
+        &self.data
+    }
+
+    /// Signed pitch bend value, 0 being the center, for `PitchBend` messages
+    pub fn pitch_bend_value(&self) -> Option<i16> {
+        match (&self.status, &self.data) {
+            (Status::PitchBend, [Data::LSB(lsb), Data::MSB(msb)]) => {
+                Some((((*msb as i16) << 7) | *lsb as i16) - 8192)
+            }
+            _ => None,
+        }
+    }
+
     // Get Note struct from Midi message
     pub fn get_midi_note(&self) -> Option<Note> {
         match &self.status {
-            Status::NoteOn | Status::NoteOff => Some(Note::try_from(&self.data[0]).unwrap()),
+            Status::NoteOn | Status::NoteOff => Note::try_from(&self.data[0]).ok(),
             _ => None,
         }
     }
 }
 
 /// Status is determined by first byte of midi frame
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[allow(dead_code)]
 pub enum Status {
     NoteOff = 0x80,               // 8x
@@ -256,6 +512,7 @@ pub enum Status {
     ProgramChange = 0xC0,         // Cx
     ChannelPressure = 0xD0,       // Dx
     PitchBend = 0xE0,             // Ex
+    SystemExclusive = 0xF0,       // F0
     MidiTimingCode = 0xF1,        // F1
     SongPositionPointer = 0xF2,   // F2
     SongSelect = 0xF3,            // F3
@@ -270,7 +527,7 @@ pub enum Status {
 }
 
 /// Midi data, second and optional third bytes
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[allow(dead_code)]
 pub enum Data {
     KeyNumber(u8),
@@ -282,18 +539,451 @@ pub enum Data {
     PressureValue(u8),
     MSB(u8),
     LSB(u8),
-    ResetAllControllers,
+    ResetAllControllers(u8),
     LocalControl(u8),
-    AllNotesOff,
-    OmniModeOff,
-    OmniModeOn,
-    MonoModeOn,
-    PolyModeOn,
+    AllNotesOff(u8),
+    OmniModeOff(u8),
+    OmniModeOn(u8),
+    /// Mono mode with the number of channels to use, 0 for as many as voices
+    MonoModeOn(u8),
+    PolyModeOn(u8),
     Generic(u8),
+    /// System Exclusive payload, without the 0xF0 and 0xF7 markers
+    Exclusive(Vec<u8>),
     None,
 }
 
-pub fn from_note(note: &Note) -> Data {
-    let p = KEYBOARD.iter().position(|&n| n == note.letter).unwrap() as u8;
-    Data::KeyNumber(12 + p + (note.octave as u8) * 12)
+impl Data {
+    /// Byte value carried by the data, if any
+    pub fn value(&self) -> Option<u8> {
+        match *self {
+            Data::KeyNumber(v)
+            | Data::Velocity(v)
+            | Data::ControllerNumber(v)
+            | Data::ControllerValue(v)
+            | Data::PressureAmount(v)
+            | Data::ProgramNumber(v)
+            | Data::PressureValue(v)
+            | Data::MSB(v)
+            | Data::LSB(v)
+            | Data::ResetAllControllers(v)
+            | Data::LocalControl(v)
+            | Data::AllNotesOff(v)
+            | Data::OmniModeOff(v)
+            | Data::OmniModeOn(v)
+            | Data::MonoModeOn(v)
+            | Data::PolyModeOn(v)
+            | Data::Generic(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// Number of data bytes following a status byte
+///
+/// Returns `None` for data bytes, System Exclusive markers and undefined status.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mumuse::messages::data_length;
+/// assert_eq!(data_length(0x93), Some(2));
+/// assert_eq!(data_length(0xC0), Some(1));
+/// assert_eq!(data_length(0xF8), Some(0));
+/// assert_eq!(data_length(0xF4), None);
+/// ```
+pub fn data_length(status: u8) -> Option<usize> {
+    match status {
+        0x80..=0xBF | 0xE0..=0xEF | 0xF2 => Some(2),
+        0xC0..=0xDF | 0xF1 | 0xF3 => Some(1),
+        0xF6 | 0xF8 | 0xFA..=0xFC | 0xFE | 0xFF => Some(0),
+        _ => None,
+    }
+}
+
+/// Error raised while decoding a MIDI byte stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Data `byte` at stream `position` with no status to apply
+    MissingStatus { position: usize, byte: u8 },
+    /// Message of a given `status` interrupted at stream `position` before all its data was received
+    Truncated { position: usize, status: u8 },
+    /// Undefined status `byte` at stream `position`
+    UndefinedStatus { position: usize, byte: u8 },
+    /// End of exclusive byte at stream `position` outside of a System Exclusive message
+    UnexpectedEndOfExclusive { position: usize },
+    /// System Exclusive payload ending at `position` while `expected` was being read
+    MalformedExclusive {
+        position: usize,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::MissingStatus { position, byte } => {
+                write!(f, "data byte {:#04x} at {} without status", byte, position)
+            }
+            DecodeError::Truncated { position, status } => write!(
+                f,
+                "message with status {:#04x} truncated at {}",
+                status, position
+            ),
+            DecodeError::UndefinedStatus { position, byte } => {
+                write!(f, "undefined status {:#04x} at {}", byte, position)
+            }
+            DecodeError::UnexpectedEndOfExclusive { position } => {
+                write!(
+                    f,
+                    "end of exclusive at {} outside of system exclusive",
+                    position
+                )
+            }
+            DecodeError::MalformedExclusive { position, expected } => write!(
+                f,
+                "system exclusive payload ends at {} while reading {}",
+                position, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Stateful decoder turning arbitrary chunks of a MIDI byte stream into `Midi` messages
+///
+/// Handles running status, real time messages interleaved inside other messages
+/// and System Exclusive messages split over several chunks.
+/// Invalid or truncated data yields errors without stopping the decoding.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mumuse::messages::{Decoder, Midi};
+/// let mut decoder = Decoder::new();
+/// let mut messages = decoder.feed(0, &[0x90, 60]);
+/// messages.extend(decoder.feed(0, &[100, 62, 100])); // Running status
+/// assert_eq!(messages[0], Ok(Midi::note_on(0, 60, 100)));
+/// assert_eq!(messages[1], Ok(Midi::note_on(0, 62, 100)));
+/// ```
+#[derive(Debug, Default)]
+pub struct Decoder {
+    /// Status of the message being received
+    status: Option<u8>,
+    /// Data bytes of the message being received, System Exclusive payload included
+    data: Vec<u8>,
+    /// Whether a status byte was received and its message is not complete yet
+    pending: bool,
+    /// Whether a System Exclusive message is being received
+    exclusive: bool,
+    /// Number of bytes decoded so far
+    position: usize,
+}
+
+impl Decoder {
+    /// Creates a `Decoder` waiting for a status byte.
+    pub fn new() -> Self {
+        Decoder {
+            status: None,
+            data: vec![],
+            pending: false,
+            exclusive: false,
+            position: 0,
+        }
+    }
+
+    /// Decodes a chunk of bytes received at `stamp`.
+    ///
+    /// Returns the messages completed by the chunk, in order.
+    /// Incomplete messages are kept until the next call.
+    pub fn feed(&mut self, stamp: u64, bytes: &[u8]) -> Vec<Result<Midi, DecodeError>> {
+        let mut decoded = vec![];
+        for &byte in bytes {
+            self.push(stamp, byte, &mut decoded);
+            self.position += 1;
+        }
+        decoded
+    }
+
+    /// Reports a pending incomplete message and resets the decoder.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::messages::Decoder;
+    /// let mut decoder = Decoder::new();
+    /// decoder.feed(0, &[0x90, 60]);
+    /// assert!(decoder.finish().is_err());
+    /// ```
+    pub fn finish(&mut self) -> Result<(), DecodeError> {
+        let result = self.check_interrupted();
+        self.status = None;
+        self.data.clear();
+        self.pending = false;
+        self.exclusive = false;
+        result
+    }
+
+    /// Error if a message is interrupted at the current position
+    fn check_interrupted(&self) -> Result<(), DecodeError> {
+        let status = match (self.exclusive, self.status) {
+            (true, _) => 0xF0,
+            (false, Some(status)) if self.pending => status,
+            _ => return Ok(()),
+        };
+        Err(DecodeError::Truncated {
+            position: self.position,
+            status,
+        })
+    }
+
+    fn push(&mut self, stamp: u64, byte: u8, decoded: &mut Vec<Result<Midi, DecodeError>>) {
+        let position = self.position;
+        match byte {
+            // Real time messages may appear anywhere without affecting the current message
+            0xF8..=0xFF => match data_length(byte) {
+                Some(_) => decoded.push(Ok(Raw::new(stamp, byte, vec![]).parse())),
+                None => decoded.push(Err(DecodeError::UndefinedStatus { position, byte })),
+            },
+            0xF7 => {
+                if self.exclusive {
+                    self.exclusive = false;
+                    let payload = std::mem::take(&mut self.data);
+                    decoded.push(Ok(Raw::new(stamp, 0xF0, payload).parse()));
+                } else {
+                    decoded.push(Err(DecodeError::UnexpectedEndOfExclusive { position }));
+                }
+            }
+            0x80..=0xF6 => {
+                if let Err(e) = self.check_interrupted() {
+                    decoded.push(Err(e));
+                }
+                self.data.clear();
+                self.exclusive = byte == 0xF0;
+                self.status = None;
+                self.pending = false;
+                match data_length(byte) {
+                    Some(0) => decoded.push(Ok(Raw::new(stamp, byte, vec![]).parse())),
+                    Some(_) => {
+                        self.status = Some(byte);
+                        self.pending = true;
+                    }
+                    None if byte == 0xF0 => (),
+                    None => decoded.push(Err(DecodeError::UndefinedStatus { position, byte })),
+                }
+            }
+            _ if self.exclusive => self.data.push(byte),
+            _ => match self.status {
+                Some(status) => {
+                    self.data.push(byte);
+                    self.pending = true;
+                    if Some(self.data.len()) == data_length(status) {
+                        let data = std::mem::take(&mut self.data);
+                        self.pending = false;
+                        decoded.push(Ok(Raw::new(stamp, status, data).parse()));
+                        // Only channel messages keep their status for running status
+                        if status >= 0xF0 {
+                            self.status = None;
+                        }
+                    }
+                }
+                None => decoded.push(Err(DecodeError::MissingStatus { position, byte })),
+            },
+        }
+    }
+}
+
+/// Converts a `Note` to a `Data::KeyNumber`, middle C (C4) being key 60
+pub fn from_note(note: &Note) -> crate::Result<Data> {
+    Ok(Data::KeyNumber(note.to_midi()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Any message the parser understands, built from its constructors
+    fn any_midi() -> impl Strategy<Value = Midi> {
+        let channel = 0..16u8;
+        let data = 0..128u8;
+        prop_oneof![
+            (channel.clone(), data.clone(), data.clone())
+                .prop_map(|(c, k, v)| Midi::note_on(c, k, v)),
+            (channel.clone(), data.clone(), data.clone())
+                .prop_map(|(c, k, v)| Midi::note_off(c, k, v)),
+            (channel.clone(), data.clone(), data.clone())
+                .prop_map(|(c, k, p)| Midi::polyphonic_key_pressure(c, k, p)),
+            (channel.clone(), data.clone(), data.clone())
+                .prop_map(|(c, n, v)| Midi::control_change(c, n, v)),
+            (channel.clone(), data.clone()).prop_map(|(c, p)| Midi::program_change(c, p)),
+            (channel.clone(), data.clone()).prop_map(|(c, p)| Midi::channel_pressure(c, p)),
+            (channel, -8192..8192i16).prop_map(|(c, v)| Midi::pitch_bend(c, v)),
+            data.clone().prop_map(Midi::timing_code),
+            (0..0x4000u16).prop_map(Midi::song_position_pointer),
+            data.prop_map(Midi::song_select),
+            proptest::collection::vec(0..128u8, 0..32).prop_map(Midi::system_exclusive),
+            prop_oneof![
+                Just(Status::TuneRequest),
+                Just(Status::TimingClock),
+                Just(Status::StartSequence),
+                Just(Status::ContinueSequence),
+                Just(Status::StopSequence),
+                Just(Status::ActiveSensing),
+                Just(Status::SystemReset),
+            ]
+            .prop_map(Midi::system),
+        ]
+    }
+
+    /// Bytes of any valid message, a status followed by its data bytes
+    fn any_message_bytes() -> impl Strategy<Value = Vec<u8>> {
+        let defined = (0x80..=0xFFu8).prop_filter("defined status", |s| {
+            *s != 0xF0 && *s != 0xF7 && data_length(*s).is_some()
+        });
+        let message = defined.prop_flat_map(|status| {
+            let len = data_length(status).unwrap();
+            proptest::collection::vec(0..128u8, len).prop_map(move |data| {
+                let mut bytes = vec![status];
+                bytes.extend(data);
+                bytes
+            })
+        });
+        let exclusive = proptest::collection::vec(0..128u8, 0..32).prop_map(|payload| {
+            let mut bytes = vec![0xF0];
+            bytes.extend(payload);
+            bytes.push(0xF7);
+            bytes
+        });
+        prop_oneof![message, exclusive]
+    }
+
+    proptest! {
+        #[test]
+        fn parse_encode_round_trip(midi in any_midi()) {
+            let bytes = midi.to_bytes();
+            let parsed = Raw::new(midi.stamp(), bytes[0], bytes[1..].to_vec()).parse();
+            prop_assert_eq!(parsed, midi);
+        }
+
+        #[test]
+        fn encode_parse_round_trip(bytes in any_message_bytes()) {
+            let parsed = Raw::new(0, bytes[0], bytes[1..].to_vec()).parse();
+            prop_assert_eq!(parsed.to_bytes(), bytes);
+        }
+
+        #[test]
+        fn pitch_bend_value(value in -8192..8192i16) {
+            prop_assert_eq!(Midi::pitch_bend(0, value).pitch_bend_value(), Some(value));
+        }
+    }
+
+    #[test]
+    fn decoder_chunks() {
+        let bytes = [0xB1, 0x07, 0x64, 0x0A, 0x40, 0xC2, 0x05, 0xF2, 0x10, 0x20];
+        let expected = vec![
+            Ok(Midi::control_change(1, 0x07, 0x64)),
+            Ok(Midi::control_change(1, 0x0A, 0x40)),
+            Ok(Midi::program_change(2, 0x05)),
+            Ok(Midi::song_position_pointer(0x1010)),
+        ];
+        for chunk_size in 1..bytes.len() {
+            let mut decoder = Decoder::new();
+            let decoded = bytes
+                .chunks(chunk_size)
+                .flat_map(|chunk| decoder.feed(0, chunk))
+                .collect::<Vec<_>>();
+            assert_eq!(decoded, expected);
+            assert_eq!(decoder.finish(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn decoder_real_time_and_exclusive() {
+        let mut decoder = Decoder::new();
+        let bytes = [
+            0x90, 0x3C, 0xF8, 0x40, // Clock inside a note on
+            0xF0, 0x7E, 0x7F, 0xF8, 0x09, 0x01, 0xF7, // Clock inside a System Exclusive
+            0x3E, 0x40, // Running status does not survive System Exclusive
+        ];
+        let decoded = decoder.feed(0, &bytes);
+        assert_eq!(
+            decoded,
+            vec![
+                Ok(Midi::system(Status::TimingClock)),
+                Ok(Midi::note_on(0, 0x3C, 0x40)),
+                Ok(Midi::system(Status::TimingClock)),
+                Ok(Midi::system_exclusive(vec![0x7E, 0x7F, 0x09, 0x01])),
+                Err(DecodeError::MissingStatus {
+                    position: 11,
+                    byte: 0x3E
+                }),
+                Err(DecodeError::MissingStatus {
+                    position: 12,
+                    byte: 0x40
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn decoder_errors() {
+        let mut decoder = Decoder::new();
+        let decoded = decoder.feed(0, &[0x90, 0x3C, 0x80, 0x3C, 0x00, 0xC0, 0xF4, 0xF7, 0xF3]);
+        assert_eq!(
+            decoded,
+            vec![
+                Err(DecodeError::Truncated {
+                    position: 2,
+                    status: 0x90
+                }),
+                Ok(Midi::note_off(0, 0x3C, 0x00)),
+                Err(DecodeError::Truncated {
+                    position: 6,
+                    status: 0xC0
+                }),
+                Err(DecodeError::UndefinedStatus {
+                    position: 6,
+                    byte: 0xF4
+                }),
+                Err(DecodeError::UnexpectedEndOfExclusive { position: 7 }),
+            ]
+        );
+        assert_eq!(
+            decoder.finish(),
+            Err(DecodeError::Truncated {
+                position: 9,
+                status: 0xF3
+            })
+        );
+    }
+
+    #[test]
+    fn parse_short_buffer() {
+        let midi = Raw::new(0, 0xB0, vec![0x07]).parse();
+        assert_eq!(midi.status(), Status::Unknown);
+    }
+
+    #[test]
+    fn channel_mode_messages() {
+        let reset = Raw::new(0, 0xB5, vec![0x79, 0x00]).parse();
+        assert_eq!(reset.channel(), 5);
+        assert_eq!(reset.data()[0], Data::ResetAllControllers(0));
+
+        let local = Raw::new(0, 0xB0, vec![0x7A, 0x7F]).parse();
+        assert_eq!(local.data()[0], Data::LocalControl(0x7F));
+
+        let volume = Raw::new(0, 0xB2, vec![0x07, 0x64]).parse();
+        assert_eq!(
+            volume.data(),
+            &[Data::ControllerNumber(0x07), Data::ControllerValue(0x64)]
+        );
+    }
 }
@@ -1,10 +1,15 @@
 //! Midi send and receive helpers
 
+use crate::error::{Error, Result};
 use crate::messages;
-use crate::messages::{Data, Status};
+use crate::messages::{Data, Midi, Status};
 use crate::music::chord::Chord;
 use crate::music::note::Note;
-use midir::{MidiIO, MidiInput, MidiInputPort, MidiOutput, MidiOutputConnection, MidiOutputPort};
+use crate::sysex::SysEx;
+use itertools::Itertools;
+use midir::{
+    Ignore, MidiIO, MidiInput, MidiInputPort, MidiOutput, MidiOutputConnection, MidiOutputPort,
+};
 use std::io::stdin;
 use std::thread::sleep;
 use std::time::Duration;
@@ -27,21 +32,21 @@ impl MidiSend for Note {
         duration: u64,
         velocity: u8,
     ) {
-        let mut kn: u8 = 0;
-        if let Data::KeyNumber(x) = messages::from_note(self) {
-            kn = x;
-        };
-        let _ = conn_out.send(&[Status::NoteOn as u8, kn, velocity]);
+        // Notes outside of the key range are not sent
+        let kn = self.to_midi().ok();
+        if let Some(kn) = kn {
+            let _ = conn_out.send(&Midi::note_on(0, kn, velocity).to_bytes());
+        }
         sleep(Duration::from_millis(duration));
-        let _ = conn_out.send(&[Status::NoteOff as u8, kn, velocity]);
+        if let Some(kn) = kn {
+            let _ = conn_out.send(&Midi::note_off(0, kn, velocity).to_bytes());
+        }
     }
 
     fn send_midi(&self, status: Status, conn_out: &mut MidiOutputConnection) {
-        let mut kn: u8 = 0;
-        if let Data::KeyNumber(x) = messages::from_note(self) {
-            kn = x;
-        };
-        let _ = conn_out.send(&[status as u8, kn, 255]);
+        if let Ok(kn) = self.to_midi() {
+            let _ = conn_out.send(&key_message(status, kn).to_bytes());
+        }
     }
 }
 
@@ -52,102 +57,136 @@ impl MidiSend for Chord {
         duration: u64,
         velocity: u8,
     ) {
-        for note in &self.notes {
-            let mut kn: u8 = 0;
-            if let Data::KeyNumber(x) = messages::from_note(note) {
-                kn = x;
-            };
-            let _ = conn_out.send(&[0x90, kn, velocity]);
+        // Notes outside of the key range are not sent
+        let keys = self
+            .notes
+            .iter()
+            .filter_map(|n| n.to_midi().ok())
+            .collect_vec();
+        for kn in &keys {
+            let _ = conn_out.send(&Midi::note_on(0, *kn, velocity).to_bytes());
         }
         sleep(Duration::from_millis(duration));
-        for note in &self.notes {
-            let mut kn: u8 = 0;
-            if let Data::KeyNumber(x) = messages::from_note(note) {
-                kn = x;
-            };
-            let _ = conn_out.send(&[0x80, kn, velocity]);
+        for kn in &keys {
+            let _ = conn_out.send(&Midi::note_off(0, *kn, velocity).to_bytes());
         }
     }
 
     fn send_midi(&self, status: Status, conn_out: &mut MidiOutputConnection) {
-        for note in &self.notes {
-            let mut kn: u8 = 0;
-            if let Data::KeyNumber(x) = messages::from_note(note) {
-                kn = x;
-            };
-            let _ = conn_out.send(&[status as u8, kn, 255]);
+        for kn in self.notes.iter().filter_map(|n| n.to_midi().ok()) {
+            let _ = conn_out.send(&key_message(status, kn).to_bytes());
         }
     }
 }
 
+/// Message of a given `status` for key number `kn` at full velocity on the first channel
+fn key_message(status: Status, kn: u8) -> Midi {
+    Midi::new(
+        0,
+        status,
+        [Data::KeyNumber(kn), Data::Velocity(messages::MAX_VELOCITY)],
+    )
+}
+
+/// Sends a System Exclusive message
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```no_run
+/// use mumuse::midi;
+/// use mumuse::sysex::{Universal, ALL_CALL};
+/// let mut conn_out = midi::get_output_connection("Virtual Midi Bus 1".to_string()).unwrap();
+/// let gm_on = Universal::GeneralMidiOn { device: ALL_CALL }.to_sysex();
+/// midi::send_sysex(&mut conn_out, &gm_on).unwrap();
+/// ```
+pub fn send_sysex(conn_out: &mut MidiOutputConnection, sysex: &SysEx) -> Result<()> {
+    conn_out
+        .send(&sysex.to_bytes())
+        .map_err(|e| Error::Connection(e.to_string()))
+}
+
 /// Lists available input port devices
-pub fn show_input_ports() {
-    let midi_in = MidiInput::new("midi_in").expect("Could not open midi input.");
+pub fn show_input_ports() -> Result<()> {
+    let midi_in = MidiInput::new("midi_in").map_err(|e| Error::Connection(e.to_string()))?;
     for (i, p) in midi_in.ports().iter().enumerate() {
-        println!("in ({}) : {}", i, midi_in.port_name(&p).unwrap());
+        let name = midi_in
+            .port_name(p)
+            .map_err(|e| Error::Connection(e.to_string()))?;
+        println!("in ({}) : {}", i, name);
     }
+    Ok(())
 }
 
 /// Lists available output port devices
-pub fn show_output_ports() {
-    let midi_out = MidiOutput::new("midi_out").expect("Could not open midi input.");
+pub fn show_output_ports() -> Result<()> {
+    let midi_out = MidiOutput::new("midi_out").map_err(|e| Error::Connection(e.to_string()))?;
     for (i, p) in midi_out.ports().iter().enumerate() {
-        println!("out ({}) : {}", i, midi_out.port_name(&p).unwrap());
+        let name = midi_out
+            .port_name(p)
+            .map_err(|e| Error::Connection(e.to_string()))?;
+        println!("out ({}) : {}", i, name);
     }
+    Ok(())
 }
 
 /// Open connection
-pub fn get_output_connection(s: String) -> MidiOutputConnection {
-    let midi_out = MidiOutput::new("midi_out").expect("Could not open midi output.");
+///
+/// Fails with `Error::PortNotFound` if no output port is named `s`.
+pub fn get_output_connection(s: String) -> Result<MidiOutputConnection> {
+    let midi_out = MidiOutput::new("midi_out").map_err(|e| Error::Connection(e.to_string()))?;
     let out_ports = midi_out.ports();
-    let device_port: Option<&MidiOutputPort> = match get_port_index_by_name(&midi_out, s) {
-        Some(i) => out_ports.get(i),
-        None => None,
-    };
+    let device_port: &MidiOutputPort = get_port_index_by_name(&midi_out, &s)
+        .and_then(|i| out_ports.get(i))
+        .ok_or(Error::PortNotFound(s))?;
     midi_out
-        .connect(device_port.unwrap(), "midir-test")
-        .unwrap()
+        .connect(device_port, "midir-test")
+        .map_err(|e| Error::Connection(e.to_string()))
 }
 
 /// Finds port for a given string name
-fn get_port_index_by_name<T: MidiIO>(midi_in: &T, name: String) -> Option<usize> {
-    let mut port_index: Option<usize> = None;
-    for (i, p) in midi_in.ports().iter().enumerate() {
-        if midi_in.port_name(&p).unwrap().eq(&name) {
-            port_index = Some(i);
-            break;
-        }
-    }
-    port_index
+fn get_port_index_by_name<T: MidiIO>(midi_in: &T, name: &str) -> Option<usize> {
+    midi_in
+        .ports()
+        .iter()
+        .position(|p| midi_in.port_name(p).map_or(false, |n| n == name))
 }
 
 /// Midi stream receive and parse
-pub fn receive(name: String) {
+///
+/// Fails with `Error::PortNotFound` if no input port is named `name`.
+pub fn receive(name: String) -> Result<()> {
     let mut input = String::new();
-    let midi_in = MidiInput::new("midi_in").expect("Could not open midi input.");
+    let mut midi_in = MidiInput::new("midi_in").map_err(|e| Error::Connection(e.to_string()))?;
+    midi_in.ignore(Ignore::None); // System Exclusive messages are filtered by default
     let input_ports = midi_in.ports();
 
     // Getting input device port
-    let device_port: Option<&MidiInputPort> = match get_port_index_by_name(&midi_in, name) {
-        Some(i) => input_ports.get(i),
-        None => None,
-    };
+    let device_port: &MidiInputPort = get_port_index_by_name(&midi_in, &name)
+        .and_then(|i| input_ports.get(i))
+        .ok_or(Error::PortNotFound(name))?;
 
     // Opening connection with input midi device
+    let mut decoder = messages::Decoder::new();
     let _conn_in = midi_in
         .connect(
-            device_port.expect("Couldn't get device from name."),
+            device_port,
             "midi_conn",
             move |stamp, message, _| {
-                let raw_message = messages::Raw::new(stamp, message[0], message[1..].to_vec());
-                let parsed: messages::Midi = raw_message.parse();
-                println!("{}", parsed);
+                for decoded in decoder.feed(stamp, message) {
+                    match decoded {
+                        Ok(parsed) => println!("{}", parsed),
+                        Err(e) => println!("{}", e),
+                    }
+                }
             },
             (),
         )
-        .unwrap();
+        .map_err(|e| Error::Connection(e.to_string()))?;
 
     println!("Press any key to terminate.");
     input.clear();
-    stdin().read_line(&mut input).unwrap();
+    stdin().read_line(&mut input)?;
+    Ok(())
 }
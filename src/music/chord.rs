@@ -1,10 +1,19 @@
 //! Collection of Notes
 
-use crate::music::common::Interval;
+use crate::music::chord_symbol::ChordSymbol;
+use crate::music::common::{find_letter_idx, Accidental, Spelling, Step};
+use crate::music::interval::Interval;
 use crate::music::note::Note;
 use itertools::Itertools;
 use std::{fmt, ops};
 
+/// Chord qualities tried by `Chord::identify`, simplest first
+const TEMPLATES: [&str; 33] = [
+    "", "m", "dim", "aug", "5", "sus4", "sus2", "7", "maj7", "m7", "m7b5", "dim7", "m(maj7)", "6",
+    "m6", "7sus4", "aug7", "7b5", "maj7#5", "add9", "madd9", "6/9", "9", "maj9", "m9", "7b9",
+    "7#9", "7#11", "maj7#11", "11", "m11", "13", "m13",
+];
+
 /// A Chord contains a vector of Notes
 #[derive(Debug, Default, Clone)]
 pub struct Chord {
@@ -39,7 +48,7 @@ impl Chord {
     /// ```
     /// use mumuse::music::note::Note;
     /// let n = Note::try_from("A3").unwrap();
-    /// let c = n.chord("maj").invert(2);
+    /// let c = n.chord("maj").unwrap().invert(2);
     /// ```
     pub fn invert(&self, inversion: usize) -> Self {
         let mut notes = self.notes.clone();
@@ -52,6 +61,116 @@ impl Chord {
     }
 }
 
+impl Chord {
+    /// Names the chord, candidates being ranked from the most likely.
+    ///
+    /// Every note must belong to a candidate, which may lack its fifth, or even its root
+    /// for rootless voicings. The lowest note gives the slash bass of inversions.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::chord::Chord;
+    /// let chord = Chord::from(vec!["C3", "D4", "F4", "A4"]);
+    /// let names: Vec<String> = chord.identify().iter().map(|s| s.to_string()).collect();
+    /// assert_eq!(names[0], "Dm7/C");
+    /// assert!(names.contains(&"F6/C".to_string()));
+    /// ```
+    pub fn identify(&self) -> Vec<ChordSymbol> {
+        let pitch_class = |note: &Note| find_letter_idx(note.letter) as i32;
+        let classes = self.notes.iter().map(pitch_class).unique().collect_vec();
+        if classes.len() < 2 {
+            return vec![];
+        }
+        let bass = self.notes.iter().min_by_key(|n| (n.octave, pitch_class(n)));
+        let spellings = self.notes.iter().map(|n| n.spelling()).collect_vec();
+
+        let mut candidates = vec![];
+        for suffix in TEMPLATES {
+            let intervals = ChordSymbol::quality_intervals(suffix).unwrap();
+            for root in 0..12i32 {
+                let tones = intervals
+                    .iter()
+                    .map(|i| (root + i.semitones()).rem_euclid(12))
+                    .collect_vec();
+                if !classes.iter().all(|c| tones.contains(c)) {
+                    continue;
+                }
+                let rootless = !classes.contains(&root);
+                if rootless && classes.len() < 3 {
+                    continue;
+                }
+                let mut score = 20 * classes.len() as i32 - intervals.len() as i32;
+                for (interval, tone) in intervals.iter().zip(&tones) {
+                    if !classes.contains(tone) {
+                        score -= match *interval {
+                            Interval::UNISON => 6,
+                            Interval::FIFTH => 2,
+                            _ => 16,
+                        };
+                    }
+                }
+                let slash = bass.filter(|b| !rootless && pitch_class(b) != root);
+                score += if slash.is_some() {
+                    -2
+                } else if rootless {
+                    0
+                } else {
+                    4
+                };
+                let root = spell_root(root, &intervals, &spellings);
+                let symbol = ChordSymbol {
+                    root,
+                    suffix: suffix.to_string(),
+                    intervals: intervals.clone(),
+                    bass: slash.map(|b| b.spelling()),
+                };
+                candidates.push((score, symbol));
+            }
+        }
+        candidates
+            .into_iter()
+            .sorted_by_key(|(score, _)| -score)
+            .map(|(_, symbol)| symbol)
+            .collect()
+    }
+}
+
+/// Spelling of the `root` pitch class agreeing the most with the chord `spellings`
+fn spell_root(root: i32, intervals: &[Interval], spellings: &[Spelling]) -> Spelling {
+    [
+        Step::C,
+        Step::D,
+        Step::E,
+        Step::F,
+        Step::G,
+        Step::A,
+        Step::B,
+    ]
+    .iter()
+    .filter_map(|step| {
+        let alteration = (root - step.semitones() as i32 + 6).rem_euclid(12) - 6;
+        // Lead sheets don't use double accidentals for roots
+        if alteration.abs() > 1 {
+            return None;
+        }
+        let accidental = Accidental::from_semitones(alteration as i8)?;
+        let spelling = Spelling::new(*step, accidental);
+        let note = Note::spelled(spelling, 4);
+        let agreeing = intervals
+            .iter()
+            .filter(|i| spellings.contains(&(note + **i).spelling()))
+            .count();
+        let written = spellings.contains(&spelling);
+        Some(((written, agreeing, -alteration.abs()), spelling))
+    })
+    .max_by_key(|(rank, _)| *rank)
+    .map(|(_, spelling)| spelling)
+    .unwrap()
+}
+
 impl From<Vec<&str>> for Chord {
     fn from(notes: Vec<&str>) -> Self {
         Self::new(
@@ -66,7 +185,7 @@ impl From<Vec<&str>> for Chord {
 impl fmt::Display for Chord {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut notes: String = "".to_string();
-        for (i, note) in (&self.notes).iter().enumerate() {
+        for (i, note) in self.notes.iter().enumerate() {
             notes += &format!("{}", note).to_string();
             if i != self.notes.len() - 1 {
                 notes += ","
@@ -84,10 +203,10 @@ impl fmt::Display for Chord {
 ///
 /// ```
 /// use mumuse::music::note::Note;
-/// use mumuse::music::common::Interval;
+/// use mumuse::music::interval::Interval;
 /// let n = Note::try_from("A3").unwrap();
-/// let c = n.chord("maj");
-/// let c_ = c + Interval::MinorSecond;
+/// let c = n.chord("maj").unwrap();
+/// let c_ = c + Interval::MINOR_SECOND;
 /// ```
 impl ops::Add<Interval> for Chord {
     type Output = Chord;
@@ -104,10 +223,10 @@ impl ops::Add<Interval> for Chord {
 ///
 /// ```
 /// use mumuse::music::note::Note;
-/// use mumuse::music::common::Interval;
+/// use mumuse::music::interval::Interval;
 /// let n = Note::try_from("A3").unwrap();
-/// let c = n.chord("maj");
-/// let c_ = c - Interval::MinorSecond;
+/// let c = n.chord("maj").unwrap();
+/// let c_ = c - Interval::MINOR_SECOND;
 /// ```
 impl ops::Sub<Interval> for Chord {
     type Output = Chord;
@@ -136,12 +255,45 @@ mod tests {
     #[test]
     fn transposition() {
         let chord = Chord::from(vec!["C0", "E1", "G2"]);
-        let transposed = chord - Interval::Octave;
+        let transposed = chord - Interval::OCTAVE;
         assert_eq!(transposed.notes[0].octave, -1);
         assert_eq!(transposed.notes[1].octave, 0);
         assert_eq!(transposed.notes[2].octave, 1);
     }
 
+    fn names(chord: &[&str]) -> Vec<String> {
+        Chord::from(chord.to_vec())
+            .identify()
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    /// Chord recognition
+    #[test]
+    fn identify() {
+        assert_eq!(names(&["C4", "E4", "G4"])[0], "C");
+        assert_eq!(names(&["E3", "G4", "C5"])[0], "C/E");
+        assert_eq!(names(&["D4", "F4", "A4", "C5"])[0], "Dm7");
+        assert_eq!(names(&["C3", "D4", "F4", "A4"])[0], "Dm7/C");
+        assert_eq!(names(&["F#3", "A3", "C4", "E4"])[0], "F#m7b5");
+        assert_eq!(names(&["G3", "B3", "F4"])[0], "G7");
+        assert_eq!(names(&["C#4", "E#4", "G#4", "B4"])[0], "C#7");
+        assert_eq!(names(&["Db4", "F4", "Ab4", "Cb5"])[0], "Db7");
+        assert_eq!(names(&["Bb3", "D4", "F4", "Ab4", "E5"])[0], "Bb7#11");
+        assert_eq!(names(&["C4", "G4"])[0], "C5");
+
+        // Rootless voicing of C9
+        assert!(names(&["E3", "Bb3", "D4"]).contains(&"C9".to_string()));
+        // Symmetric chords have several roots
+        let diminished = names(&["C4", "Eb4", "Gb4", "A4"]);
+        assert_eq!(diminished[0], "Cdim7");
+        assert!(diminished.contains(&"Ebdim7/C".to_string()));
+
+        assert!(names(&["C4"]).is_empty());
+        assert!(names(&["C4", "Db4", "D4", "Eb4", "E4"]).is_empty());
+    }
+
     /// Chord creation from string
     #[test]
     fn from_str() {
@@ -0,0 +1,401 @@
+//! Lead sheet chord symbols
+
+use crate::error::{Error, Result};
+use crate::music::chord::Chord;
+use crate::music::common::{find_letter_idx, Accidental, Spelling, Step};
+use crate::music::interval::Interval;
+use crate::music::note::Note;
+use itertools::Itertools;
+use std::fmt;
+
+/// Markers of a major seventh, needing an extension unlike triangles
+const MAJOR: [&str; 4] = ["maj", "Maj", "ma", "M"];
+
+/// A chord symbol as written on lead sheets, like `Cmaj7/G`, `F#m7b5` or `E7alt`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChordSymbol {
+    /// Root of the chord
+    pub root: Spelling,
+    /// Quality, extensions and alterations as written after the root
+    pub suffix: String,
+    /// Intervals of the chord tones from the root, in ascending order
+    pub intervals: Vec<Interval>,
+    /// Bass note of a slash chord
+    pub bass: Option<Spelling>,
+}
+
+impl ChordSymbol {
+    /// Builds the chord with its root in `octave`, a slash bass being placed below.
+    ///
+    /// The bass pitch class is not repeated in the upper notes.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::chord_symbol::ChordSymbol;
+    /// let symbol = ChordSymbol::try_from("Cmaj7/G").unwrap();
+    /// let chord = symbol.to_chord(4);
+    /// assert_eq!(chord.to_string(), "Chord(Note(G3),Note(C4),Note(E4),Note(B4))");
+    /// ```
+    pub fn to_chord(&self, octave: i8) -> Chord {
+        let root = Note::spelled(self.root, octave);
+        let mut notes = self.intervals.iter().map(|i| root + *i).collect_vec();
+        if let Some(bass) = self.bass {
+            notes.retain(|note| note.letter != bass.letter());
+            let mut bass = Note::spelled(bass, octave);
+            let height = |note: &Note| (note.octave, find_letter_idx(note.letter));
+            if height(&bass) >= height(&root) {
+                bass = bass - Interval::OCTAVE;
+            }
+            notes.insert(0, bass);
+        }
+        Chord::new(notes)
+    }
+
+    /// Intervals of a chord quality written without root, like `m7b5` or `13#11`.
+    pub fn quality_intervals(quality: &str) -> Result<Vec<Interval>> {
+        let (intervals, end) = parse_quality(quality, 0)?;
+        if end != quality.len() {
+            return Err(Error::parse(quality, end, "chord quality"));
+        }
+        Ok(intervals)
+    }
+}
+
+/// Displays a `ChordSymbol` as written, like `Bb13#11` or `C/E`
+impl fmt::Display for ChordSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.root, self.suffix)?;
+        match self.bass {
+            Some(bass) => write!(f, "/{}", bass),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Conversion from `&str`.
+///
+/// A root with its accidentals, then the quality (`m`, `-`, `dim`, `°`, `ø`, `aug`, `+`,
+/// `maj`, `Δ`, `5`), extensions (`6`, `7`, `9`, `11`, `13`, `6/9`), and any of `sus2`,
+/// `sus4`, `add9`, `omit5`, `no3`, `alt` and alterations like `b5`, `#9` or `b13`,
+/// optionally in parentheses, then an optional slash bass.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mumuse::music::chord_symbol::ChordSymbol;
+/// let symbol = ChordSymbol::try_from("F#m7b5").unwrap();
+/// let names: Vec<String> = symbol.intervals.iter().map(|i| i.to_string()).collect();
+/// assert_eq!(names, vec!["P1", "m3", "d5", "m7"]);
+/// assert!(ChordSymbol::try_from("Cmaj8").is_err());
+/// ```
+impl TryFrom<&str> for ChordSymbol {
+    type Error = Error;
+    fn try_from(s: &str) -> Result<Self> {
+        let (root, root_end) = parse_spelling(s).ok_or_else(|| Error::parse(s, 0, "chord root"))?;
+        let (intervals, end) = parse_quality(s, root_end)?;
+        let bass = match s[end..].strip_prefix('/') {
+            Some(bass) => match parse_spelling(bass) {
+                Some((spelling, len)) if len == bass.len() => Some(spelling),
+                _ => return Err(Error::parse(s, end + 1, "bass note")),
+            },
+            None => None,
+        };
+        Ok(ChordSymbol {
+            root,
+            suffix: s[root_end..end].to_string(),
+            intervals,
+            bass,
+        })
+    }
+}
+
+/// Chord tones being gathered while parsing
+struct Tones {
+    intervals: Vec<Interval>,
+    seventh: Interval,
+}
+
+impl Tones {
+    fn add(&mut self, interval: Interval) {
+        if !self.intervals.contains(&interval) {
+            self.intervals.push(interval);
+        }
+    }
+
+    fn remove(&mut self, number: u8) {
+        self.intervals.retain(|i| i.number() != number);
+    }
+
+    fn replace(&mut self, interval: Interval) {
+        self.remove(interval.number());
+        self.add(interval);
+    }
+
+    /// Alters the tone of `number`, keeping other alterations of it (as in `7b9#9`)
+    fn alter(&mut self, number: u8, semitones: i32) -> Result<()> {
+        let natural = Interval::natural(number);
+        self.intervals.retain(|i| *i != natural);
+        self.add(natural.altered(semitones)?);
+        Ok(())
+    }
+
+    fn extend(&mut self, extension: u8) {
+        if extension >= 7 {
+            self.add(self.seventh);
+        }
+        let minor = self.intervals.contains(&Interval::MINOR_THIRD);
+        match extension {
+            6 => self.add(Interval::MAJOR_SIXTH),
+            9 => self.add(Interval::MAJOR_NINTH),
+            11 => {
+                self.add(Interval::MAJOR_NINTH);
+                self.add(Interval::ELEVENTH);
+            }
+            13 => {
+                self.add(Interval::MAJOR_NINTH);
+                // The eleventh clashes with a major third
+                if minor {
+                    self.add(Interval::ELEVENTH);
+                }
+                self.add(Interval::MAJOR_THIRTEENTH);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Parses a note name at the start of `s`, returning it and its length
+pub(crate) fn parse_spelling(s: &str) -> Option<(Spelling, usize)> {
+    let step = match s.chars().next()? {
+        'C' => Step::C,
+        'D' => Step::D,
+        'E' => Step::E,
+        'F' => Step::F,
+        'G' => Step::G,
+        'A' => Step::A,
+        'B' => Step::B,
+        _ => return None,
+    };
+    let accidentals = s[1..]
+        .chars()
+        .take_while(|c| *c == 'b' || *c == '#')
+        .count();
+    let alteration = s[1..1 + accidentals]
+        .chars()
+        .map(|c| if c == 'b' { -1 } else { 1 })
+        .sum::<i8>();
+    let accidental = Accidental::from_semitones(alteration)?;
+    Some((Spelling::new(step, accidental), 1 + accidentals))
+}
+
+/// Removes the first of `tokens` starting `rest`
+fn eat(rest: &mut &str, tokens: &[&str]) -> bool {
+    match tokens.iter().find(|token| rest.starts_with(*token)) {
+        Some(token) => {
+            *rest = &rest[token.len()..];
+            true
+        }
+        None => false,
+    }
+}
+
+/// Removes the number starting `rest`
+fn eat_number(rest: &mut &str) -> Option<u8> {
+    let len = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    let number = rest[..len].parse().ok().filter(|n| *n > 0)?;
+    *rest = &rest[len..];
+    Some(number)
+}
+
+/// Removes an alteration sign starting `rest`, returning it in semitones
+fn eat_sign(rest: &mut &str) -> Option<i32> {
+    if eat(rest, &["b", "-", "♭"]) {
+        Some(-1)
+    } else if eat(rest, &["#", "+", "♯"]) {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+/// Parses the chord quality of `input` from `start` up to a slash bass or the end,
+/// returning the intervals and where it stopped.
+fn parse_quality(input: &str, start: usize) -> Result<(Vec<Interval>, usize)> {
+    let mut rest = &input[start..];
+    let position = |rest: &str| input.len() - rest.len();
+    let mut tones = Tones {
+        intervals: vec![Interval::UNISON, Interval::MAJOR_THIRD, Interval::FIFTH],
+        seventh: Interval::MINOR_SEVENTH,
+    };
+
+    // Triad quality, major markers being left to the extensions
+    let major = !rest.starts_with("madd") && MAJOR.iter().any(|token| rest.starts_with(token));
+    if !rest.starts_with("omit") && eat(&mut rest, &["dim", "°", "o"]) {
+        tones.replace(Interval::MINOR_THIRD);
+        tones.replace(Interval::DIMINISHED_FIFTH);
+        tones.seventh = Interval::DIMINISHED_SEVENTH;
+    } else if eat(&mut rest, &["ø", "Ø"]) {
+        tones.replace(Interval::MINOR_THIRD);
+        tones.replace(Interval::DIMINISHED_FIFTH);
+        tones.add(Interval::MINOR_SEVENTH);
+    } else if eat(&mut rest, &["aug", "+"]) {
+        tones.replace(Interval::AUGMENTED_FIFTH);
+    } else if !major && eat(&mut rest, &["min", "mi", "m", "-"]) {
+        tones.replace(Interval::MINOR_THIRD);
+    } else if rest.starts_with('5') && !rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+        rest = &rest[1..];
+        tones.remove(3);
+    }
+
+    loop {
+        let token_start = rest;
+        if eat(&mut rest, &["(", ")", ",", " "]) {
+            continue;
+        }
+        if rest.is_empty() || rest.starts_with('/') {
+            break;
+        }
+        if eat(&mut rest, &["6/9", "69"]) {
+            tones.extend(6);
+            tones.add(Interval::MAJOR_NINTH);
+        } else if eat(&mut rest, &MAJOR) {
+            tones.seventh = Interval::MAJOR_SEVENTH;
+        } else if eat(&mut rest, &["Δ", "^"]) {
+            tones.seventh = Interval::MAJOR_SEVENTH;
+            // A triangle alone stands for a major seventh chord
+            if !rest.starts_with(|c: char| c.is_ascii_digit()) {
+                tones.extend(7);
+            }
+        } else if eat(&mut rest, &["sus2"]) {
+            tones.remove(3);
+            tones.add(Interval::MAJOR_SECOND);
+        } else if eat(&mut rest, &["sus4", "sus"]) {
+            tones.remove(3);
+            tones.add(Interval::FOURTH);
+        } else if eat(&mut rest, &["add"]) {
+            let sign = eat_sign(&mut rest).unwrap_or(0);
+            let number = eat_number(&mut rest)
+                .ok_or_else(|| Error::parse(input, position(rest), "added tone"))?;
+            tones.add(Interval::natural(number).altered(sign)?);
+        } else if eat(&mut rest, &["omit", "no"]) {
+            let number = eat_number(&mut rest)
+                .ok_or_else(|| Error::parse(input, position(rest), "omitted tone"))?;
+            tones.remove(number);
+        } else if eat(&mut rest, &["alt"]) {
+            tones.add(Interval::MINOR_SEVENTH);
+            tones.remove(5);
+            tones.add(Interval::DIMINISHED_FIFTH);
+            tones.add(Interval::AUGMENTED_FIFTH);
+            tones.add(Interval::MINOR_NINTH);
+            tones.add(Interval::AUGMENTED_NINTH);
+        } else if let Some(sign) = eat_sign(&mut rest) {
+            let number = eat_number(&mut rest)
+                .ok_or_else(|| Error::parse(input, position(rest), "altered tone"))?;
+            tones.alter(number, sign)?;
+        } else {
+            match eat_number(&mut rest) {
+                Some(extension @ (6 | 7 | 9 | 11 | 13)) => tones.extend(extension),
+                _ => return Err(Error::parse(input, position(token_start), "chord quality")),
+            }
+        }
+    }
+
+    let intervals = tones
+        .intervals
+        .into_iter()
+        .sorted_by_key(|i| (i.semitones(), i.number()))
+        .collect_vec();
+    Ok((intervals, position(rest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notes(symbol: &str) -> String {
+        ChordSymbol::try_from(symbol)
+            .unwrap()
+            .to_chord(4)
+            .notes
+            .iter()
+            .map(|n| n.to_string().replace("Note(", "").replace(')', ""))
+            .join(" ")
+    }
+
+    #[test]
+    fn triads_and_sevenths() {
+        assert_eq!(notes("C"), "C4 E4 G4");
+        assert_eq!(notes("Cm"), "C4 Eb4 G4");
+        assert_eq!(notes("C-7"), "C4 Eb4 G4 Bb4");
+        assert_eq!(notes("C+"), "C4 E4 G#4");
+        assert_eq!(notes("Caug7"), "C4 E4 G#4 Bb4");
+        assert_eq!(notes("Cdim7"), "C4 Eb4 Gb4 Bbb4");
+        assert_eq!(notes("Cø"), "C4 Eb4 Gb4 Bb4");
+        assert_eq!(notes("F#m7b5"), "F#4 A4 C5 E5");
+        assert_eq!(notes("CΔ"), "C4 E4 G4 B4");
+        assert_eq!(notes("CM"), "C4 E4 G4");
+        assert_eq!(notes("Cm(maj7)"), "C4 Eb4 G4 B4");
+        assert_eq!(notes("CmMaj9"), "C4 Eb4 G4 B4 D5");
+        assert_eq!(notes("C5"), "C4 G4");
+        assert_eq!(notes("Csus"), "C4 F4 G4");
+        assert_eq!(notes("D7sus4"), "D4 G4 A4 C5");
+        assert_eq!(notes("Gsus2"), "G4 A4 D5");
+    }
+
+    #[test]
+    fn extensions_and_alterations() {
+        assert_eq!(notes("C6"), "C4 E4 G4 A4");
+        assert_eq!(notes("C6/9"), "C4 E4 G4 A4 D5");
+        assert_eq!(notes("Cm69"), "C4 Eb4 G4 A4 D5");
+        assert_eq!(notes("Cadd9"), "C4 E4 G4 D5");
+        assert_eq!(notes("Cmadd9"), "C4 Eb4 G4 D5");
+        assert_eq!(notes("Cmaj9"), "C4 E4 G4 B4 D5");
+        assert_eq!(notes("Cm11"), "C4 Eb4 G4 Bb4 D5 F5");
+        assert_eq!(notes("C13"), "C4 E4 G4 Bb4 D5 A5");
+        assert_eq!(notes("Bb13#11"), "Bb4 D5 F5 Ab5 C6 E6 G6");
+        assert_eq!(notes("C7b9"), "C4 E4 G4 Bb4 Db5");
+        assert_eq!(notes("C7(b9,#9)"), "C4 E4 G4 Bb4 Db5 D#5");
+        assert_eq!(notes("C9b5"), "C4 E4 Gb4 Bb4 D5");
+        assert_eq!(notes("C7#5"), "C4 E4 G#4 Bb4");
+        assert_eq!(notes("Cm7b13"), "C4 Eb4 G4 Bb4 Ab5");
+        assert_eq!(notes("E7alt"), "E4 G#4 Bb4 B#4 D5 F5 F##5");
+        assert_eq!(notes("C7no5"), "C4 E4 Bb4");
+        assert_eq!(notes("C9omit3"), "C4 G4 Bb4 D5");
+    }
+
+    #[test]
+    fn slash_chords() {
+        assert_eq!(notes("Cmaj7/G"), "G3 C4 E4 B4");
+        assert_eq!(notes("C/E"), "E3 C4 G4");
+        assert_eq!(notes("D/C"), "C4 D4 F#4 A4");
+        assert_eq!(notes("Am7/Bb"), "Bb3 A4 C5 E5 G5");
+        let symbol = ChordSymbol::try_from("Ebm7b5/Bbb").unwrap();
+        assert_eq!(symbol.to_string(), "Ebm7b5/Bbb");
+        assert_eq!(symbol.suffix, "m7b5");
+    }
+
+    #[test]
+    fn parse_errors() {
+        for (s, position) in [
+            ("", 0),
+            ("H7", 0),
+            ("Cxyz", 1),
+            ("C7/", 3),
+            ("Cmaj7/Q", 6),
+            ("Cadd", 4),
+            ("C8", 1),
+            ("C7b", 3),
+            ("Cbbb", 0),
+        ] {
+            match ChordSymbol::try_from(s) {
+                Err(Error::Parse { position: p, .. }) => assert_eq!(p, position, "{}", s),
+                other => panic!("{:?} parsed as {:?}", s, other),
+            }
+        }
+    }
+}
@@ -1,5 +1,7 @@
 //! Common music definitions
 
+use std::fmt;
+
 /// Twelve tone temperament Keyboard vector of Note
 pub const KEYBOARD: [Letter; 12] = [
     Letter::C,
@@ -16,7 +18,7 @@ pub const KEYBOARD: [Letter; 12] = [
     Letter::B,
 ];
 
-/// Letters to describe notes
+/// Pitch classes of the twelve tone keyboard, named with flats
 #[derive(Debug, Eq, PartialEq, Clone, Copy, FromPrimitive)]
 pub enum Letter {
     C,
@@ -33,45 +35,146 @@ pub enum Letter {
     B,
 }
 
-/// Letters to describe notes
-#[derive(Debug, PartialEq, Clone, Copy, FromPrimitive)]
-pub enum Interval {
-    Unison,
-    MinorSecond,
-    MajorSecond,
-    MinorThird,
-    MajorThird,
-    Fourth,
-    Tritone,
-    Fifth,
-    MinorSixth,
-    MajorSixth,
-    MinorSeventh,
-    MajorSeventh,
-    Octave,
-    MinorNinth,
-    MajorNinth,
-    MinorTenth,
-    MajorTenth,
-    Eleventh,
-    DoubleTritone,
-    Twelfth,
-    MinorThirteenth,
-    MajorThirteenth,
-    MinorFourteenth,
-    MajorFourteenth,
-    Fifteenth,
-}
-
 impl Default for Letter {
     fn default() -> Self {
         Letter::C
     }
 }
 
-impl Default for Interval {
+/// Natural note names, the seven steps of the staff
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash, FromPrimitive)]
+pub enum Step {
+    C,
+    D,
+    E,
+    F,
+    G,
+    A,
+    B,
+}
+
+impl Default for Step {
+    fn default() -> Self {
+        Step::C
+    }
+}
+
+impl Step {
+    /// Semitones from C to the natural note
+    pub fn semitones(&self) -> i8 {
+        [0, 2, 4, 5, 7, 9, 11][*self as usize]
+    }
+}
+
+/// Alteration of a natural note, up to two semitones
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub enum Accidental {
+    DoubleFlat,
+    Flat,
+    Natural,
+    Sharp,
+    DoubleSharp,
+}
+
+impl Default for Accidental {
     fn default() -> Self {
-        Interval::Unison
+        Accidental::Natural
+    }
+}
+
+impl Accidental {
+    /// Alteration in semitones, negative for flats
+    pub fn semitones(&self) -> i8 {
+        *self as i8 - 2
+    }
+
+    /// Accidental altering by `semitones`, `None` beyond double sharps and flats.
+    pub fn from_semitones(semitones: i8) -> Option<Self> {
+        match semitones {
+            -2 => Some(Accidental::DoubleFlat),
+            -1 => Some(Accidental::Flat),
+            0 => Some(Accidental::Natural),
+            1 => Some(Accidental::Sharp),
+            2 => Some(Accidental::DoubleSharp),
+            _ => None,
+        }
+    }
+}
+
+/// Displays an `Accidental` as written after a note name, a natural being empty
+impl fmt::Display for Accidental {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Accidental::DoubleFlat => "bb",
+            Accidental::Flat => "b",
+            Accidental::Natural => "",
+            Accidental::Sharp => "#",
+            Accidental::DoubleSharp => "##",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// Name of a pitch class, a natural `Step` and its `Accidental`
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy, Hash)]
+pub struct Spelling {
+    pub step: Step,
+    pub accidental: Accidental,
+}
+
+impl Spelling {
+    /// Construct Spelling from a `Step` and an `Accidental`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::common::{Accidental, Letter, Spelling, Step};
+    /// let e_sharp = Spelling::new(Step::E, Accidental::Sharp);
+    /// assert_eq!(e_sharp.letter(), Letter::F);
+    /// assert_eq!(e_sharp.to_string(), "E#");
+    /// ```
+    pub fn new(step: Step, accidental: Accidental) -> Self {
+        Spelling { step, accidental }
+    }
+
+    /// Signed semitones from the C of the same staff octave, -2 for Cbb up to 13 for B##
+    pub fn semitones(&self) -> i8 {
+        self.step.semitones() + self.accidental.semitones()
+    }
+
+    /// Sounding pitch class
+    pub fn letter(&self) -> Letter {
+        KEYBOARD[self.semitones().rem_euclid(12) as usize]
+    }
+}
+
+/// Default spelling of a `Letter`, flats being used for black keys
+impl From<Letter> for Spelling {
+    fn from(letter: Letter) -> Self {
+        let (step, accidental) = match letter {
+            Letter::C => (Step::C, Accidental::Natural),
+            Letter::Db => (Step::D, Accidental::Flat),
+            Letter::D => (Step::D, Accidental::Natural),
+            Letter::Eb => (Step::E, Accidental::Flat),
+            Letter::E => (Step::E, Accidental::Natural),
+            Letter::F => (Step::F, Accidental::Natural),
+            Letter::Gb => (Step::G, Accidental::Flat),
+            Letter::G => (Step::G, Accidental::Natural),
+            Letter::Ab => (Step::A, Accidental::Flat),
+            Letter::A => (Step::A, Accidental::Natural),
+            Letter::Bb => (Step::B, Accidental::Flat),
+            Letter::B => (Step::B, Accidental::Natural),
+        };
+        Spelling::new(step, accidental)
+    }
+}
+
+/// Displays a `Spelling` as a note name, like `F#` or `Bbb`
+impl fmt::Display for Spelling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}{}", self.step, self.accidental)
     }
 }
 
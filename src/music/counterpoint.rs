@@ -0,0 +1,381 @@
+//! Part writing rules checking of several voices
+
+use crate::music::chord::Chord;
+use crate::music::common::find_letter_idx;
+use crate::music::key::Key;
+use crate::music::note::Note;
+use crate::music::stream::Stream;
+use crate::music::time::Time;
+use itertools::Itertools;
+use std::fmt;
+
+/// Largest leap in semitones not needing a recovery, a perfect fifth
+const LARGEST_FREE_LEAP: i32 = 7;
+
+/// A part writing rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rule {
+    ParallelFifths,
+    ParallelOctaves,
+    /// Outer voices reaching a fifth in similar motion, the upper one leaping
+    HiddenFifths,
+    /// Outer voices reaching an octave in similar motion, the upper one leaping
+    HiddenOctaves,
+    VoiceCrossing,
+    /// A voice moving past the previous note of an adjacent voice
+    VoiceOverlap,
+    Range,
+    /// Leading tone of an outer voice not going up to the tonic
+    UnresolvedLeadingTone,
+    /// Chord seventh not going down by step
+    UnresolvedSeventh,
+    /// Leap larger than a fifth not followed by a step the other way
+    UnrecoveredLeap,
+}
+
+/// A rule broken by `voices`, numbered from the top, at `time`
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub rule: Rule,
+    pub time: Time,
+    pub voices: Vec<usize>,
+}
+
+/// Displays a `Violation` like `ParallelFifths in voices [0, 3] at bar 2 + 1/4`
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} in voices {:?} at bar {}",
+            self.rule, self.voices, self.time
+        )
+    }
+}
+
+/// A melodic line, notes lasting until the next one
+#[derive(Debug, Clone, Default)]
+pub struct Voice {
+    pub notes: Vec<(Time, Note)>,
+    /// Lowest and highest notes allowed
+    pub range: Option<(Note, Note)>,
+}
+
+impl Voice {
+    /// Creates a `Voice` from notes and their start time, in time order
+    pub fn new(notes: Vec<(Time, Note)>) -> Self {
+        Voice { notes, range: None }
+    }
+
+    /// Restricts the voice between `lowest` and `highest`
+    pub fn with_range(self, lowest: Note, highest: Note) -> Self {
+        Voice {
+            range: Some((lowest, highest)),
+            ..self
+        }
+    }
+
+    fn pitch(&self, index: usize) -> i32 {
+        self.notes[index].1.semitones()
+    }
+}
+
+/// A voice of one note per bar
+impl From<Vec<Note>> for Voice {
+    fn from(notes: Vec<Note>) -> Self {
+        let times = (1..).map(|bar| Time::new(bar, 1, 1));
+        Voice::new(times.zip(notes).collect())
+    }
+}
+
+/// A voice of the notes of a stream
+impl From<&Stream> for Voice {
+    fn from(stream: &Stream) -> Self {
+        Voice::new(stream.onsets())
+    }
+}
+
+/// Checks part writing rules on `voices`, ordered from the highest one.
+///
+/// Leading tones are only checked in a `key`. Violations are sorted by time.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mumuse::music::counterpoint::{check, Rule, Voice};
+/// use mumuse::music::note::Note;
+/// let voice = |notes: [&str; 2]| {
+///     Voice::from(notes.iter().map(|n| Note::try_from(*n).unwrap()).collect::<Vec<_>>())
+/// };
+/// let violations = check(&[voice(["A4", "B4"]), voice(["D4", "E4"])], None);
+/// assert_eq!(violations[0].rule, Rule::ParallelFifths);
+/// assert_eq!(violations[0].time.bar(), 2);
+/// ```
+pub fn check(voices: &[Voice], key: Option<&Key>) -> Vec<Violation> {
+    let mut violations = vec![];
+    let mut report = |rule, time, voices: &[usize]| {
+        violations.push(Violation {
+            rule,
+            time,
+            voices: voices.to_vec(),
+        })
+    };
+    let outer = |voice: usize| voice == 0 || voice + 1 == voices.len();
+
+    // Notes sounding in each voice at every note start
+    let times = voices
+        .iter()
+        .flat_map(|v| v.notes.iter().map(|(time, _)| *time))
+        .sorted()
+        .dedup()
+        .collect_vec();
+    let slices = times
+        .iter()
+        .map(|time| {
+            voices
+                .iter()
+                .map(|v| v.notes.iter().rposition(|(t, _)| t <= time))
+                .collect_vec()
+        })
+        .collect_vec();
+    let pitch =
+        |slice: &[Option<usize>], voice: usize| slice[voice].map(|i| voices[voice].pitch(i));
+
+    for (index, slice) in slices.iter().enumerate() {
+        let time = times[index];
+        let previous = index.checked_sub(1).map(|i| &slices[i]);
+        for (i, j) in (0..voices.len()).tuple_combinations() {
+            let (upper, lower) = match (pitch(slice, i), pitch(slice, j)) {
+                (Some(upper), Some(lower)) => (upper, lower),
+                _ => continue,
+            };
+            let adjacent = j == i + 1;
+            let crossed = |slice: &[Option<usize>]| match (pitch(slice, i), pitch(slice, j)) {
+                (Some(upper), Some(lower)) => upper < lower,
+                _ => false,
+            };
+            if adjacent && crossed(slice) && !previous.map_or(false, |p| crossed(p)) {
+                report(Rule::VoiceCrossing, time, &[i, j]);
+            }
+
+            let (upper_before, lower_before) = match previous.map(|p| (pitch(p, i), pitch(p, j))) {
+                Some((Some(upper), Some(lower))) => (upper, lower),
+                _ => continue,
+            };
+            let (upper_motion, lower_motion) = (upper - upper_before, lower - lower_before);
+            if upper_motion == 0 && lower_motion == 0 {
+                continue;
+            }
+            let similar = upper_motion.signum() * lower_motion.signum() == 1;
+            let (before, after) = (
+                (upper_before - lower_before).abs() % 12,
+                (upper - lower).abs() % 12,
+            );
+            let rule = |fifths, octaves| if after == 7 { fifths } else { octaves };
+            if similar && (after == 0 || after == 7) {
+                if before == after {
+                    report(
+                        rule(Rule::ParallelFifths, Rule::ParallelOctaves),
+                        time,
+                        &[i, j],
+                    );
+                } else if outer(i) && outer(j) && upper_motion.abs() > 2 {
+                    report(rule(Rule::HiddenFifths, Rule::HiddenOctaves), time, &[i, j]);
+                }
+            }
+            let overlapping = (upper_motion != 0 && upper < lower_before)
+                || (lower_motion != 0 && lower > upper_before);
+            if adjacent && overlapping {
+                report(Rule::VoiceOverlap, time, &[i, j]);
+            }
+        }
+    }
+
+    for (v, voice) in voices.iter().enumerate() {
+        for (k, (time, note)) in voice.notes.iter().enumerate() {
+            if let Some((lowest, highest)) = voice.range {
+                if note.semitones() < lowest.semitones() || note.semitones() > highest.semitones() {
+                    report(Rule::Range, *time, &[v]);
+                }
+            }
+            let next = match voice.notes.get(k + 1) {
+                Some(_) => voice.pitch(k + 1) - voice.pitch(k),
+                None => continue,
+            };
+
+            if let (Some(key), true) = (key, outer(v)) {
+                let tonic = find_letter_idx(key.tonic.letter()) as i32;
+                if note.semitones().rem_euclid(12) == (tonic + 11) % 12 && next != 1 {
+                    report(Rule::UnresolvedLeadingTone, *time, &[v]);
+                }
+            }
+
+            // Seventh of the chord sounding when the note starts
+            let slice = times.iter().position(|t| t == time);
+            let sounding = slice
+                .map(|s| {
+                    (0..voices.len())
+                        .filter_map(|u| slices[s][u].map(|i| voices[u].notes[i].1))
+                        .collect_vec()
+                })
+                .unwrap_or_default();
+            let triad = sounding.iter().map(|n| n.spelling()).unique().count() >= 3;
+            let chord = Chord::new(sounding).identify();
+            if let Some(symbol) = chord.first().filter(|_| triad) {
+                let root = find_letter_idx(symbol.root.letter()) as i32;
+                let seventh = symbol.intervals.iter().find(|i| i.number() == 7);
+                if let Some(seventh) = seventh {
+                    let seventh = (root + seventh.semitones()).rem_euclid(12);
+                    if note.semitones().rem_euclid(12) == seventh && !(-2..=-1).contains(&next) {
+                        report(Rule::UnresolvedSeventh, *time, &[v]);
+                    }
+                }
+            }
+
+            if next.abs() > LARGEST_FREE_LEAP {
+                let recovered = voice.notes.get(k + 2).map_or(true, |_| {
+                    let step = voice.pitch(k + 2) - voice.pitch(k + 1);
+                    (1..=2).contains(&step.abs()) && step.signum() != next.signum()
+                });
+                if !recovered {
+                    report(Rule::UnrecoveredLeap, voice.notes[k + 1].0, &[v]);
+                }
+            }
+        }
+    }
+
+    violations
+        .into_iter()
+        .sorted_by_key(|v| (v.time, v.rule))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voice(notes: &[&str]) -> Voice {
+        Voice::from(
+            notes
+                .iter()
+                .map(|n| Note::try_from(*n).unwrap())
+                .collect_vec(),
+        )
+    }
+
+    fn rules(voices: &[Voice], key: Option<&str>) -> Vec<(Rule, u32, Vec<usize>)> {
+        let key = key.map(|k| Key::try_from(k).unwrap());
+        check(voices, key.as_ref())
+            .into_iter()
+            .map(|v| (v.rule, v.time.bar(), v.voices))
+            .collect()
+    }
+
+    #[test]
+    fn clean_cadence() {
+        let voices = [
+            voice(&["C5", "B4", "C5"]),
+            voice(&["G4", "G4", "G4"]),
+            voice(&["E4", "D4", "E4"]),
+            voice(&["C3", "G3", "C3"]),
+        ];
+        assert!(rules(&voices, Some("C major")).is_empty());
+    }
+
+    #[test]
+    fn parallels() {
+        let voices = [
+            voice(&["C5", "D5"]),
+            voice(&["F4", "G4"]),
+            voice(&["C4", "D4"]),
+        ];
+        assert_eq!(
+            rules(&voices, None),
+            vec![
+                (Rule::ParallelFifths, 2, vec![0, 1]),
+                (Rule::ParallelOctaves, 2, vec![0, 2]),
+            ]
+        );
+        // Contrary motion to an octave is fine, similar motion with a leap is hidden
+        let voices = [voice(&["B4", "C5", "G5"]), voice(&["G3", "C3", "C4"])];
+        assert_eq!(
+            rules(&voices, None),
+            vec![(Rule::HiddenFifths, 3, vec![0, 1])]
+        );
+    }
+
+    #[test]
+    fn crossing_and_overlap() {
+        let voices = [voice(&["E4", "E4", "G4"]), voice(&["C4", "F4", "F4"])];
+        assert_eq!(
+            rules(&voices, None),
+            vec![
+                (Rule::VoiceCrossing, 2, vec![0, 1]),
+                (Rule::VoiceOverlap, 2, vec![0, 1]),
+            ]
+        );
+    }
+
+    #[test]
+    fn melodic_rules() {
+        let soprano = voice(&["B4", "A4", "F5", "G5"])
+            .with_range(Note::try_from("C4").unwrap(), Note::try_from("F5").unwrap());
+        assert_eq!(
+            rules(&[soprano], Some("C major")),
+            vec![
+                (Rule::UnresolvedLeadingTone, 1, vec![0]),
+                (Rule::UnrecoveredLeap, 3, vec![0]),
+                (Rule::Range, 4, vec![0]),
+            ]
+        );
+        // Leading tones of inner voices are free
+        let voices = [
+            voice(&["D5", "C5"]),
+            voice(&["B4", "G4"]),
+            voice(&["G3", "C4"]),
+        ];
+        assert!(rules(&voices, Some("C major")).is_empty());
+    }
+
+    #[test]
+    fn sevenths() {
+        let voices = [
+            voice(&["F5", "G5"]),
+            voice(&["D5", "C5"]),
+            voice(&["B4", "C5"]),
+            voice(&["G3", "C4"]),
+        ];
+        let violations = rules(&voices, None);
+        assert!(violations.contains(&(Rule::UnresolvedSeventh, 1, vec![0])));
+        let resolved = [
+            voice(&["F5", "E5"]),
+            voice(&["B4", "C5"]),
+            voice(&["G3", "C4"]),
+        ];
+        assert!(rules(&resolved, None).is_empty());
+    }
+
+    #[test]
+    fn stream_voices() {
+        let mut stream = Stream::new();
+        for (i, n) in ["C4", "E4", "D4"].iter().enumerate() {
+            let note = Note::try_from(*n).unwrap();
+            let duration = crate::music::duration::Duration::new(4, 1);
+            stream.add_note(note, Time::new(1, 4, i as u32 + 1), duration);
+        }
+        let voice = Voice::from(&stream);
+        assert_eq!(voice.notes.len(), 3);
+        assert_eq!(voice.notes[2].0, Time::new(1, 4, 3));
+        let violations = check(
+            &[voice, Voice::from(vec![Note::try_from("E4").unwrap()])],
+            None,
+        );
+        assert_eq!(violations[0].rule, Rule::VoiceCrossing);
+        assert_eq!(violations[0].time, Time::new(1, 4, 1));
+        assert_eq!(
+            violations[0].to_string(),
+            "VoiceCrossing in voices [0, 1] at bar 1"
+        );
+    }
+}
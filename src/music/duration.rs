@@ -1,6 +1,20 @@
 //! Time duration consisting in a number of a given bar divisions
 
-/// Time duration consisting in a number of a given bar divisions
+use num::rational::Ratio;
+use num::CheckedSub;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::{fmt, ops};
+
+/// Names of the note values from the whole note, halving each time
+const NOTE_VALUES: [&str; 8] = [
+    "whole", "half", "quarter", "eighth", "16th", "32nd", "64th", "128th",
+];
+
+/// Time duration consisting in a number of a given bar divisions, a bar lasting a whole note
+///
+/// Durations compare by length, whatever their divisions. `divisions` is never zero,
+/// constructors rejecting it.
 #[derive(Clone, Copy, Debug)]
 pub struct Duration {
     pub divisions: u32,
@@ -8,11 +22,23 @@ pub struct Duration {
 }
 
 impl Duration {
+    pub const WHOLE: Duration = Duration::new(1, 1);
+    pub const HALF: Duration = Duration::new(2, 1);
+    pub const QUARTER: Duration = Duration::new(4, 1);
+    pub const EIGHTH: Duration = Duration::new(8, 1);
+    pub const SIXTEENTH: Duration = Duration::new(16, 1);
+    pub const THIRTY_SECOND: Duration = Duration::new(32, 1);
+    pub const SIXTY_FOURTH: Duration = Duration::new(64, 1);
+
     /// Creates a `Duration` from `divisions` and `length`
     ///
-    /// Specify a duration by dividing a bar into N `divisions` and 
+    /// Specify a duration by dividing a bar into N `divisions` and
     /// by specifying a multiple of this 1/N division.
     ///
+    /// # Panics
+    ///
+    /// Panics if `divisions` is zero. See [`Duration::checked_new`] for a
+    /// non-panicking version.
     ///
     /// # Examples
     ///
@@ -22,14 +48,33 @@ impl Duration {
     /// use mumuse::music::duration::Duration;
     /// let duration: Duration = Duration::new(16, 1);
     /// ```
-    pub fn new(divisions: u32, length: u32) -> Self {
+    pub const fn new(divisions: u32, length: u32) -> Self {
+        assert!(divisions != 0, "duration without divisions");
         Duration { divisions, length }
     }
 
+    /// Creates a `Duration` from `divisions` and `length`, `None` if `divisions` is zero
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::duration::Duration;
+    /// assert_eq!(Duration::checked_new(8, 2), Some(Duration::QUARTER));
+    /// assert_eq!(Duration::checked_new(0, 1), None);
+    /// ```
+    pub const fn checked_new(divisions: u32, length: u32) -> Option<Self> {
+        if divisions == 0 {
+            return None;
+        }
+        Some(Duration { divisions, length })
+    }
+
     /// Convert duration into seconds
     ///
     /// In order to convert the duration into seconds, one need to declare a `bpm`
-    /// (beats per minutes) and a `bpb` (beats per bar).
+    /// (beats per minutes), a beat being a quarter note.
     ///
     /// # Examples
     ///
@@ -38,10 +83,391 @@ impl Duration {
     /// ```
     /// use mumuse::music::duration::Duration;
     /// let duration: Duration = Duration::new(16, 1);
-    /// let duration_seconds = duration.to_seconds(120.0, 4);
+    /// let duration_seconds = duration.to_seconds(120.0);
+    /// assert_eq!(duration_seconds, 0.125);
     /// ```
-    pub fn to_seconds(&self, bpm: f64, bpb: u32) -> f64 {
-        let bar_duration = (bpb as f64) * 60. / bpm;
-        self.length as f64 * bar_duration / self.divisions as f64
+    pub fn to_seconds(&self, bpm: f64) -> f64 {
+        self.length as f64 * 4. * 60. / bpm / self.divisions as f64
+    }
+
+    /// Same duration in the fewest divisions
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::duration::Duration;
+    /// let d = Duration::new(12, 9).reduce();
+    /// assert_eq!((d.divisions, d.length), (4, 3));
+    /// ```
+    pub fn reduce(&self) -> Self {
+        Duration::from_ratio(self.to_ratio())
+    }
+
+    /// Duration lengthened by a dot, half of it
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::duration::Duration;
+    /// assert_eq!(Duration::QUARTER.dotted(), Duration::new(8, 3));
+    /// ```
+    pub fn dotted(&self) -> Self {
+        *self * 3 / 2
+    }
+
+    /// Duration lengthened by two dots, three quarters of it
+    pub fn double_dotted(&self) -> Self {
+        *self * 7 / 4
+    }
+
+    /// Duration of a tuplet note, `n` of them lasting as long as `m` of `self`
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::duration::Duration;
+    /// let triplet = Duration::EIGHTH.tuplet(3, 2);
+    /// assert_eq!(triplet * 3, Duration::QUARTER);
+    /// let quintuplet = Duration::SIXTEENTH.tuplet(5, 4);
+    /// assert_eq!(quintuplet, Duration::new(20, 1));
+    /// ```
+    pub fn tuplet(&self, n: u32, m: u32) -> Self {
+        *self * m / n
+    }
+
+    /// Nearest multiple of `grid`, halves rounding up
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::duration::Duration;
+    /// let played = Duration::new(480, 131);
+    /// assert_eq!(played.quantize(Duration::SIXTEENTH), Duration::new(16, 4));
+    /// ```
+    pub fn quantize(&self, grid: Duration) -> Self {
+        let multiple = (self.to_ratio() / grid.to_ratio()).round();
+        Duration::from_ratio(multiple * grid.to_ratio())
+    }
+
+    /// Notatable values tied to last the duration, longest first.
+    ///
+    /// Whole notes are tied first, the rest taking dotted values when possible.
+    /// Durations that are not a sum of powers of two of a whole note are written
+    /// as tuplets of their odd divisions, like triplets for thirds.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::duration::Duration;
+    /// let values: Vec<String> = Duration::new(16, 27)
+    ///     .to_note_values()
+    ///     .iter()
+    ///     .map(|v| v.to_string())
+    ///     .collect();
+    /// assert_eq!(values, vec!["whole", "half", "dotted eighth"]);
+    /// let triplet = Duration::new(6, 1).to_note_values();
+    /// assert_eq!(triplet[0].to_string(), "quarter (3:2)");
+    /// ```
+    pub fn to_note_values(&self) -> Vec<NoteValue> {
+        let ratio = self.to_ratio();
+        // Odd factor of the divisions, notated as a tuplet against the power of two below it
+        let odd = ratio.denom() >> ratio.denom().trailing_zeros();
+        let tuplet = if odd > 1 {
+            let normal = 1 << (31 - odd.leading_zeros());
+            Some((odd, normal))
+        } else {
+            None
+        };
+        let written = match tuplet {
+            Some((n, m)) => ratio * Ratio::new(n, m),
+            None => ratio,
+        };
+
+        let mut values = vec![];
+        let mut remaining = written;
+        let whole = Ratio::from_integer(1);
+        while remaining >= whole {
+            values.push(NoteValue::new(Duration::WHOLE, 0, tuplet));
+            remaining -= whole;
+        }
+        while remaining > Ratio::from_integer(0) {
+            let base = (0..)
+                .map(|power| Ratio::new(1, 1 << power))
+                .find(|base| *base <= remaining)
+                .unwrap();
+            let dots = (0..=2)
+                .rev()
+                .find(|dots| base * Ratio::new((2 << dots) - 1, 1 << dots) <= remaining)
+                .unwrap();
+            let value = NoteValue::new(Duration::from_ratio(base), dots, tuplet);
+            remaining -= value.written().to_ratio();
+            values.push(value);
+        }
+        values
+    }
+}
+
+impl Duration {
+    /// Whole notes of the duration, in lowest terms
+    pub(crate) fn to_ratio(self) -> Ratio<u32> {
+        Ratio::new(self.length, self.divisions)
+    }
+
+    /// Duration of `whole_notes`, divisions being the fewest possible
+    pub(crate) fn from_ratio(whole_notes: Ratio<u32>) -> Self {
+        Duration::new(*whole_notes.denom(), *whole_notes.numer())
+    }
+}
+
+impl ops::Add<Duration> for Duration {
+    type Output = Duration;
+    /// Sum of two durations, in the fewest divisions
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::duration::Duration;
+    /// let d = Duration::new(4, 1) + Duration::new(8, 1); // Dotted quarter
+    /// assert_eq!((d.divisions, d.length), (8, 3));
+    /// ```
+    fn add(self, rhs: Duration) -> Duration {
+        Duration::from_ratio(self.to_ratio() + rhs.to_ratio())
+    }
+}
+
+impl ops::AddAssign<Duration> for Duration {
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
+
+impl ops::Sub<Duration> for Duration {
+    type Output = Duration;
+    /// Difference of two durations, in the fewest divisions
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rhs` is longer than `self`.
+    fn sub(self, rhs: Duration) -> Duration {
+        let whole_notes = self.to_ratio().checked_sub(&rhs.to_ratio());
+        Duration::from_ratio(whole_notes.expect("negative duration"))
+    }
+}
+
+impl ops::Mul<u32> for Duration {
+    type Output = Duration;
+    /// Duration scaled by `rhs`, in the fewest divisions
+    fn mul(self, rhs: u32) -> Duration {
+        Duration::from_ratio(self.to_ratio() * rhs)
+    }
+}
+
+impl ops::Div<u32> for Duration {
+    type Output = Duration;
+    /// Duration divided by `rhs`, in the fewest divisions
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::duration::Duration;
+    /// let d = Duration::new(4, 2) / 3; // Quarter note triplet
+    /// assert_eq!((d.divisions, d.length), (6, 1));
+    /// ```
+    fn div(self, rhs: u32) -> Duration {
+        Duration::from_ratio(self.to_ratio() / rhs)
+    }
+}
+
+impl PartialEq for Duration {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Duration {}
+
+impl PartialOrd for Duration {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Duration {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_ratio().cmp(&other.to_ratio())
+    }
+}
+
+impl Hash for Duration {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_ratio().hash(state);
+    }
+}
+
+/// Displays a `Duration` as a fraction of a whole note, like `3/8`
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_ratio())
+    }
+}
+
+/// A written note value, a power of two of a whole note, possibly dotted or in a tuplet
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NoteValue {
+    /// Undotted value, a whole note or shorter
+    pub base: Duration,
+    pub dots: u8,
+    /// Tuplet ratio `(n, m)`, n notes lasting as long as m
+    pub tuplet: Option<(u32, u32)>,
+}
+
+impl NoteValue {
+    /// Creates a `NoteValue`, `base` being a whole note or a power of two shorter
+    pub fn new(base: Duration, dots: u8, tuplet: Option<(u32, u32)>) -> Self {
+        NoteValue { base, dots, tuplet }
+    }
+
+    /// Duration of the value as written, its tuplet aside
+    fn written(&self) -> Duration {
+        let dots = self.dots as u32;
+        self.base * ((2 << dots) - 1) / (1 << dots)
+    }
+
+    /// Duration of the value, as played
+    pub fn duration(&self) -> Duration {
+        match self.tuplet {
+            Some((n, m)) => self.written().tuplet(n, m),
+            None => self.written(),
+        }
+    }
+}
+
+/// Displays a `NoteValue` like `dotted quarter` or `eighth (3:2)`
+impl fmt::Display for NoteValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.dots {
+            0 => (),
+            1 => write!(f, "dotted ")?,
+            2 => write!(f, "double dotted ")?,
+            dots => write!(f, "{} dots ", dots)?,
+        }
+        let power = self.base.reduce().divisions.trailing_zeros() as usize;
+        match NOTE_VALUES.get(power) {
+            Some(name) => write!(f, "{}", name)?,
+            None => write!(f, "{}", self.base)?,
+        }
+        if let Some((n, m)) = self.tuplet {
+            write!(f, " ({}:{})", n, m)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(duration: Duration) -> Vec<String> {
+        duration
+            .to_note_values()
+            .iter()
+            .map(|v| v.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(Duration::new(4, 2), Duration::HALF);
+        assert!(Duration::new(3, 1) > Duration::new(4, 1));
+        assert_eq!(
+            Duration::HALF - Duration::EIGHTH,
+            Duration::QUARTER.dotted()
+        );
+        assert_eq!(Duration::HALF.double_dotted(), Duration::new(8, 7));
+        let mut total = Duration::new(12, 1);
+        total += Duration::new(6, 1);
+        assert_eq!(total, Duration::QUARTER);
+        assert_eq!(total.to_string(), "1/4");
+        let mut sorted = [
+            Duration::WHOLE,
+            Duration::EIGHTH.tuplet(3, 2),
+            Duration::EIGHTH,
+        ];
+        sorted.sort();
+        assert_eq!(sorted[0], Duration::new(12, 1));
+        assert_eq!(
+            Duration::new(8, 3).quantize(Duration::QUARTER),
+            Duration::HALF
+        );
+        assert_eq!(
+            Duration::new(8, 1).quantize(Duration::QUARTER),
+            Duration::QUARTER
+        );
+    }
+
+    #[test]
+    fn without_divisions() {
+        use std::collections::HashSet;
+        assert_eq!(Duration::checked_new(0, 1), None);
+        assert_eq!(Duration::checked_new(0, 0), None);
+        assert!(Duration::WHOLE < Duration::new(1, u32::MAX));
+        assert_eq!(Duration::new(u32::MAX, u32::MAX), Duration::WHOLE);
+        let durations: HashSet<_> = [Duration::new(4, 1), Duration::new(8, 2)]
+            .iter()
+            .copied()
+            .collect();
+        assert_eq!(durations.len(), 1);
+        assert!(durations.contains(&Duration::QUARTER));
+        assert!(std::panic::catch_unwind(|| Duration::new(0, 1)).is_err());
+    }
+
+    #[test]
+    fn note_values() {
+        assert_eq!(names(Duration::QUARTER), vec!["quarter"]);
+        assert_eq!(names(Duration::new(8, 7)), vec!["double dotted half"]);
+        assert_eq!(names(Duration::new(8, 5)), vec!["half", "eighth"]);
+        assert_eq!(
+            names(Duration::new(4, 9)),
+            vec!["whole", "whole", "quarter"]
+        );
+        assert_eq!(
+            names(Duration::new(32, 15)),
+            vec!["double dotted quarter", "32nd"]
+        );
+        assert_eq!(names(Duration::new(12, 2)), vec!["quarter (3:2)"]);
+        assert_eq!(names(Duration::new(5, 2)), vec!["half (5:4)"]);
+        assert_eq!(names(Duration::new(20, 3)), vec!["dotted eighth (5:4)"]);
+        assert_eq!(names(Duration::new(7, 1)), vec!["quarter (7:4)"]);
+        assert_eq!(
+            names(Duration::new(24, 5)),
+            vec!["quarter (3:2)", "16th (3:2)"]
+        );
+        assert!(names(Duration::new(4, 0)).is_empty());
+        // Tied values always add up to the duration
+        for divisions in 1..100 {
+            for length in 1..40 {
+                let duration = Duration::new(divisions, length);
+                let values = duration.to_note_values();
+                let total = values
+                    .iter()
+                    .fold(Duration::new(1, 0), |total, v| total + v.duration());
+                assert_eq!(total, duration);
+                assert!(values.iter().all(|v| v.dots <= 2));
+            }
+        }
     }
 }
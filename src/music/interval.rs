@@ -0,0 +1,476 @@
+//! Intervals with quality and number
+
+use crate::error::{Error, Result};
+use std::{fmt, ops};
+
+/// Semitones of the major or perfect simple intervals, from unison to seventh
+const NATURAL_SEMITONES: [i32; 7] = [0, 2, 4, 5, 7, 9, 11];
+
+/// Quality of an interval, augmented and diminished ones possibly several times
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quality {
+    Perfect,
+    Major,
+    Minor,
+    Augmented(u8),
+    Diminished(u8),
+}
+
+impl Quality {
+    /// Quality of the inverted interval
+    pub fn invert(&self) -> Self {
+        match self {
+            Quality::Perfect => Quality::Perfect,
+            Quality::Major => Quality::Minor,
+            Quality::Minor => Quality::Major,
+            Quality::Augmented(n) => Quality::Diminished(*n),
+            Quality::Diminished(n) => Quality::Augmented(*n),
+        }
+    }
+}
+
+/// Displays a `Quality` as its abbreviation, `P`, `M`, `m`, `A` or `d`
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Quality::Perfect => write!(f, "P"),
+            Quality::Major => write!(f, "M"),
+            Quality::Minor => write!(f, "m"),
+            Quality::Augmented(n) => write!(f, "{}", "A".repeat(*n as usize)),
+            Quality::Diminished(n) => write!(f, "{}", "d".repeat(*n as usize)),
+        }
+    }
+}
+
+/// An interval between two notes, a `Quality` and a generic number (1 for unison, 8 for octave)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interval {
+    quality: Quality,
+    number: u8,
+}
+
+impl Default for Interval {
+    fn default() -> Self {
+        Interval::UNISON
+    }
+}
+
+impl Interval {
+    pub const UNISON: Interval = Interval::constant(Quality::Perfect, 1);
+    pub const AUGMENTED_UNISON: Interval = Interval::constant(Quality::Augmented(1), 1);
+    pub const MINOR_SECOND: Interval = Interval::constant(Quality::Minor, 2);
+    pub const MAJOR_SECOND: Interval = Interval::constant(Quality::Major, 2);
+    pub const AUGMENTED_SECOND: Interval = Interval::constant(Quality::Augmented(1), 2);
+    pub const DIMINISHED_THIRD: Interval = Interval::constant(Quality::Diminished(1), 3);
+    pub const MINOR_THIRD: Interval = Interval::constant(Quality::Minor, 3);
+    pub const MAJOR_THIRD: Interval = Interval::constant(Quality::Major, 3);
+    pub const DIMINISHED_FOURTH: Interval = Interval::constant(Quality::Diminished(1), 4);
+    pub const FOURTH: Interval = Interval::constant(Quality::Perfect, 4);
+    pub const AUGMENTED_FOURTH: Interval = Interval::constant(Quality::Augmented(1), 4);
+    pub const DIMINISHED_FIFTH: Interval = Interval::constant(Quality::Diminished(1), 5);
+    pub const FIFTH: Interval = Interval::constant(Quality::Perfect, 5);
+    pub const AUGMENTED_FIFTH: Interval = Interval::constant(Quality::Augmented(1), 5);
+    pub const MINOR_SIXTH: Interval = Interval::constant(Quality::Minor, 6);
+    pub const MAJOR_SIXTH: Interval = Interval::constant(Quality::Major, 6);
+    pub const AUGMENTED_SIXTH: Interval = Interval::constant(Quality::Augmented(1), 6);
+    pub const DIMINISHED_SEVENTH: Interval = Interval::constant(Quality::Diminished(1), 7);
+    pub const MINOR_SEVENTH: Interval = Interval::constant(Quality::Minor, 7);
+    pub const MAJOR_SEVENTH: Interval = Interval::constant(Quality::Major, 7);
+    pub const OCTAVE: Interval = Interval::constant(Quality::Perfect, 8);
+    pub const MINOR_NINTH: Interval = Interval::constant(Quality::Minor, 9);
+    pub const MAJOR_NINTH: Interval = Interval::constant(Quality::Major, 9);
+    pub const AUGMENTED_NINTH: Interval = Interval::constant(Quality::Augmented(1), 9);
+    pub const MINOR_TENTH: Interval = Interval::constant(Quality::Minor, 10);
+    pub const MAJOR_TENTH: Interval = Interval::constant(Quality::Major, 10);
+    pub const ELEVENTH: Interval = Interval::constant(Quality::Perfect, 11);
+    pub const AUGMENTED_ELEVENTH: Interval = Interval::constant(Quality::Augmented(1), 11);
+    pub const TWELFTH: Interval = Interval::constant(Quality::Perfect, 12);
+    pub const MINOR_THIRTEENTH: Interval = Interval::constant(Quality::Minor, 13);
+    pub const MAJOR_THIRTEENTH: Interval = Interval::constant(Quality::Major, 13);
+    pub const FIFTEENTH: Interval = Interval::constant(Quality::Perfect, 15);
+
+    const fn constant(quality: Quality, number: u8) -> Self {
+        Interval { quality, number }
+    }
+
+    /// Construct Interval from a `Quality` and a generic number.
+    ///
+    /// Unisons, fourths, fifths and their compounds are perfect, the others major or minor,
+    /// any other combination being an `Error::InvalidInterval`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::interval::{Interval, Quality};
+    /// let minor_third = Interval::new(Quality::Minor, 3).unwrap();
+    /// assert_eq!(minor_third, Interval::MINOR_THIRD);
+    /// assert!(Interval::new(Quality::Perfect, 3).is_err());
+    /// ```
+    pub fn new(quality: Quality, number: u8) -> Result<Self> {
+        let perfect = Interval::is_perfect_number(number);
+        let valid = number > 0
+            && match quality {
+                Quality::Perfect => perfect,
+                Quality::Major | Quality::Minor => !perfect,
+                Quality::Augmented(n) | Quality::Diminished(n) => n > 0,
+            };
+        if valid {
+            Ok(Interval { quality, number })
+        } else {
+            Err(Error::InvalidInterval(format!("{}{}", quality, number)))
+        }
+    }
+
+    /// Interval spanning `steps` staff steps and `semitones`, its quality following from both.
+    ///
+    /// An error is returned if the number or the augmentation of the interval exceed 255.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::interval::Interval;
+    /// assert_eq!(Interval::from_size(3, 6).unwrap(), Interval::AUGMENTED_FOURTH);
+    /// assert_eq!(Interval::from_size(4, 6).unwrap(), Interval::DIMINISHED_FIFTH);
+    /// assert!(Interval::from_size(255, 425).is_err());
+    /// ```
+    pub fn from_size(steps: u8, semitones: i32) -> Result<Self> {
+        let invalid =
+            || Error::InvalidInterval(format!("of {} steps and {} semitones", steps, semitones));
+        let number = steps.checked_add(1).ok_or_else(invalid)?;
+        let natural = NATURAL_SEMITONES[steps as usize % 7] + 12 * (steps as usize / 7) as i32;
+        let deviation = semitones as i64 - natural as i64;
+        let degree = |d: i64| u8::try_from(d).map_err(|_| invalid());
+        let quality = if Interval::is_perfect_number(number) {
+            match deviation {
+                0 => Quality::Perfect,
+                d if d > 0 => Quality::Augmented(degree(d)?),
+                d => Quality::Diminished(degree(-d)?),
+            }
+        } else {
+            match deviation {
+                0 => Quality::Major,
+                -1 => Quality::Minor,
+                d if d > 0 => Quality::Augmented(degree(d)?),
+                d => Quality::Diminished(degree(-d - 1)?),
+            }
+        };
+        Ok(Interval { quality, number })
+    }
+
+    /// Usual name of an interval of `semitones`, the tritone being a diminished fifth.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::interval::Interval;
+    /// assert_eq!(Interval::from_semitones(7), Interval::FIFTH);
+    /// assert_eq!(Interval::from_semitones(15).to_string(), "m10");
+    /// ```
+    pub fn from_semitones(semitones: u8) -> Self {
+        const STEPS: [u8; 12] = [0, 1, 1, 2, 2, 3, 4, 4, 5, 5, 6, 6];
+        let steps = STEPS[semitones as usize % 12] + 7 * (semitones / 12);
+        // Fewer than 255 steps, of a major, minor, perfect or diminished interval
+        Interval::from_size(steps, semitones as i32).unwrap()
+    }
+
+    /// Major or perfect interval of a non zero generic `number`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::interval::Interval;
+    /// assert_eq!(Interval::natural(13), Interval::MAJOR_THIRTEENTH);
+    /// assert_eq!(Interval::natural(11), Interval::ELEVENTH);
+    /// ```
+    pub fn natural(number: u8) -> Self {
+        let number = number.max(1);
+        let quality = if Interval::is_perfect_number(number) {
+            Quality::Perfect
+        } else {
+            Quality::Major
+        };
+        Interval { quality, number }
+    }
+
+    /// Same interval number, widened by `semitones` or narrowed if negative.
+    ///
+    /// An error is returned if the interval would be augmented or diminished more than 255 times.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::interval::Interval;
+    /// assert_eq!(Interval::FIFTH.altered(1).unwrap(), Interval::AUGMENTED_FIFTH);
+    /// assert_eq!(Interval::MAJOR_NINTH.altered(-1).unwrap(), Interval::MINOR_NINTH);
+    /// ```
+    pub fn altered(&self, semitones: i32) -> Result<Self> {
+        let semitones = self.semitones().saturating_add(semitones);
+        Interval::from_size(self.steps(), semitones)
+    }
+
+    /// Quality of the interval
+    pub fn quality(&self) -> Quality {
+        self.quality
+    }
+
+    /// Generic number of the interval, 1 for an unison
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// Number of staff steps spanned, 0 for an unison
+    pub fn steps(&self) -> u8 {
+        self.number - 1
+    }
+
+    /// Size of the interval in semitones, negative for some diminished unisons.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::interval::Interval;
+    /// assert_eq!(Interval::MAJOR_THIRTEENTH.semitones(), 21);
+    /// ```
+    pub fn semitones(&self) -> i32 {
+        let steps = self.steps() as usize;
+        let natural = NATURAL_SEMITONES[steps % 7] + 12 * (steps / 7) as i32;
+        natural
+            + match self.quality {
+                Quality::Perfect | Quality::Major => 0,
+                Quality::Minor => -1,
+                Quality::Augmented(n) => n as i32,
+                Quality::Diminished(n) if Interval::is_perfect_number(self.number) => -(n as i32),
+                Quality::Diminished(n) => -(n as i32) - 1,
+            }
+    }
+
+    /// True if the interval spans more than an octave
+    pub fn is_compound(&self) -> bool {
+        self.number > 8
+    }
+
+    /// Interval reduced to an octave at most, an octave staying an octave.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::interval::Interval;
+    /// assert_eq!(Interval::MINOR_NINTH.simple(), Interval::MINOR_SECOND);
+    /// assert_eq!(Interval::FIFTEENTH.simple(), Interval::OCTAVE);
+    /// ```
+    pub fn simple(&self) -> Self {
+        let number = if self.number > 8 {
+            (self.number - 2) % 7 + 2
+        } else {
+            self.number
+        };
+        Interval { number, ..*self }
+    }
+
+    /// Interval raised by `octaves`, an error being returned if its number exceeds 255.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::interval::Interval;
+    /// assert_eq!(Interval::MAJOR_SECOND.compound(1).unwrap(), Interval::MAJOR_NINTH);
+    /// assert!(Interval::try_from("A255").unwrap().compound(1).is_err());
+    /// ```
+    pub fn compound(&self, octaves: u8) -> Result<Self> {
+        let number = octaves
+            .checked_mul(7)
+            .and_then(|steps| self.number.checked_add(steps))
+            .ok_or_else(|| {
+                Error::InvalidInterval(format!("{} raised by {} octaves", self, octaves))
+            })?;
+        Ok(Interval { number, ..*self })
+    }
+
+    /// Inversion of the simple interval, so that both add up to an octave.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::interval::Interval;
+    /// assert_eq!(Interval::MAJOR_THIRD.invert(), Interval::MINOR_SIXTH);
+    /// assert_eq!(Interval::AUGMENTED_FOURTH.invert(), Interval::DIMINISHED_FIFTH);
+    /// assert_eq!(Interval::MAJOR_TENTH.invert(), Interval::MINOR_SIXTH);
+    /// ```
+    pub fn invert(&self) -> Self {
+        let simple = self.simple();
+        Interval {
+            quality: simple.quality.invert(),
+            number: 9 - simple.number,
+        }
+    }
+
+    fn is_perfect_number(number: u8) -> bool {
+        matches!(number.saturating_sub(1) % 7, 0 | 3 | 4)
+    }
+}
+
+/// Displays an `Interval` as its quality abbreviation and number, like `m3` or `P5`
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.quality, self.number)
+    }
+}
+
+/// Conversion from `&str`.
+///
+/// A quality abbreviation (`P`, `M`, `m`, one or more `A` or `d`) followed by the number.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mumuse::music::interval::Interval;
+/// let tritone = Interval::try_from("A4").unwrap();
+/// assert_eq!(tritone.semitones(), 6);
+/// assert!(Interval::try_from("M5").is_err());
+/// ```
+impl TryFrom<&str> for Interval {
+    type Error = Error;
+    fn try_from(s: &str) -> Result<Self> {
+        let number_start = s
+            .find(|c: char| c.is_ascii_digit())
+            .ok_or_else(|| Error::parse(s, s.len(), "interval number"))?;
+        let (quality, number) = s.split_at(number_start);
+        let count =
+            u8::try_from(quality.len()).map_err(|_| Error::parse(s, 0, "interval quality"))?;
+        let quality = match quality {
+            "P" => Quality::Perfect,
+            "M" => Quality::Major,
+            "m" => Quality::Minor,
+            q if !q.is_empty() && q.chars().all(|c| c == 'A') => Quality::Augmented(count),
+            q if !q.is_empty() && q.chars().all(|c| c == 'd') => Quality::Diminished(count),
+            _ => return Err(Error::parse(s, 0, "interval quality")),
+        };
+        let number = number
+            .parse()
+            .map_err(|_| Error::parse(s, number_start, "interval number"))?;
+        Interval::new(quality, number)
+    }
+}
+
+/// Overload operator + for Interval + Interval
+impl ops::Add for Interval {
+    type Output = Interval;
+    /// Stacks two intervals
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number or the augmentation of the sum exceed 255.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::interval::Interval;
+    /// let seventh = Interval::MAJOR_THIRD + Interval::FIFTH;
+    /// assert_eq!(seventh, Interval::MAJOR_SEVENTH);
+    /// ```
+    fn add(self, rhs: Interval) -> Interval {
+        self.steps()
+            .checked_add(rhs.steps())
+            .and_then(|steps| Interval::from_size(steps, self.semitones() + rhs.semitones()).ok())
+            .expect("interval out of range")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn semitones_round_trip() {
+        for semitones in 0..48 {
+            let interval = Interval::from_semitones(semitones);
+            assert_eq!(interval.semitones(), semitones as i32);
+            assert_eq!(
+                Interval::try_from(&interval.to_string()[..]).unwrap(),
+                interval
+            );
+        }
+        for steps in 0..21 {
+            for semitones in -3..40 {
+                let interval = Interval::from_size(steps, semitones).unwrap();
+                assert_eq!((interval.steps(), interval.semitones()), (steps, semitones));
+            }
+        }
+    }
+
+    #[test]
+    fn inversion() {
+        for semitones in 0..=12 {
+            let interval = Interval::from_semitones(semitones);
+            let inverted = interval.invert();
+            assert_eq!(interval.semitones() + inverted.semitones(), 12);
+            assert_eq!(interval.number() + inverted.number(), 9);
+            assert_eq!(inverted.invert(), interval);
+        }
+        assert_eq!(
+            Interval::try_from("dd7").unwrap().invert().to_string(),
+            "AA2"
+        );
+    }
+
+    #[test]
+    fn compound() {
+        assert!(Interval::MAJOR_NINTH.is_compound());
+        assert!(!Interval::OCTAVE.is_compound());
+        assert_eq!(
+            Interval::MAJOR_SECOND.compound(1).unwrap(),
+            Interval::MAJOR_NINTH
+        );
+        assert_eq!(
+            Interval::AUGMENTED_ELEVENTH.simple(),
+            Interval::AUGMENTED_FOURTH
+        );
+        assert_eq!(Interval::FIFTH.compound(2).unwrap().semitones(), 31);
+        assert!(Interval::natural(200).compound(8).is_err());
+        assert!(Interval::FIFTH.compound(255).is_err());
+        assert_eq!(Interval::OCTAVE + Interval::OCTAVE, Interval::FIFTEENTH);
+    }
+
+    #[test]
+    fn parse_errors() {
+        for (s, position) in [("", 0), ("P", 1), ("X3", 0), ("Ad4", 0), ("m", 1)] {
+            match Interval::try_from(s) {
+                Err(Error::Parse { position: p, .. }) => assert_eq!(p, position, "{}", s),
+                other => panic!("{:?} parsed as {:?}", s, other),
+            }
+        }
+        let augmented = "A".repeat(300) + "4";
+        assert!(matches!(
+            Interval::try_from(augmented.as_str()),
+            Err(Error::Parse { position: 0, .. })
+        ));
+        for s in ["P3", "m4", "M0", "P0"] {
+            assert!(matches!(
+                Interval::try_from(s),
+                Err(Error::InvalidInterval(_))
+            ));
+        }
+    }
+}
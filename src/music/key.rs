@@ -0,0 +1,268 @@
+//! Tonal keys and their estimation from pitch class profiles
+
+use crate::error::{Error, Result};
+use crate::music::chord::Chord;
+use crate::music::chord_symbol::parse_spelling;
+use crate::music::common::Spelling;
+use crate::music::note::Note;
+use crate::music::roman::{self, RomanNumeral};
+use crate::music::scale::Scale;
+use itertools::Itertools;
+use std::fmt;
+
+/// Usual spelling of the major keys tonics, from C
+const MAJOR_TONICS: [&str; 12] = [
+    "C", "Db", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B",
+];
+/// Usual spelling of the minor keys tonics, from C
+const MINOR_TONICS: [&str; 12] = [
+    "C", "C#", "D", "Eb", "E", "F", "F#", "G", "G#", "A", "Bb", "B",
+];
+
+/// Mode of a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Major,
+    Minor,
+}
+
+/// A key, a tonic and a mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub tonic: Spelling,
+    pub mode: Mode,
+}
+
+/// Key profiles, weights of the pitch classes from the tonic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyProfile {
+    /// Probe tone ratings of Krumhansl and Kessler (1982)
+    KrumhanslKessler,
+    /// Frequencies of the Kostka-Payne corpus, after Temperley (2007)
+    Temperley,
+}
+
+impl KeyProfile {
+    /// Weights of the twelve pitch classes from the tonic in `mode`
+    pub fn weights(&self, mode: Mode) -> [f64; 12] {
+        match (self, mode) {
+            (KeyProfile::KrumhanslKessler, Mode::Major) => [
+                6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+            ],
+            (KeyProfile::KrumhanslKessler, Mode::Minor) => [
+                6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+            ],
+            (KeyProfile::Temperley, Mode::Major) => [
+                0.748, 0.060, 0.488, 0.082, 0.670, 0.460, 0.096, 0.715, 0.104, 0.366, 0.057, 0.400,
+            ],
+            (KeyProfile::Temperley, Mode::Minor) => [
+                0.712, 0.084, 0.474, 0.618, 0.049, 0.460, 0.105, 0.747, 0.404, 0.067, 0.133, 0.330,
+            ],
+        }
+    }
+}
+
+/// A key estimated from a pitch class distribution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyEstimate {
+    pub key: Key,
+    /// Correlation between the distribution and the key profile, from -1 to 1
+    pub confidence: f64,
+}
+
+impl Key {
+    /// Creates a `Key` from its tonic and mode
+    pub fn new(tonic: Spelling, mode: Mode) -> Self {
+        Key { tonic, mode }
+    }
+
+    /// Key on the `pitch_class` (0 for C) with its usual spelling, F# major and Eb minor
+    pub fn from_pitch_class(pitch_class: usize, mode: Mode) -> Self {
+        let tonics = match mode {
+            Mode::Major => MAJOR_TONICS,
+            Mode::Minor => MINOR_TONICS,
+        };
+        let (tonic, _) = parse_spelling(tonics[pitch_class % 12]).unwrap();
+        Key::new(tonic, mode)
+    }
+
+    /// Scale of the key, natural minor for minor keys, with its tonic in `octave`
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::key::Key;
+    /// let key = Key::try_from("F# minor").unwrap();
+    /// assert_eq!(key.scale(4).notes()[6].to_string(), "Note(E5)");
+    /// ```
+    pub fn scale(&self, octave: i8) -> Scale {
+        let tonic = Note::spelled(self.tonic, octave);
+        match self.mode {
+            Mode::Major => Scale::major(tonic),
+            Mode::Minor => Scale::minor(tonic),
+        }
+    }
+
+    /// Roman numeral of `chord` in the key, `None` if it is not a known harmony.
+    ///
+    /// Diatonic numerals are preferred, then secondary dominants and leading tone chords,
+    /// then augmented sixths and chromatic numerals like borrowed chords.
+    /// Notes spelling settles enharmonic ties, a German sixth from a flat sixth chord.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::chord::Chord;
+    /// use mumuse::music::key::Key;
+    /// let key = Key::try_from("C major").unwrap();
+    /// let chord = Chord::from(vec!["F#3", "A3", "C4", "D4"]);
+    /// assert_eq!(key.analyze(&chord).unwrap().to_string(), "V6/5/V");
+    /// ```
+    pub fn analyze(&self, chord: &Chord) -> Option<RomanNumeral> {
+        roman::analyze(self, chord)
+    }
+
+    /// Every key ranked by correlation of its `profile` with the pitch class `weights`.
+    ///
+    /// Weights are usually the durations of the pitch classes, from C to B.
+    /// Nothing is ranked for a flat distribution, silence for instance, or for
+    /// weights that are not finite.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::key::{Key, KeyProfile};
+    /// // A minor triad and its leading tone
+    /// let weights = [1., 0., 0., 0., 2., 0., 0., 0., 1., 2., 0., 0.];
+    /// let keys = Key::rank(&weights, KeyProfile::KrumhanslKessler);
+    /// assert_eq!(keys[0].key.to_string(), "A minor");
+    /// ```
+    pub fn rank(weights: &[f64; 12], profile: KeyProfile) -> Vec<KeyEstimate> {
+        [Mode::Major, Mode::Minor]
+            .iter()
+            .flat_map(|mode| {
+                let profile = profile.weights(*mode);
+                (0..12).filter_map(move |tonic| {
+                    let rotated = (0..12).map(|pc| profile[(pc + 12 - tonic) % 12]);
+                    let confidence = correlation(weights.iter().copied(), rotated)?;
+                    Some(KeyEstimate {
+                        key: Key::from_pitch_class(tonic, *mode),
+                        confidence,
+                    })
+                })
+            })
+            .sorted_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap())
+            .collect()
+    }
+
+    /// Key best correlated with the pitch class `weights`, see `Key::rank`
+    pub fn estimate(weights: &[f64; 12], profile: KeyProfile) -> Option<KeyEstimate> {
+        Key::rank(weights, profile).into_iter().next()
+    }
+}
+
+/// Pearson correlation of two series, `None` if one of them is constant or not finite
+fn correlation(x: impl Iterator<Item = f64>, y: impl Iterator<Item = f64>) -> Option<f64> {
+    let (x, y): (Vec<f64>, Vec<f64>) = x.zip(y).unzip();
+    let n = x.len() as f64;
+    let (mean_x, mean_y) = (x.iter().sum::<f64>() / n, y.iter().sum::<f64>() / n);
+    let (mut covariance, mut var_x, mut var_y) = (0., 0., 0.);
+    for (a, b) in x.iter().zip(&y) {
+        covariance += (a - mean_x) * (b - mean_y);
+        var_x += (a - mean_x).powi(2);
+        var_y += (b - mean_y).powi(2);
+    }
+    if var_x < f64::EPSILON || var_y < f64::EPSILON {
+        return None;
+    }
+    Some(covariance / (var_x * var_y).sqrt()).filter(|r| r.is_finite())
+}
+
+/// Displays a `Key` as `Eb major` or `C# minor`
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self.mode {
+            Mode::Major => "major",
+            Mode::Minor => "minor",
+        };
+        write!(f, "{} {}", self.tonic, mode)
+    }
+}
+
+/// Parses keys like `Eb major`, `C# minor` or the shorter `Eb` and `C#m`
+impl TryFrom<&str> for Key {
+    type Error = Error;
+    fn try_from(s: &str) -> Result<Self> {
+        let (tonic, len) = parse_spelling(s).ok_or_else(|| Error::parse(s, 0, "tonic"))?;
+        let mode = match s[len..].trim() {
+            "" | "major" | "maj" => Mode::Major,
+            "m" | "minor" | "min" => Mode::Minor,
+            _ => return Err(Error::parse(s, len, "mode")),
+        };
+        Ok(Key::new(tonic, mode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pitch class weights of notes, from their names
+    fn weights(notes: &[&str]) -> [f64; 12] {
+        let mut weights = [0.; 12];
+        for note in notes {
+            let note = Note::try_from(*note).unwrap();
+            weights[crate::music::common::find_letter_idx(note.letter) as usize] += 1.;
+        }
+        weights
+    }
+
+    #[test]
+    fn estimation() {
+        let c_major = weights(&["C4", "D4", "E4", "F4", "G4", "A4", "B4", "C5", "E5", "G5"]);
+        for profile in [KeyProfile::KrumhanslKessler, KeyProfile::Temperley] {
+            let estimate = Key::estimate(&c_major, profile).unwrap();
+            assert_eq!(estimate.key.to_string(), "C major");
+            assert!(estimate.confidence > 0.8);
+        }
+        let g_sharp_minor = weights(&["G#3", "B3", "D#4", "G#4", "F##4", "D#4", "C#4", "B3"]);
+        let keys = Key::rank(&g_sharp_minor, KeyProfile::Temperley);
+        assert_eq!(keys[0].key.to_string(), "G# minor");
+        assert_eq!(keys.len(), 24);
+        assert!(keys.windows(2).all(|k| k[0].confidence >= k[1].confidence));
+        assert!(Key::estimate(&[0.; 12], KeyProfile::Temperley).is_none());
+        let mut corrupted = c_major;
+        corrupted[3] = f64::NAN;
+        assert!(Key::rank(&corrupted, KeyProfile::Temperley).is_empty());
+        corrupted[3] = f64::INFINITY;
+        assert!(Key::estimate(&corrupted, KeyProfile::KrumhanslKessler).is_none());
+    }
+
+    #[test]
+    fn parsing() {
+        let key = |s| Key::try_from(s).unwrap().to_string();
+        assert_eq!(key("Eb major"), "Eb major");
+        assert_eq!(key("C#m"), "C# minor");
+        assert_eq!(key("F"), "F major");
+        assert!(Key::try_from("H minor").is_err());
+        assert!(Key::try_from("D dorian").is_err());
+        assert_eq!(
+            Key::from_pitch_class(6, Mode::Major).to_string(),
+            "F# major"
+        );
+        assert_eq!(
+            Key::from_pitch_class(8, Mode::Minor).to_string(),
+            "G# minor"
+        );
+        assert_eq!(
+            Key::from_pitch_class(3, Mode::Minor).to_string(),
+            "Eb minor"
+        );
+    }
+}
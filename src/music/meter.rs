@@ -0,0 +1,439 @@
+//! Time signatures and their changes along a piece
+
+use crate::error::{Error, Result};
+use crate::music::duration::Duration;
+use crate::music::time::Time;
+use num::rational::Ratio;
+use std::fmt;
+
+/// A time signature, beats grouping units of the denominator note value
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TimeSignature {
+    numerator: u8,
+    denominator: u8,
+    grouping: Vec<u8>,
+}
+
+impl TimeSignature {
+    /// Creates a `TimeSignature` with its usual beats.
+    ///
+    /// Compound meters like 6/8 have beats of three units, other numerators over eighths
+    /// or shorter being grouped by two, a last group of three closing odd ones like 7/8.
+    /// Any other meter has a beat per unit.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::meter::TimeSignature;
+    /// assert_eq!(TimeSignature::new(6, 8).unwrap().grouping(), &[3, 3]);
+    /// assert_eq!(TimeSignature::new(7, 8).unwrap().grouping(), &[2, 2, 3]);
+    /// assert_eq!(TimeSignature::new(5, 4).unwrap().grouping(), &[1, 1, 1, 1, 1]);
+    /// assert!(TimeSignature::new(3, 6).is_err());
+    /// ```
+    pub fn new(numerator: u8, denominator: u8) -> Result<Self> {
+        let grouping = if denominator < 8 || numerator <= 3 {
+            vec![1; numerator as usize]
+        } else if numerator % 3 == 0 {
+            vec![3; numerator as usize / 3]
+        } else {
+            let mut groups = vec![2; numerator as usize / 2];
+            if numerator % 2 == 1 {
+                *groups.last_mut().unwrap() = 3;
+            }
+            groups
+        };
+        TimeSignature::grouped(&grouping, denominator)
+    }
+
+    /// Creates a `TimeSignature` from its beats, each one a number of units.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::meter::TimeSignature;
+    /// let signature = TimeSignature::grouped(&[3, 2, 2], 8).unwrap();
+    /// assert_eq!(signature.numerator(), 7);
+    /// assert_eq!(signature.to_string(), "3+2+2/8");
+    /// ```
+    pub fn grouped(grouping: &[u8], denominator: u8) -> Result<Self> {
+        let numerator = grouping.iter().map(|g| *g as u32).sum::<u32>();
+        let signature = format!("{}/{}", numerator, denominator);
+        if grouping.is_empty() || grouping.contains(&0) || !denominator.is_power_of_two() {
+            return Err(Error::InvalidTimeSignature(signature));
+        }
+        let numerator =
+            u8::try_from(numerator).map_err(|_| Error::InvalidTimeSignature(signature))?;
+        Ok(TimeSignature {
+            numerator,
+            denominator,
+            grouping: grouping.to_vec(),
+        })
+    }
+
+    pub fn numerator(&self) -> u8 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> u8 {
+        self.denominator
+    }
+
+    /// Units of the denominator note value in each beat
+    pub fn grouping(&self) -> &[u8] {
+        &self.grouping
+    }
+
+    /// Number of beats in a bar
+    pub fn beats(&self) -> usize {
+        self.grouping.len()
+    }
+
+    /// Length of a bar
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::meter::TimeSignature;
+    /// let bar = TimeSignature::new(6, 8).unwrap().bar_length();
+    /// assert_eq!((bar.divisions, bar.length), (8, 6));
+    /// ```
+    pub fn bar_length(&self) -> Duration {
+        Duration::new(self.denominator as u32, self.numerator as u32)
+    }
+
+    /// Start of the `beat` (from 0) in whole notes from the start of the bar
+    fn beat_offset(&self, beat: usize) -> Ratio<u32> {
+        let units = self.grouping[..beat].iter().map(|g| *g as u32).sum::<u32>();
+        Ratio::new(units, self.denominator as u32)
+    }
+
+    /// Strength of the `beat` (from 0), the middle beat of even bars of four beats or more
+    /// being strong
+    fn beat_strength(&self, beat: usize) -> Strength {
+        let beats = self.beats();
+        if beat == 0 {
+            Strength::Downbeat
+        } else if beats >= 4 && beats % 2 == 0 && beat == beats / 2 {
+            Strength::Strong
+        } else {
+            Strength::Weak
+        }
+    }
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        TimeSignature::new(4, 4).unwrap()
+    }
+}
+
+/// Displays a `TimeSignature` as `6/8`, or with its beats like `3+2+2/8` if unusual
+impl fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let usual = TimeSignature::new(self.numerator, self.denominator);
+        if usual.map_or(false, |usual| usual.grouping == self.grouping) {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        } else {
+            let grouping: Vec<String> = self.grouping.iter().map(|g| g.to_string()).collect();
+            write!(f, "{}/{}", grouping.join("+"), self.denominator)
+        }
+    }
+}
+
+/// Parses time signatures like `6/8` or `2+2+3/8`
+impl TryFrom<&str> for TimeSignature {
+    type Error = Error;
+    fn try_from(s: &str) -> Result<Self> {
+        let slash = s.find('/').ok_or_else(|| Error::parse(s, s.len(), "/"))?;
+        let denominator = s[slash + 1..]
+            .trim()
+            .parse()
+            .map_err(|_| Error::parse(s, slash + 1, "denominator"))?;
+        let groups = s[..slash]
+            .split('+')
+            .map(|g| g.trim().parse::<u8>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| Error::parse(s, 0, "numerator"))?;
+        if groups.len() == 1 {
+            TimeSignature::new(groups[0], denominator)
+        } else {
+            TimeSignature::grouped(&groups, denominator)
+        }
+    }
+}
+
+/// Metric strength of a time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strength {
+    /// First beat of a bar
+    Downbeat,
+    /// Secondary strong beat, like the third beat of 4/4
+    Strong,
+    /// Any other beat
+    Weak,
+    /// Between two beats
+    Offbeat,
+}
+
+/// A time located by its bar and beat, both starting at 1, and ticks into the beat
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BarBeatTick {
+    pub bar: u32,
+    pub beat: u32,
+    pub tick: u32,
+}
+
+/// Time signatures of a piece, each one starting at a bar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Meter {
+    /// Bars where the time signature changes, the first one being bar 1
+    changes: Vec<(u32, TimeSignature)>,
+}
+
+impl Meter {
+    /// Creates a `Meter` in the same `signature` all along
+    pub fn new(signature: TimeSignature) -> Self {
+        Meter {
+            changes: vec![(1, signature)],
+        }
+    }
+
+    /// Changes the time signature from `bar` (from 1) onwards, until the next change
+    pub fn set(&mut self, bar: u32, signature: TimeSignature) {
+        let bar = bar.max(1);
+        match self.changes.binary_search_by_key(&bar, |(b, _)| *b) {
+            Ok(i) => self.changes[i].1 = signature,
+            Err(i) => self.changes.insert(i, (bar, signature)),
+        }
+    }
+
+    /// Bars where the time signature changes, along with the new one
+    pub fn changes(&self) -> &[(u32, TimeSignature)] {
+        &self.changes
+    }
+
+    /// Time signature of `bar`
+    pub fn time_signature(&self, bar: u32) -> &TimeSignature {
+        let i = self.changes.partition_point(|(b, _)| *b <= bar).max(1);
+        &self.changes[i - 1].1
+    }
+
+    /// Start of `bar` (from 1), bar 0 being read as the first one
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::meter::{Meter, TimeSignature};
+    /// use mumuse::music::time::Time;
+    /// let mut meter = Meter::new(TimeSignature::new(3, 4).unwrap());
+    /// meter.set(3, TimeSignature::new(6, 8).unwrap());
+    /// assert_eq!(meter.bar_start(3), Time::new(2, 2, 2));
+    /// assert_eq!(meter.bar_start(5), Time::new(4, 1, 1));
+    /// ```
+    pub fn bar_start(&self, bar: u32) -> Time {
+        let bar = bar.max(1);
+        let whole_notes = self
+            .segments()
+            .take_while(|(first, _, _)| *first <= bar)
+            .last()
+            .map(|(first, start, signature)| {
+                start + signature.bar_length().to_ratio() * (bar - first)
+            })
+            .unwrap();
+        Time::from_whole_notes(whole_notes)
+    }
+
+    /// Bar, beat and ticks into the beat of `time`, a quarter note lasting `ppq` ticks.
+    ///
+    /// Ticks are rounded to the nearest one.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::meter::{Meter, TimeSignature};
+    /// use mumuse::music::time::Time;
+    /// let meter = Meter::new(TimeSignature::new(7, 8).unwrap());
+    /// let position = meter.bar_beat_tick(Time::new(2, 8, 6), 480);
+    /// assert_eq!((position.bar, position.beat, position.tick), (2, 3, 480));
+    /// ```
+    pub fn bar_beat_tick(&self, time: Time, ppq: u16) -> BarBeatTick {
+        let (bar, beat, offset) = self.locate(time);
+        let ticks = offset * Ratio::from_integer(4 * ppq as u32);
+        BarBeatTick {
+            bar,
+            beat: beat as u32 + 1,
+            tick: ticks.round().to_integer(),
+        }
+    }
+
+    /// Time of a bar, beat and ticks into the beat, a quarter note lasting `ppq` ticks.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::meter::{BarBeatTick, Meter, TimeSignature};
+    /// use mumuse::music::time::Time;
+    /// let meter = Meter::new(TimeSignature::new(6, 8).unwrap());
+    /// let position = BarBeatTick { bar: 2, beat: 2, tick: 240 };
+    /// assert_eq!(meter.time(position, 480), Time::new(2, 4, 2));
+    /// ```
+    pub fn time(&self, position: BarBeatTick, ppq: u16) -> Time {
+        let signature = self.time_signature(position.bar);
+        let beat = (position.beat.max(1) as usize - 1).min(signature.beats() - 1);
+        let whole_notes = self.bar_start(position.bar).whole_notes()
+            + signature.beat_offset(beat)
+            + Ratio::new(position.tick, 4 * ppq as u32);
+        Time::from_whole_notes(whole_notes)
+    }
+
+    /// Metric strength of `time`, `Offbeat` if it does not fall on a beat.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::meter::{Meter, Strength, TimeSignature};
+    /// use mumuse::music::time::Time;
+    /// let meter = Meter::default();
+    /// assert_eq!(meter.strength(Time::new(2, 4, 1)), Strength::Downbeat);
+    /// assert_eq!(meter.strength(Time::new(2, 4, 3)), Strength::Strong);
+    /// assert_eq!(meter.strength(Time::new(2, 4, 4)), Strength::Weak);
+    /// assert_eq!(meter.strength(Time::new(2, 8, 4)), Strength::Offbeat);
+    /// ```
+    pub fn strength(&self, time: Time) -> Strength {
+        let (bar, beat, offset) = self.locate(time);
+        if offset == Ratio::from_integer(0) {
+            self.time_signature(bar).beat_strength(beat)
+        } else {
+            Strength::Offbeat
+        }
+    }
+
+    /// True on downbeats and strong beats
+    pub fn is_strong(&self, time: Time) -> bool {
+        self.strength(time) <= Strength::Strong
+    }
+
+    /// Bar (from 1), beat (from 0) and whole notes into the beat of `time`
+    fn locate(&self, time: Time) -> (u32, usize, Ratio<u32>) {
+        let whole_notes = time.whole_notes();
+        let (first, start, signature) = self
+            .segments()
+            .take_while(|(_, start, _)| *start <= whole_notes)
+            .last()
+            .unwrap();
+        let length = signature.bar_length().to_ratio();
+        let bars = ((whole_notes - start) / length).to_integer();
+        let offset = whole_notes - start - length * bars;
+        let beat = (0..signature.beats())
+            .rev()
+            .find(|beat| signature.beat_offset(*beat) <= offset)
+            .unwrap();
+        (first + bars, beat, offset - signature.beat_offset(beat))
+    }
+
+    /// First bar, start in whole notes and time signature of every change
+    fn segments(&self) -> impl Iterator<Item = (u32, Ratio<u32>, &TimeSignature)> + '_ {
+        let mut start = Ratio::from_integer(0);
+        let mut previous: Option<(u32, &TimeSignature)> = None;
+        self.changes.iter().map(move |(bar, signature)| {
+            if let Some((previous_bar, previous_signature)) = previous {
+                start += previous_signature.bar_length().to_ratio() * (bar - previous_bar);
+            }
+            previous = Some((*bar, signature));
+            (*bar, start, signature)
+        })
+    }
+}
+
+/// A meter in 4/4 all along
+impl Default for Meter {
+    fn default() -> Self {
+        Meter::new(TimeSignature::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature(s: &str) -> TimeSignature {
+        TimeSignature::try_from(s).unwrap()
+    }
+
+    #[test]
+    fn time_signatures() {
+        assert_eq!(signature("12/8").grouping(), &[3, 3, 3, 3]);
+        assert_eq!(signature("5/8").grouping(), &[2, 3]);
+        assert_eq!(signature("3/8").grouping(), &[1, 1, 1]);
+        assert_eq!(signature("2+2+3/8"), signature("7/8"));
+        assert_eq!(signature("2+2+3/8").to_string(), "7/8");
+        assert_eq!(signature("3+3+2/8").to_string(), "3+3+2/8");
+        assert_eq!(signature("9/16").bar_length().to_ratio(), Ratio::new(9, 16));
+        assert!(TimeSignature::try_from("4").is_err());
+        assert!(TimeSignature::try_from("0/4").is_err());
+        assert!(TimeSignature::try_from("4/0").is_err());
+        assert!(TimeSignature::try_from("2+x/8").is_err());
+    }
+
+    #[test]
+    fn meter_changes() {
+        let mut meter = Meter::new(signature("4/4"));
+        meter.set(2, signature("3/4"));
+        meter.set(4, signature("7/8"));
+        meter.set(2, signature("5/4"));
+        assert_eq!(meter.changes().len(), 3);
+        assert_eq!(meter.time_signature(3), &signature("5/4"));
+        assert_eq!(meter.time_signature(9).to_string(), "7/8");
+
+        // Bars of 4/4, 5/4, 5/4 then 7/8
+        let starts = (1..=5).map(|bar| meter.bar_start(bar)).collect::<Vec<_>>();
+        let expected = [(0, 1), (1, 1), (9, 4), (7, 2), (35, 8)];
+        for (start, (numer, denom)) in starts.iter().zip(expected) {
+            assert_eq!(start.whole_notes(), Ratio::new(numer, denom));
+        }
+        for (bar, start) in (1..=5).zip(starts) {
+            let position = meter.bar_beat_tick(start, 480);
+            assert_eq!((position.bar, position.beat, position.tick), (bar, 1, 0));
+        }
+        assert_eq!(meter.bar_start(0), Time::default());
+        let position = BarBeatTick {
+            bar: 0,
+            beat: 1,
+            tick: 0,
+        };
+        assert_eq!(meter.time(position, 480), Time::default());
+    }
+
+    #[test]
+    fn bar_beat_tick() {
+        let mut meter = Meter::new(signature("6/8"));
+        meter.set(3, signature("2+2+3/8"));
+        for ticks in (0..20 * 480).step_by(120) {
+            let time = Time::from_ticks(ticks, 480);
+            assert_eq!(meter.time(meter.bar_beat_tick(time, 480), 480), time);
+        }
+        // Third beat of the 7/8 bar lasts three eighths
+        let time = meter.bar_start(3) + Duration::new(8, 6);
+        let position = meter.bar_beat_tick(time, 480);
+        assert_eq!((position.bar, position.beat, position.tick), (3, 3, 480));
+        assert_eq!(meter.strength(time), Strength::Offbeat);
+        assert_eq!(meter.strength(meter.bar_start(3)), Strength::Downbeat);
+        assert_eq!(meter.strength(Time::new(1, 8, 4)), Strength::Weak);
+        assert!(!meter.is_strong(Time::new(1, 8, 4)));
+        assert!(meter.is_strong(Time::new(1, 4, 4)));
+    }
+}
@@ -1,9 +1,20 @@
 //! Music theory representation elements
 
 pub mod chord;
+pub mod chord_symbol;
 pub mod common;
+pub mod counterpoint;
 pub mod duration;
+pub mod interval;
+pub mod key;
+pub mod meter;
+pub mod neo_riemannian;
 pub mod note;
+pub mod pitch_class_set;
+pub mod roman;
 pub mod scale;
 pub mod stream;
+pub mod tempo;
 pub mod time;
+pub mod tuning;
+pub mod voice_leading;
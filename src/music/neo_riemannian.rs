@@ -0,0 +1,393 @@
+//! Neo-Riemannian transformations of triads and their Tonnetz
+
+use crate::error::{Error, Result};
+use crate::music::chord::Chord;
+use crate::music::chord_symbol::parse_spelling;
+use crate::music::common::{find_letter_idx, Spelling};
+use crate::music::key::Mode;
+use crate::music::note::Note;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// A transformation of major and minor triads, each its own inverse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transformation {
+    /// Parallel, C major to C minor
+    P,
+    /// Leading tone exchange, C major to E minor
+    L,
+    /// Relative, C major to A minor
+    R,
+    /// Slide, C major to C# minor
+    Slide,
+    /// Nebenverwandt, RLP, C major to F minor
+    N,
+    /// Hexatonic pole, LPL, C major to Ab minor
+    H,
+}
+
+impl Transformation {
+    pub const ALL: [Transformation; 6] = [
+        Transformation::P,
+        Transformation::L,
+        Transformation::R,
+        Transformation::Slide,
+        Transformation::N,
+        Transformation::H,
+    ];
+
+    /// Moves of the root, third and fifth of a triad in `mode`, in semitones and staff steps
+    fn moves(&self, mode: Mode) -> [(i32, i32); 3] {
+        match (self, mode) {
+            (Transformation::P, Mode::Major) => [(0, 0), (-1, 0), (0, 0)],
+            (Transformation::P, Mode::Minor) => [(0, 0), (1, 0), (0, 0)],
+            (Transformation::L, Mode::Major) => [(-1, -1), (0, 0), (0, 0)],
+            (Transformation::L, Mode::Minor) => [(0, 0), (0, 0), (1, 1)],
+            (Transformation::R, Mode::Major) => [(0, 0), (0, 0), (2, 1)],
+            (Transformation::R, Mode::Minor) => [(-2, -1), (0, 0), (0, 0)],
+            (Transformation::Slide, Mode::Major) => [(1, 0), (0, 0), (1, 0)],
+            (Transformation::Slide, Mode::Minor) => [(-1, 0), (0, 0), (-1, 0)],
+            (Transformation::N, Mode::Major) => [(0, 0), (1, 1), (1, 1)],
+            (Transformation::N, Mode::Minor) => [(-1, -1), (-1, -1), (0, 0)],
+            (Transformation::H, Mode::Major) => [(-1, 0), (-1, 0), (1, 1)],
+            (Transformation::H, Mode::Minor) => [(-1, -1), (1, 0), (1, 0)],
+        }
+    }
+
+    /// Transforms a major or minor triad, whatever its voicing and doublings.
+    ///
+    /// Each voice moves to its nearest tone in the new triad, the others being held,
+    /// `None` if `chord` is not a major or minor triad.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::chord::Chord;
+    /// use mumuse::music::neo_riemannian::Transformation;
+    /// let c_major = Chord::from(vec!["E3", "C4", "G4", "C5"]);
+    /// let a_minor = Transformation::R.apply(&c_major).unwrap();
+    /// assert_eq!(a_minor.to_string(), "Chord(Note(E3),Note(C4),Note(A4),Note(C5))");
+    /// ```
+    pub fn apply(&self, chord: &Chord) -> Option<Chord> {
+        let (root, mode) = triad_root(chord)?;
+        let moves = self.moves(mode);
+        let notes = chord
+            .notes
+            .iter()
+            .map(|note| {
+                let (semitones, steps) = moves[role(root, mode, note)];
+                note.transpose(semitones, steps)
+            })
+            .collect();
+        Some(Chord::new(notes))
+    }
+}
+
+/// Displays a `Transformation` as its usual letter, `S` for the slide
+impl fmt::Display for Transformation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let letter = match self {
+            Transformation::P => "P",
+            Transformation::L => "L",
+            Transformation::R => "R",
+            Transformation::Slide => "S",
+            Transformation::N => "N",
+            Transformation::H => "H",
+        };
+        write!(f, "{}", letter)
+    }
+}
+
+/// Root note and mode of a major or minor triad
+fn triad_root(chord: &Chord) -> Option<(Note, Mode)> {
+    let mut classes: Vec<i8> = chord
+        .notes
+        .iter()
+        .map(|n| find_letter_idx(n.letter))
+        .collect();
+    classes.sort_unstable();
+    classes.dedup();
+    if classes.len() != 3 {
+        return None;
+    }
+    chord.notes.iter().find_map(|note| {
+        let root = find_letter_idx(note.letter);
+        let has = |semitones: i8| classes.contains(&((root + semitones) % 12));
+        match (has(4), has(3), has(7)) {
+            (true, _, true) => Some((*note, Mode::Major)),
+            (_, true, true) => Some((*note, Mode::Minor)),
+            _ => None,
+        }
+    })
+}
+
+/// Index of the role of `note`, root, third or fifth, in the triad on `root`
+fn role(root: Note, mode: Mode, note: &Note) -> usize {
+    let third = match mode {
+        Mode::Major => 4,
+        Mode::Minor => 3,
+    };
+    match (find_letter_idx(note.letter) - find_letter_idx(root.letter)).rem_euclid(12) {
+        0 => 0,
+        semitones if semitones == third => 1,
+        _ => 2,
+    }
+}
+
+/// Position of a pitch class on the Tonnetz torus, in fifths from 0 to 3
+/// and major thirds from 0 to 2, C being at the origin.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mumuse::music::neo_riemannian::coordinates;
+/// assert_eq!(coordinates(7), (1, 0)); // G, a fifth above C
+/// assert_eq!(coordinates(11), (1, 1)); // B, a major third above G
+/// ```
+pub fn coordinates(pitch_class: u8) -> (u8, u8) {
+    (0..4)
+        .flat_map(|fifths| (0..3).map(move |thirds| (fifths, thirds)))
+        .find(|(fifths, thirds)| (7 * fifths + 4 * thirds) % 12 == pitch_class % 12)
+        .unwrap()
+}
+
+/// A major or minor triad, a triangle of the Tonnetz
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Triad {
+    pub root: Spelling,
+    pub mode: Mode,
+}
+
+impl Triad {
+    /// Creates a `Triad` from its root and mode
+    pub fn new(root: Spelling, mode: Mode) -> Self {
+        Triad { root, mode }
+    }
+
+    /// Pitch class of the root, 0 for C
+    pub fn pitch_class(&self) -> u8 {
+        self.root.semitones().rem_euclid(12) as u8
+    }
+
+    /// Tonnetz vertices of the root, third and fifth, the triangle on the root
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::neo_riemannian::Triad;
+    /// let e_minor = Triad::try_from("Em").unwrap();
+    /// assert_eq!(e_minor.vertices(), [(0, 1), (1, 0), (1, 1)]);
+    /// ```
+    pub fn vertices(&self) -> [(u8, u8); 3] {
+        let third = match self.mode {
+            Mode::Major => 4,
+            Mode::Minor => 3,
+        };
+        let root = self.pitch_class();
+        [root, root + third, root + 7].map(coordinates)
+    }
+
+    /// Root position triad with its root in `octave`
+    pub fn to_chord(&self, octave: i8) -> Chord {
+        let root = Note::spelled(self.root, octave);
+        let third = match self.mode {
+            Mode::Major => root.transpose(4, 2),
+            Mode::Minor => root.transpose(3, 2),
+        };
+        Chord::new(vec![root, third, root.transpose(7, 4)])
+    }
+
+    /// Triad resulting from `transformation`
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::neo_riemannian::{Transformation, Triad};
+    /// let c_major = Triad::try_from("C").unwrap();
+    /// assert_eq!(c_major.transform(Transformation::H).to_string(), "Abm");
+    /// assert_eq!(c_major.transform(Transformation::Slide).to_string(), "C#m");
+    /// ```
+    pub fn transform(&self, transformation: Transformation) -> Self {
+        let chord = transformation.apply(&self.to_chord(4)).unwrap();
+        Triad::try_from(&chord).unwrap()
+    }
+
+    /// Shortest sequence of `transformations` leading to `other`, enharmonics being equal,
+    /// `None` if they cannot lead there.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::neo_riemannian::{Transformation, Triad};
+    /// let c_major = Triad::try_from("C").unwrap();
+    /// let a_flat_minor = Triad::try_from("Abm").unwrap();
+    /// let plr = [Transformation::P, Transformation::L, Transformation::R];
+    /// let path = c_major.path_to(&a_flat_minor, &plr).unwrap();
+    /// assert_eq!(path.len(), 3);
+    /// let path = c_major.path_to(&a_flat_minor, &Transformation::ALL).unwrap();
+    /// assert_eq!(path, vec![Transformation::H]);
+    /// ```
+    pub fn path_to(
+        &self,
+        other: &Triad,
+        transformations: &[Transformation],
+    ) -> Option<Vec<Transformation>> {
+        let index = |triad: &Triad| triad.pitch_class() as usize * 2 + triad.mode as usize;
+        // Transformation leading to each triad first, from its predecessor
+        let mut previous: [Option<(usize, Transformation)>; 24] = [None; 24];
+        let mut queue = VecDeque::from(vec![*self]);
+        while let Some(triad) = queue.pop_front() {
+            if index(&triad) == index(other) {
+                let mut path = vec![];
+                let mut current = index(&triad);
+                while current != index(self) {
+                    let (from, transformation) = previous[current].unwrap();
+                    path.push(transformation);
+                    current = from;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for transformation in transformations {
+                let next = triad.transform(*transformation);
+                if index(&next) != index(self) && previous[index(&next)].is_none() {
+                    previous[index(&next)] = Some((index(&triad), *transformation));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Triad of a major or minor triad chord, its root spelled as in the chord
+impl TryFrom<&Chord> for Triad {
+    type Error = Error;
+    fn try_from(chord: &Chord) -> Result<Self> {
+        let (root, mode) =
+            triad_root(chord).ok_or_else(|| Error::parse(&chord.to_string(), 0, "triad"))?;
+        Ok(Triad::new(root.spelling(), mode))
+    }
+}
+
+/// Displays a `Triad` as a chord symbol, `Eb` or `C#m`
+impl fmt::Display for Triad {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mode {
+            Mode::Major => write!(f, "{}", self.root),
+            Mode::Minor => write!(f, "{}m", self.root),
+        }
+    }
+}
+
+/// Parses triads written as chord symbols, `Eb` or `C#m`
+impl TryFrom<&str> for Triad {
+    type Error = Error;
+    fn try_from(s: &str) -> Result<Self> {
+        let (root, len) = parse_spelling(s).ok_or_else(|| Error::parse(s, 0, "root"))?;
+        let mode = match &s[len..] {
+            "" => Mode::Major,
+            "m" => Mode::Minor,
+            _ => return Err(Error::parse(s, len, "m or nothing")),
+        };
+        Ok(Triad::new(root, mode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triad(s: &str) -> Triad {
+        Triad::try_from(s).unwrap()
+    }
+
+    #[test]
+    fn transformations() {
+        let cases = [
+            (Transformation::P, "C", "Cm"),
+            (Transformation::L, "C", "Em"),
+            (Transformation::R, "C", "Am"),
+            (Transformation::Slide, "C", "C#m"),
+            (Transformation::N, "C", "Fm"),
+            (Transformation::H, "C", "Abm"),
+            (Transformation::L, "F#", "A#m"),
+            (Transformation::R, "Ebm", "Gb"),
+        ];
+        for (transformation, from, to) in cases {
+            assert_eq!(triad(from).transform(transformation).to_string(), to);
+            // Every transformation is an involution
+            assert_eq!(triad(to).transform(transformation), triad(from));
+        }
+        // N and H compound the basic ones, up to enharmonic spelling
+        for root in ["C", "Bbm", "F#"] {
+            let t = triad(root);
+            let rlp = t
+                .transform(Transformation::R)
+                .transform(Transformation::L)
+                .transform(Transformation::P);
+            let same = |a: Triad, b: Triad| (a.pitch_class(), a.mode) == (b.pitch_class(), b.mode);
+            assert!(same(t.transform(Transformation::N), rlp));
+            let lpl = t
+                .transform(Transformation::L)
+                .transform(Transformation::P)
+                .transform(Transformation::L);
+            assert!(same(t.transform(Transformation::H), lpl));
+        }
+    }
+
+    #[test]
+    fn voicings() {
+        let chord = Chord::from(vec!["G3", "E4", "C5", "G5"]);
+        let parallel = Transformation::P.apply(&chord).unwrap();
+        assert_eq!(
+            parallel.to_string(),
+            "Chord(Note(G3),Note(Eb4),Note(C5),Note(G5))"
+        );
+        let leading = Transformation::L.apply(&chord).unwrap();
+        assert_eq!(
+            leading.to_string(),
+            "Chord(Note(G3),Note(E4),Note(B4),Note(G5))"
+        );
+        assert!(Transformation::P
+            .apply(&Chord::from(vec!["C4", "E4", "G4", "Bb4"]))
+            .is_none());
+        assert!(Triad::try_from(&Chord::from(vec!["C4", "D4"])).is_err());
+    }
+
+    #[test]
+    fn tonnetz_paths() {
+        let plr = [Transformation::P, Transformation::L, Transformation::R];
+        let c_major = triad("C");
+        assert_eq!(c_major.path_to(&c_major, &plr).unwrap(), vec![]);
+        assert_eq!(
+            c_major.path_to(&triad("Ab"), &plr).unwrap(),
+            vec![Transformation::P, Transformation::L]
+        );
+        // Enharmonic triads are the same triangle
+        assert_eq!(c_major.path_to(&triad("G#"), &plr).unwrap().len(), 2);
+        // PLR reach every triad in at most five steps
+        for pitch_class in 0..12 {
+            for mode in [Mode::Major, Mode::Minor] {
+                let key = crate::music::key::Key::from_pitch_class(pitch_class, mode);
+                let path = c_major.path_to(&Triad::new(key.tonic, mode), &plr).unwrap();
+                assert!(path.len() <= 5);
+            }
+        }
+        // P alone only reaches C minor
+        assert!(c_major
+            .path_to(&triad("Am"), &[Transformation::P])
+            .is_none());
+        assert_eq!(triad("Cm").vertices(), [(0, 0), (1, 2), (1, 0)]);
+    }
+}
@@ -1,24 +1,59 @@
 //! Letter and octave
 
-use itertools::Itertools;
+use crate::error::{Error, Result};
 use crate::messages::Data;
 use crate::music::chord::Chord;
-use crate::music::common::Interval::*;
-use crate::music::common::{find_letter_idx, Interval, Letter, KEYBOARD};
+use crate::music::chord_symbol::ChordSymbol;
+use crate::music::common::{find_letter_idx, Accidental, Letter, Spelling, Step, KEYBOARD};
+use crate::music::interval::Interval;
+use crate::music::tuning::{Tuning, A4_SEMITONES};
+use itertools::Itertools;
 use std::{fmt, ops};
 
+/// Octave numbering convention, given by the name of middle C (key number 60)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MiddleC {
+    /// Middle C is C3, key 0 being C-2 (Yamaha)
+    C3,
+    /// Middle C is C4, key 0 being C-1 (scientific pitch notation, Roland)
+    C4,
+    /// Middle C is C5, key 0 being C0 (some trackers and older software)
+    C5,
+}
+
+impl Default for MiddleC {
+    fn default() -> Self {
+        MiddleC::C4
+    }
+}
+
+impl MiddleC {
+    /// Octave of key number 0
+    fn lowest_octave(&self) -> i32 {
+        match self {
+            MiddleC::C3 => -2,
+            MiddleC::C4 => -1,
+            MiddleC::C5 => 0,
+        }
+    }
+}
+
 /// Note abstraction with letter and octave
+///
+/// `letter` and `octave` give the sounding pitch, the spelling only naming it:
+/// B#3 has letter C and octave 4.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Note {
     pub letter: Letter,
     pub octave: i8,
+    spelling: Spelling,
 }
 
 impl Note {
     /// Construct Note from `Letter` and `octave`.
     ///
-    /// The `Letter` are all letter from A to G with only flat `b` variations.
-    /// Octaves can be negatives.
+    /// The `Letter` are all letter from A to G with only flat `b` variations,
+    /// and are spelled that way. Octaves can be negatives.
     ///
     /// # Examples
     ///
@@ -29,12 +64,125 @@ impl Note {
     /// let n = Note::new(Letter::A, 4);
     /// ```
     pub fn new(letter: Letter, octave: i8) -> Self {
-        Note { letter, octave }
+        Note {
+            letter,
+            octave,
+            spelling: Spelling::from(letter),
+        }
+    }
+
+    /// Construct Note from a `Spelling` and its written octave.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::common::{Accidental, Letter, Spelling, Step};
+    /// use mumuse::music::note::Note;
+    /// let b_sharp = Note::spelled(Spelling::new(Step::B, Accidental::Sharp), 3);
+    /// assert_eq!((b_sharp.letter, b_sharp.octave), (Letter::C, 4));
+    /// assert_eq!(b_sharp.to_string(), "Note(B#3)");
+    /// ```
+    pub fn spelled(spelling: Spelling, octave: i8) -> Self {
+        Note {
+            spelling,
+            ..Note::from_semitones(octave as i32 * 12 + spelling.semitones() as i32)
+        }
+    }
+
+    /// Spelling of the note, the flat spelling of `letter` if they disagree.
+    pub fn spelling(&self) -> Spelling {
+        if self.spelling.letter() == self.letter {
+            self.spelling
+        } else {
+            Spelling::from(self.letter)
+        }
+    }
+
+    /// Octave as written with the spelling, B#3 sounding in octave 4.
+    pub fn written_octave(&self) -> i8 {
+        self.octave - self.spelling().semitones().div_euclid(12)
+    }
+
+    /// Same pitch spelled on `step`, `None` if it needs more than a double accidental.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::common::Step;
+    /// use mumuse::music::note::Note;
+    /// let g_flat = Note::try_from("Gb4").unwrap();
+    /// assert_eq!(g_flat.respell(Step::F).unwrap().to_string(), "Note(F#4)");
+    /// assert!(g_flat.respell(Step::C).is_none());
+    /// ```
+    pub fn respell(&self, step: Step) -> Option<Note> {
+        let alteration = (find_letter_idx(self.letter) - step.semitones() + 6).rem_euclid(12) - 6;
+        let accidental = Accidental::from_semitones(alteration)?;
+        Some(Note {
+            spelling: Spelling::new(step, accidental),
+            ..*self
+        })
+    }
+
+    /// True if both notes sound the same pitch, whatever their spelling.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::note::Note;
+    /// let c_sharp = Note::try_from("C#4").unwrap();
+    /// let d_flat = Note::try_from("Db4").unwrap();
+    /// assert!(c_sharp.is_enharmonic(&d_flat));
+    /// assert_ne!(c_sharp, d_flat);
+    /// ```
+    pub fn is_enharmonic(&self, other: &Note) -> bool {
+        self.letter == other.letter && self.octave == other.octave
+    }
+
+    /// Moves the note by `semitones`, spelled `steps` staff steps away.
+    ///
+    /// Falls back to the flat spelling when more than a double accidental would be needed.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::note::Note;
+    /// let d = Note::try_from("D4").unwrap();
+    /// assert_eq!(d.transpose(3, 1).to_string(), "Note(E#4)"); // Augmented second
+    /// assert_eq!(d.transpose(3, 2).to_string(), "Note(F4)"); // Minor third
+    /// ```
+    pub fn transpose(&self, semitones: i32, steps: i32) -> Note {
+        let spelling = self.spelling();
+        let step = spelling.step as i32 + steps;
+        let octave = self.written_octave() as i32 + step.div_euclid(7);
+        let step: Step = num::FromPrimitive::from_i32(step.rem_euclid(7)).unwrap();
+        let pitch = self.semitones() + semitones;
+        let natural = octave * 12 + step.semitones() as i32;
+        let note = Note::from_semitones(pitch);
+        match i8::try_from(pitch - natural)
+            .ok()
+            .and_then(Accidental::from_semitones)
+        {
+            Some(accidental) => Note {
+                spelling: Spelling::new(step, accidental),
+                ..note
+            },
+            None => note,
+        }
     }
 
     /// Creates Chord with `self` as root note.
     ///
-    /// Specify by an `&str` the type of chord to build.
+    /// Specify by an `&str` the type of chord to build, either one of the named types
+    /// (`maj`, `min7`, `halfdim7`...) or a chord symbol quality (`m7b5`, `13#11`...),
+    /// unknown types being an error.
     ///
     /// # Examples
     ///
@@ -43,32 +191,234 @@ impl Note {
     /// ```
     /// use mumuse::music::{note::Note, common::Letter};
     /// let n = Note::new(Letter::A, 4);
-    /// let c = n.chord("maj7");
+    /// let c = n.chord("maj7").unwrap();
+    /// let altered = n.chord("7#9").unwrap();
+    /// assert!(n.chord("major").is_err());
     /// ```
-    pub fn chord(&self, s: &str) -> Chord {
+    pub fn chord(&self, s: &str) -> Result<Chord> {
         let intervals = match s {
-            "sus2" => vec![Unison, MajorSecond, Fifth],
-            "sus4" => vec![Unison, Fourth, Fifth],
-            "maj" => vec![Unison, MajorThird, Fifth],
-            "min" => vec![Unison, MinorThird, Fifth],
-            "dim" => vec![Unison, MinorThird, Tritone],
-            "aug" => vec![Unison, MajorThird, MinorSixth],
-            "maj6" => vec![Unison, MajorThird, Fifth, MajorSixth],
-            "min6" => vec![Unison, MinorThird, Fifth, MajorSixth],
-            "maj7" => vec![Unison, MajorThird, Fifth, MajorSeventh],
-            "min7" => vec![Unison, MinorThird, Fifth, MinorSeventh],
-            "dom7" => vec![Unison, MajorThird, Fifth, MinorSeventh],
-            "aug7" => vec![Unison, MajorThird, MinorSixth, MajorSeventh],
-            "dim7" => vec![Unison, MinorThird, Tritone, MajorSixth],
-            "minmaj7" => vec![Unison, MinorThird, Fifth, MajorSeventh],
-            "halfdim7" => vec![Unison, MinorThird, Tritone, MinorSeventh],
-            _ => vec![Unison],
+            "sus2" => vec![Interval::UNISON, Interval::MAJOR_SECOND, Interval::FIFTH],
+            "sus4" => vec![Interval::UNISON, Interval::FOURTH, Interval::FIFTH],
+            "maj" => vec![Interval::UNISON, Interval::MAJOR_THIRD, Interval::FIFTH],
+            "min" => vec![Interval::UNISON, Interval::MINOR_THIRD, Interval::FIFTH],
+            "dim" => vec![
+                Interval::UNISON,
+                Interval::MINOR_THIRD,
+                Interval::DIMINISHED_FIFTH,
+            ],
+            "aug" => vec![
+                Interval::UNISON,
+                Interval::MAJOR_THIRD,
+                Interval::AUGMENTED_FIFTH,
+            ],
+            "maj6" => vec![
+                Interval::UNISON,
+                Interval::MAJOR_THIRD,
+                Interval::FIFTH,
+                Interval::MAJOR_SIXTH,
+            ],
+            "min6" => vec![
+                Interval::UNISON,
+                Interval::MINOR_THIRD,
+                Interval::FIFTH,
+                Interval::MAJOR_SIXTH,
+            ],
+            "maj7" => vec![
+                Interval::UNISON,
+                Interval::MAJOR_THIRD,
+                Interval::FIFTH,
+                Interval::MAJOR_SEVENTH,
+            ],
+            "min7" => vec![
+                Interval::UNISON,
+                Interval::MINOR_THIRD,
+                Interval::FIFTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            "dom7" => vec![
+                Interval::UNISON,
+                Interval::MAJOR_THIRD,
+                Interval::FIFTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            "aug7" => vec![
+                Interval::UNISON,
+                Interval::MAJOR_THIRD,
+                Interval::AUGMENTED_FIFTH,
+                Interval::MAJOR_SEVENTH,
+            ],
+            "dim7" => vec![
+                Interval::UNISON,
+                Interval::MINOR_THIRD,
+                Interval::DIMINISHED_FIFTH,
+                Interval::DIMINISHED_SEVENTH,
+            ],
+            "minmaj7" => vec![
+                Interval::UNISON,
+                Interval::MINOR_THIRD,
+                Interval::FIFTH,
+                Interval::MAJOR_SEVENTH,
+            ],
+            "halfdim7" => vec![
+                Interval::UNISON,
+                Interval::MINOR_THIRD,
+                Interval::DIMINISHED_FIFTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            _ => ChordSymbol::quality_intervals(s)
+                .map_err(|_| Error::UnknownChordQuality(s.to_string()))?,
         };
         let notes = intervals
             .iter()
             .map(|interval| *self + *interval)
             .collect_vec();
-        Chord::new(notes)
+        Ok(Chord::new(notes))
+    }
+
+    /// Converts to a MIDI key number, middle C (C4) being key 60.
+    ///
+    /// Fails with `Error::PitchOutOfRange` below C-1 and above G9.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::note::Note;
+    /// let a = Note::try_from("A4").unwrap();
+    /// assert_eq!(a.to_midi().unwrap(), 69);
+    /// assert!(Note::try_from("A9").unwrap().to_midi().is_err());
+    /// ```
+    pub fn to_midi(&self) -> Result<u8> {
+        self.to_midi_with(MiddleC::default())
+    }
+
+    /// Converts to a MIDI key number with a given octave numbering convention.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::note::{MiddleC, Note};
+    /// let c = Note::try_from("C3").unwrap();
+    /// assert_eq!(c.to_midi_with(MiddleC::C3).unwrap(), 60);
+    /// ```
+    pub fn to_midi_with(&self, middle_c: MiddleC) -> Result<u8> {
+        let semitones = self.semitones();
+        let key = semitones - middle_c.lowest_octave() * 12;
+        u8::try_from(key)
+            .ok()
+            .filter(|k| *k < 128)
+            .ok_or(Error::PitchOutOfRange(semitones))
+    }
+
+    /// Creates a `Note` from a MIDI key number, middle C (C4) being key 60.
+    ///
+    /// An error is returned for key numbers above 127.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::note::Note;
+    /// let n = Note::from_midi(21).unwrap();
+    /// assert_eq!(n.to_string(), "Note(A0)");
+    /// assert!(Note::from_midi(200).is_err());
+    /// ```
+    pub fn from_midi(key: u8) -> Result<Self> {
+        Self::from_midi_with(key, MiddleC::default())
+    }
+
+    /// Creates a `Note` from a MIDI key number with a given octave numbering convention.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::note::{MiddleC, Note};
+    /// let n = Note::from_midi_with(60, MiddleC::C5).unwrap();
+    /// assert_eq!(n.to_string(), "Note(C5)");
+    /// ```
+    pub fn from_midi_with(key: u8, middle_c: MiddleC) -> Result<Self> {
+        if key > 127 {
+            return Err(Error::KeyOutOfRange(key));
+        }
+        let key = key as i32;
+        Ok(Note::new(
+            KEYBOARD[(key % 12) as usize],
+            (key / 12 + middle_c.lowest_octave()) as i8,
+        ))
+    }
+
+    /// Frequency in Hz in twelve tone equal temperament, relative to the `tuning` A4.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::{note::Note, tuning::Tuning};
+    /// let a = Note::try_from("A3").unwrap();
+    /// assert_eq!(a.frequency(Tuning::STANDARD), 220.0);
+    /// assert_eq!(a.frequency(Tuning::new(442.0)), 221.0);
+    /// ```
+    pub fn frequency(&self, tuning: Tuning) -> f64 {
+        tuning.frequency((self.semitones() - A4_SEMITONES) as f64)
+    }
+
+    /// Closest `Note` to `frequency` in Hz, with the offset in cents from it to `frequency`.
+    ///
+    /// The offset lies between -50 and 50 cents. Fails with `Error::InvalidFrequency` if
+    /// `frequency` is not strictly positive, or `Error::PitchOutOfRange` if the octave
+    /// can't be represented.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::{note::Note, tuning::Tuning};
+    /// let (note, cents) = Note::from_frequency(445.0, Tuning::STANDARD).unwrap();
+    /// assert_eq!(note.to_string(), "Note(A4)");
+    /// assert!((cents - 19.56).abs() < 0.01);
+    /// ```
+    pub fn from_frequency(frequency: f64, tuning: Tuning) -> Result<(Note, f64)> {
+        if !(frequency.is_finite() && frequency > 0.0) {
+            return Err(Error::InvalidFrequency(frequency));
+        }
+        let semitones = tuning.semitones(frequency) + A4_SEMITONES as f64;
+        let nearest = semitones.round();
+        let octave = (nearest / 12.0).floor();
+        if octave < i8::MIN as f64 || octave > i8::MAX as f64 {
+            return Err(Error::PitchOutOfRange(
+                nearest.clamp(i32::MIN as f64, i32::MAX as f64) as i32,
+            ));
+        }
+        Ok((
+            Note::from_semitones(nearest as i32),
+            (semitones - nearest) * 100.0,
+        ))
+    }
+
+    /// Signed distance in semitones from C0
+    pub(crate) fn semitones(&self) -> i32 {
+        self.octave as i32 * 12 + find_letter_idx(self.letter) as i32
+    }
+
+    /// Staff steps from C0, as written
+    fn staff_position(&self) -> i32 {
+        self.written_octave() as i32 * 7 + self.spelling().step as i32
+    }
+
+    /// Note `semitones` away from C0, spelled with flats
+    fn from_semitones(semitones: i32) -> Self {
+        Note::new(
+            KEYBOARD[semitones.rem_euclid(12) as usize],
+            semitones.div_euclid(12) as i8,
+        )
     }
 
     /// Compute distance in semitones between two notes.
@@ -84,7 +434,7 @@ impl Note {
     /// let dist = a.dist_to(&b);
     /// ```
     pub fn dist_to(&self, other: &Note) -> u8 {
-        let octave_diff: i8 = self.octave as i8 - other.octave as i8;
+        let octave_diff: i8 = self.octave - other.octave;
         (find_letter_idx(self.letter) - find_letter_idx(other.letter) + octave_diff * 12)
             .abs()
             .try_into()
@@ -92,14 +442,23 @@ impl Note {
     }
 }
 
-/// Displays a `Note`
+/// Displays a `Note` with its spelling and written octave
 impl fmt::Display for Note {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Note({:?}{})", self.letter, self.octave)
+        write!(f, "Note({}{})", self.spelling(), self.written_octave())
+    }
+}
+
+/// Notes are equal when they have the same pitch and spelling
+impl PartialEq for Note {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_enharmonic(other) && self.spelling() == other.spelling()
     }
 }
 
-/// Conversion from `Data::KeyNumber`.
+impl Eq for Note {}
+
+/// Conversion from `Data::KeyNumber`, middle C (C4) being key 60.
 ///
 /// # Examples
 ///
@@ -110,22 +469,23 @@ impl fmt::Display for Note {
 /// use mumuse::music::note::Note;
 /// let kn = Data::KeyNumber(44);
 /// let n = Note::try_from(&kn);
+/// assert!(Note::try_from(&Data::Velocity(44)).is_err());
 /// ```
 impl TryFrom<&Data> for Note {
-    type Error = ();
-    fn try_from(kn: &Data) -> Result<Self, Self::Error> {
+    type Error = Error;
+    fn try_from(kn: &Data) -> Result<Self> {
         match kn {
-            Data::KeyNumber(x) => {
-                let index: usize = ((x - 21) % 12) as usize;
-                Ok(Note::new(KEYBOARD[index], (*x as i8 - 21) / 12))
-            }
-            _ => Err(()),
+            Data::KeyNumber(x) => Note::from_midi(*x),
+            _ => Err(Error::UnexpectedData(kn.clone())),
         }
     }
 }
 
 /// Conversion from `&str`.
 ///
+/// A letter with optional accidentals (`b`, `bb`, `#`, `##` or `x`), followed by
+/// the written octave number.
+///
 /// # Examples
 ///
 /// Basic usage:
@@ -133,82 +493,126 @@ impl TryFrom<&Data> for Note {
 /// ```
 /// use mumuse::music::note::Note;
 /// let n = Note::try_from("A3"); // Is a Result
+/// let low = Note::try_from("C#-1").unwrap();
+/// let high = Note::try_from("B#3").unwrap();
+/// assert!(high.is_enharmonic(&Note::try_from("C4").unwrap()));
+/// assert!(Note::try_from("H2").is_err());
+/// assert!(Note::try_from("Cb#4").is_err());
 /// ```
 impl TryFrom<&str> for Note {
-    type Error = ();
-    fn try_from(s: &str) -> Result<Self, Self::Error> {
-        let octave_str: &i8 = &s[s.len() - 1..].parse::<i8>().unwrap();
-        let letter: Option<Letter> = match &s[0..s.len() - 1] {
-            "C" | "B#" => Some(Letter::C),
-            "Db" | "C#" => Some(Letter::Db),
-            "D" => Some(Letter::D),
-            "Eb" | "D#" => Some(Letter::Eb),
-            "E" | "Fb" => Some(Letter::E),
-            "F" | "E#" => Some(Letter::F),
-            "Gb" | "F#" => Some(Letter::Gb),
-            "G" => Some(Letter::G),
-            "Ab" | "G#" => Some(Letter::Ab),
-            "A" => Some(Letter::A),
-            "Bb" | "A#" => Some(Letter::Bb),
-            "B" | "Cb" => Some(Letter::B),
-            _ => None,
+    type Error = Error;
+    fn try_from(s: &str) -> Result<Self> {
+        let step = match s.chars().next() {
+            Some('C') => Step::C,
+            Some('D') => Step::D,
+            Some('E') => Step::E,
+            Some('F') => Step::F,
+            Some('G') => Step::G,
+            Some('A') => Step::A,
+            Some('B') => Step::B,
+            _ => return Err(Error::parse(s, 0, "note letter")),
         };
-        match letter {
-            Some(l) => Ok(Note::new(l, *octave_str)),
-            None => Err(()),
+        // Octave starts after the letter and its accidentals
+        let octave_start = s
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| !matches!(c, 'b' | '#' | 'x'))
+            .map_or(s.len(), |(i, _)| i);
+        // Flats and sharps don't mix
+        let accidentals = &s[1..octave_start];
+        if accidentals.contains('b') && accidentals.contains(['#', 'x']) {
+            return Err(Error::parse(s, 1, "accidental"));
         }
+        let alteration: i32 = accidentals
+            .chars()
+            .map(|c| match c {
+                'b' => -1,
+                '#' => 1,
+                _ => 2,
+            })
+            .sum();
+        let accidental = i8::try_from(alteration)
+            .ok()
+            .and_then(Accidental::from_semitones)
+            .ok_or_else(|| Error::parse(s, 1, "accidental"))?;
+        let octave = s[octave_start..]
+            .parse::<i8>()
+            .map_err(|_| Error::parse(s, octave_start, "octave number"))?;
+        Ok(Note::spelled(Spelling::new(step, accidental), octave))
     }
 }
 
 /// Overload operator + for Note + Interval
 impl ops::Add<Interval> for Note {
     type Output = Note;
-    /// Adds an `Interval` to a `Note`
+    /// Adds an `Interval` to a `Note`, spelled after the interval name
     ///
     /// # Examples
     ///
     /// Basic usage:
     ///
     /// ```
-    /// use mumuse::music::{note::Note, common::{Interval, Letter}};
-    /// let n = Note::new(Letter::A, 3) + Interval::MinorSecond;
+    /// use mumuse::music::{note::Note, interval::Interval, common::Letter};
+    /// let n = Note::new(Letter::A, 3) + Interval::MINOR_SECOND;
+    /// let e_sharp = Note::try_from("C#4").unwrap() + Interval::MAJOR_THIRD;
+    /// assert_eq!(e_sharp.to_string(), "Note(E#4)");
     /// ```
     fn add(self, rhs: Interval) -> Note {
-        let self_index = find_letter_idx(self.letter);
-        let target_index = self_index + rhs as i8;
-        Note::new(
-            KEYBOARD[(target_index % 12) as usize],
-            self.octave + (target_index / 12) as i8,
-        )
+        self.transpose(rhs.semitones(), rhs.steps() as i32)
     }
 }
 
 /// Overload operator - for Note - Interval
 impl ops::Sub<Interval> for Note {
     type Output = Note;
-    /// Subtracts an `Interval` to a `Note`
+    /// Subtracts an `Interval` to a `Note`, spelled after the interval name
     ///
     /// # Examples
     ///
     /// Basic usage:
     ///
     /// ```
-    /// use mumuse::music::{note::Note, common::{Interval, Letter}};
-    /// let n = Note::new(Letter::A, 3) - Interval::MinorSecond;
+    /// use mumuse::music::{note::Note, interval::Interval, common::Letter};
+    /// let n = Note::new(Letter::A, 3) - Interval::MINOR_SECOND;
     /// ```
     fn sub(self, rhs: Interval) -> Note {
-        let self_index = find_letter_idx(self.letter);
-        let mut neg_offset = 0;
-        let mut target_index: i8 = self_index - (rhs as i8);
-        while target_index < 0 {
-            target_index += 12;
-            neg_offset = if target_index == 0 { 0 } else { -1 }
-        }
+        self.transpose(-rhs.semitones(), -(rhs.steps() as i32))
+    }
+}
 
-        Note::new(
-            KEYBOARD[(target_index % 12) as usize],
-            self.octave + neg_offset + (self_index - (rhs as i8)) / 12,
-        )
+/// Overload operator - for Note - Note
+impl ops::Sub<Note> for Note {
+    type Output = Interval;
+    /// Interval between two notes, from the lower to the higher on the staff
+    ///
+    /// # Panics
+    ///
+    /// Panics if the notes are more than 254 staff steps apart.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::{interval::Interval, note::Note};
+    /// let c = Note::try_from("C4").unwrap();
+    /// let f_sharp = Note::try_from("F#4").unwrap();
+    /// assert_eq!(f_sharp - c, Interval::AUGMENTED_FOURTH);
+    /// assert_eq!(c - f_sharp, Interval::AUGMENTED_FOURTH);
+    /// ```
+    fn sub(self, rhs: Note) -> Interval {
+        let (lower, upper) = if self.staff_position() < rhs.staff_position() {
+            (self, rhs)
+        } else {
+            (rhs, self)
+        };
+        let steps = upper.staff_position() - lower.staff_position();
+        u8::try_from(steps)
+            .ok()
+            .and_then(|steps| {
+                Interval::from_size(steps, upper.semitones() - lower.semitones()).ok()
+            })
+            .expect("interval out of range")
     }
 }
 
@@ -227,15 +631,122 @@ mod tests {
         assert_eq!(bb.octave, 2);
     }
 
+    #[test]
+    fn note_from_invalid_str() {
+        for (s, position) in [
+            ("", 0),
+            ("H2", 0),
+            ("C", 1),
+            ("Db", 2),
+            ("A-", 1),
+            ("G#y", 2),
+            ("Fbbb4", 1),
+            ("C#x2", 1),
+            ("Cb#4", 1),
+            ("Cbx4", 1),
+            ("Fxb3", 1),
+        ] {
+            match Note::try_from(s) {
+                Err(Error::Parse { position: p, .. }) => assert_eq!(p, position),
+                other => panic!("{:?} parsed as {:?}", s, other),
+            }
+        }
+        let low = Note::try_from("Bb-2").unwrap();
+        assert_eq!((low.letter, low.octave), (Letter::Bb, -2));
+        let high = Note::try_from("E12").unwrap();
+        assert_eq!((high.letter, high.octave), (Letter::E, 12));
+    }
+
+    #[test]
+    fn midi_round_trip() {
+        for middle_c in [MiddleC::C3, MiddleC::C4, MiddleC::C5] {
+            for key in 0..128u8 {
+                let note = Note::from_midi_with(key, middle_c).unwrap();
+                assert_eq!(note.letter, KEYBOARD[key as usize % 12]);
+                assert_eq!(note.to_midi_with(middle_c).unwrap(), key);
+            }
+        }
+        let middle_c = Note::try_from("C4").unwrap();
+        assert_eq!(middle_c.to_midi_with(MiddleC::C3).unwrap(), 72);
+        assert_eq!(middle_c.to_midi_with(MiddleC::C4).unwrap(), 60);
+        assert_eq!(middle_c.to_midi_with(MiddleC::C5).unwrap(), 48);
+        for key in [128, 200, 255] {
+            assert!(matches!(
+                Note::from_midi(key),
+                Err(Error::KeyOutOfRange(k)) if k == key
+            ));
+        }
+    }
+
+    #[test]
+    fn midi_out_of_range() {
+        for (s, middle_c) in [
+            ("B-2", MiddleC::C4),
+            ("Ab9", MiddleC::C4),
+            ("B-3", MiddleC::C3),
+            ("Ab8", MiddleC::C3),
+            ("B-1", MiddleC::C5),
+            ("Ab10", MiddleC::C5),
+        ] {
+            let note = Note::try_from(s).unwrap();
+            assert!(matches!(
+                note.to_midi_with(middle_c),
+                Err(Error::PitchOutOfRange(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn frequency_round_trip() {
+        let middle_c = Note::try_from("C4").unwrap();
+        assert!((middle_c.frequency(Tuning::STANDARD) - 261.6256).abs() < 1e-4);
+        assert!((middle_c.frequency(Tuning::new(432.0)) - 256.8687).abs() < 1e-4);
+
+        for a4 in [415.0, 432.0, 440.0, 442.0] {
+            let tuning = Tuning::new(a4);
+            for key in 0..128 {
+                let note = Note::from_midi(key).unwrap();
+                let (found, cents) = Note::from_frequency(note.frequency(tuning), tuning).unwrap();
+                assert_eq!((found.letter, found.octave), (note.letter, note.octave));
+                assert!(cents.abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn frequency_cents_offset() {
+        let (note, cents) = Note::from_frequency(450.0, Tuning::STANDARD).unwrap();
+        assert_eq!((note.letter, note.octave), (Letter::A, 4));
+        assert!((cents - 38.906).abs() < 1e-3);
+
+        let (note, cents) = Note::from_frequency(460.0, Tuning::STANDARD).unwrap();
+        assert_eq!((note.letter, note.octave), (Letter::Bb, 4));
+        assert!((cents + 23.05).abs() < 1e-2);
+
+        let (note, _) = Note::from_frequency(15.0, Tuning::STANDARD).unwrap();
+        assert_eq!((note.letter, note.octave), (Letter::B, -1));
+
+        for frequency in [0.0, -440.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                Note::from_frequency(frequency, Tuning::STANDARD),
+                Err(Error::InvalidFrequency(_))
+            ));
+        }
+        assert!(matches!(
+            Note::from_frequency(1e-300, Tuning::STANDARD),
+            Err(Error::PitchOutOfRange(_))
+        ));
+    }
+
     #[test]
     fn note_add_interval() {
         let c = Note::try_from("C2").unwrap();
         for i in 1..24 {
-            let interval: Interval = num::FromPrimitive::from_u32(i).unwrap();
+            let interval = Interval::from_semitones(i as u8);
             let letter: Letter = num::FromPrimitive::from_u32(i % 12).unwrap();
             let note = c + interval;
             assert_eq!(note.letter, letter);
-            assert_eq!(note.octave as i8, 2 + (i as i8) / 12);
+            assert_eq!(note.octave, 2 + (i as i8) / 12);
         }
     }
 
@@ -243,39 +754,92 @@ mod tests {
     fn note_sub_interval() {
         let c = Note::try_from("C2").unwrap();
         for i in 1..24 {
-            let interval: Interval = num::FromPrimitive::from_u32(i).unwrap();
+            let interval = Interval::from_semitones(i as u8);
             let letter: Letter = num::FromPrimitive::from_u32((24 - i) % 12).unwrap();
             let note = c - interval;
             assert_eq!(note.letter, letter);
             assert_eq!(
-                note.octave as i8,
+                note.octave,
                 1 - (i as i8) / 12 + if i % 12 == 0 { 1 } else { 0 }
             );
         }
     }
 
+    #[test]
+    fn spelled_notes() {
+        for (s, letter, octave) in [
+            ("C#4", Letter::Db, 4),
+            ("B#3", Letter::C, 4),
+            ("Cb4", Letter::B, 3),
+            ("Fx2", Letter::G, 2),
+            ("F##2", Letter::G, 2),
+            ("Dbb0", Letter::C, 0),
+            ("Cbb0", Letter::Bb, -1),
+        ] {
+            let note = Note::try_from(s).unwrap();
+            assert_eq!((note.letter, note.octave), (letter, octave), "{}", s);
+            let name = s.replace('x', "##");
+            assert_eq!(note.to_string(), format!("Note({})", name));
+            assert_eq!(Note::try_from(&name[..]).unwrap(), note);
+        }
+        assert_ne!(
+            Note::try_from("E#4").unwrap(),
+            Note::try_from("F4").unwrap()
+        );
+
+        // Spelling is dropped when the letter is changed directly
+        let mut note = Note::try_from("C#4").unwrap();
+        note.letter = Letter::D;
+        assert_eq!(note.to_string(), "Note(D4)");
+    }
+
+    #[test]
+    fn spelled_intervals() {
+        let f_sharp = Note::try_from("F#4").unwrap();
+        assert_eq!((f_sharp + Interval::MAJOR_SEVENTH).to_string(), "Note(E#5)");
+        assert_eq!(
+            (f_sharp + Interval::AUGMENTED_FOURTH).to_string(),
+            "Note(B#4)"
+        );
+        assert_eq!((f_sharp - Interval::MAJOR_THIRD).to_string(), "Note(D4)");
+        let c_flat = Note::try_from("Cb4").unwrap();
+        assert_eq!((c_flat - Interval::MINOR_SECOND).to_string(), "Note(Bb3)");
+        assert_eq!((c_flat - Interval::MAJOR_SECOND).to_string(), "Note(Bbb3)");
+        assert_eq!((c_flat + Interval::OCTAVE).to_string(), "Note(Cb5)");
+        // Beyond double accidentals, the flat spelling is used
+        let b_sharp = Note::try_from("B#3").unwrap();
+        assert_eq!(b_sharp.transpose(0, 1).to_string(), "Note(C4)");
+        assert_eq!(b_sharp.transpose(2, 1).to_string(), "Note(C##4)");
+        assert_eq!(b_sharp.transpose(3, 1).to_string(), "Note(Eb4)");
+    }
+
+    #[test]
+    fn interval_between_notes() {
+        for (lower, upper, interval) in [
+            ("C4", "E4", "M3"),
+            ("C#4", "Eb4", "d3"),
+            ("Eb4", "C#5", "A6"),
+            ("B#3", "C4", "d2"),
+            ("C4", "C#4", "A1"),
+            ("G3", "A5", "M16"),
+            ("F#4", "C5", "d5"),
+        ] {
+            let lower = Note::try_from(lower).unwrap();
+            let upper = Note::try_from(upper).unwrap();
+            let interval = Interval::try_from(interval).unwrap();
+            assert_eq!(upper - lower, interval);
+            assert_eq!(lower + interval, upper);
+            assert_eq!(upper - interval, lower);
+        }
+    }
+
     #[test]
     fn distance_between_notes() {
-        let note_1 = Note {
-            letter: Letter::C,
-            octave: 0,
-        };
-        let note_2 = Note {
-            letter: Letter::E,
-            octave: 0,
-        };
-        let note_3 = Note {
-            letter: Letter::E,
-            octave: 1,
-        };
-        let note_4 = Note {
-            letter: Letter::B,
-            octave: 0,
-        };
-        let note_5 = Note {
-            letter: Letter::C,
-            octave: 1,
-        };
+        let note_1 = Note::new(Letter::C, 0);
+        let note_2 = Note::new(Letter::E, 0);
+        let note_3 = Note::new(Letter::E, 1);
+        let note_4 = Note::new(Letter::B, 0);
+        let note_5 = Note::new(Letter::C, 1);
         assert_eq!(note_1.dist_to(&note_2), 4);
         assert_eq!(note_2.dist_to(&note_1), 4);
         assert_eq!(note_1.dist_to(&note_3), 16);
@@ -0,0 +1,553 @@
+//! Pitch class sets of the twelve tone keyboard, after Forte
+
+use crate::music::chord::Chord;
+use crate::music::common::{find_letter_idx, Letter, KEYBOARD};
+use crate::music::interval::Interval;
+use crate::music::note::Note;
+use crate::music::scale::Scale;
+use itertools::Itertools;
+use std::fmt;
+
+/// Forte names and prime forms of the set classes up to hexachords,
+/// larger ones being named after their complement. Ten and eleven are written T and E.
+const FORTE: [(&str, &str); 137] = [
+    ("0-1", ""),
+    ("1-1", "0"),
+    ("2-1", "01"),
+    ("2-2", "02"),
+    ("2-3", "03"),
+    ("2-4", "04"),
+    ("2-5", "05"),
+    ("2-6", "06"),
+    ("3-1", "012"),
+    ("3-2", "013"),
+    ("3-3", "014"),
+    ("3-4", "015"),
+    ("3-5", "016"),
+    ("3-6", "024"),
+    ("3-7", "025"),
+    ("3-8", "026"),
+    ("3-9", "027"),
+    ("3-10", "036"),
+    ("3-11", "037"),
+    ("3-12", "048"),
+    ("4-1", "0123"),
+    ("4-2", "0124"),
+    ("4-3", "0134"),
+    ("4-4", "0125"),
+    ("4-5", "0126"),
+    ("4-6", "0127"),
+    ("4-7", "0145"),
+    ("4-8", "0156"),
+    ("4-9", "0167"),
+    ("4-10", "0235"),
+    ("4-11", "0135"),
+    ("4-12", "0236"),
+    ("4-13", "0136"),
+    ("4-14", "0237"),
+    ("4-Z15", "0146"),
+    ("4-16", "0157"),
+    ("4-17", "0347"),
+    ("4-18", "0147"),
+    ("4-19", "0148"),
+    ("4-20", "0158"),
+    ("4-21", "0246"),
+    ("4-22", "0247"),
+    ("4-23", "0257"),
+    ("4-24", "0248"),
+    ("4-25", "0268"),
+    ("4-26", "0358"),
+    ("4-27", "0258"),
+    ("4-28", "0369"),
+    ("4-Z29", "0137"),
+    ("5-1", "01234"),
+    ("5-2", "01235"),
+    ("5-3", "01245"),
+    ("5-4", "01236"),
+    ("5-5", "01237"),
+    ("5-6", "01256"),
+    ("5-7", "01267"),
+    ("5-8", "02346"),
+    ("5-9", "01246"),
+    ("5-10", "01346"),
+    ("5-11", "02347"),
+    ("5-Z12", "01356"),
+    ("5-13", "01248"),
+    ("5-14", "01257"),
+    ("5-15", "01268"),
+    ("5-16", "01347"),
+    ("5-Z17", "01348"),
+    ("5-Z18", "01457"),
+    ("5-19", "01367"),
+    ("5-20", "01568"),
+    ("5-21", "01458"),
+    ("5-22", "01478"),
+    ("5-23", "02357"),
+    ("5-24", "01357"),
+    ("5-25", "02358"),
+    ("5-26", "02458"),
+    ("5-27", "01358"),
+    ("5-28", "02368"),
+    ("5-29", "01368"),
+    ("5-30", "01468"),
+    ("5-31", "01369"),
+    ("5-32", "01469"),
+    ("5-33", "02468"),
+    ("5-34", "02469"),
+    ("5-35", "02479"),
+    ("5-Z36", "01247"),
+    ("5-Z37", "03458"),
+    ("5-Z38", "01258"),
+    ("6-1", "012345"),
+    ("6-2", "012346"),
+    ("6-Z3", "012356"),
+    ("6-Z4", "012456"),
+    ("6-5", "012367"),
+    ("6-Z6", "012567"),
+    ("6-7", "012678"),
+    ("6-8", "023457"),
+    ("6-9", "012357"),
+    ("6-Z10", "013457"),
+    ("6-Z11", "012457"),
+    ("6-Z12", "012467"),
+    ("6-Z13", "013467"),
+    ("6-14", "013458"),
+    ("6-15", "012458"),
+    ("6-16", "014568"),
+    ("6-Z17", "012478"),
+    ("6-18", "012578"),
+    ("6-Z19", "013478"),
+    ("6-20", "014589"),
+    ("6-21", "023468"),
+    ("6-22", "012468"),
+    ("6-Z23", "023568"),
+    ("6-Z24", "013468"),
+    ("6-Z25", "013568"),
+    ("6-Z26", "013578"),
+    ("6-27", "013469"),
+    ("6-Z28", "013569"),
+    ("6-Z29", "013689"),
+    ("6-30", "013679"),
+    ("6-31", "014579"),
+    ("6-32", "024579"),
+    ("6-33", "023579"),
+    ("6-34", "013579"),
+    ("6-35", "02468T"),
+    ("6-Z36", "012347"),
+    ("6-Z37", "012348"),
+    ("6-Z38", "012378"),
+    ("6-Z39", "023458"),
+    ("6-Z40", "012358"),
+    ("6-Z41", "012368"),
+    ("6-Z42", "012369"),
+    ("6-Z43", "012568"),
+    ("6-Z44", "012569"),
+    ("6-Z45", "023469"),
+    ("6-Z46", "012469"),
+    ("6-Z47", "012479"),
+    ("6-Z48", "012579"),
+    ("6-Z49", "013479"),
+    ("6-Z50", "014679"),
+];
+
+/// A set of pitch classes, numbered from 0 for C as in `KEYBOARD`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PitchClassSet {
+    /// Bit n set for pitch class n
+    bits: u16,
+}
+
+impl PitchClassSet {
+    /// Creates a `PitchClassSet` from pitch classes, taken modulo 12.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::pitch_class_set::PitchClassSet;
+    /// let set = PitchClassSet::new(&[7, 4, 12]);
+    /// assert_eq!(set.pitch_classes(), vec![0, 4, 7]);
+    /// ```
+    pub fn new(pitch_classes: &[u8]) -> Self {
+        let bits = pitch_classes
+            .iter()
+            .fold(0, |bits, pc| bits | 1 << (pc % 12));
+        PitchClassSet { bits }
+    }
+
+    /// Creates a `PitchClassSet` from keyboard letters
+    pub fn from_letters(letters: &[Letter]) -> Self {
+        let pitch_classes = letters
+            .iter()
+            .map(|l| find_letter_idx(*l) as u8)
+            .collect_vec();
+        Self::new(&pitch_classes)
+    }
+
+    /// Set class of a Forte name like `4-Z15`, in prime form
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::pitch_class_set::PitchClassSet;
+    /// let set = PitchClassSet::from_forte("7-35").unwrap();
+    /// assert_eq!(set.pitch_classes(), vec![0, 1, 3, 5, 6, 8, 10]);
+    /// assert!(PitchClassSet::from_forte("4-30").is_none());
+    /// ```
+    pub fn from_forte(name: &str) -> Option<Self> {
+        forte_table()
+            .into_iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, set)| set)
+    }
+
+    /// Pitch classes in ascending order
+    pub fn pitch_classes(&self) -> Vec<u8> {
+        (0..12).filter(|pc| self.contains(*pc)).collect()
+    }
+
+    /// Keyboard letters in ascending order
+    pub fn letters(&self) -> Vec<Letter> {
+        self.pitch_classes()
+            .iter()
+            .map(|pc| KEYBOARD[*pc as usize])
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    pub fn contains(&self, pitch_class: u8) -> bool {
+        self.bits & 1 << (pitch_class % 12) != 0
+    }
+
+    /// Transposition Tn by `n` semitones
+    pub fn transpose(&self, n: u8) -> Self {
+        let n = n % 12;
+        let bits = (self.bits << n | self.bits >> (12 - n)) & 0xFFF;
+        PitchClassSet { bits }
+    }
+
+    /// Inversion followed by a transposition, TnI, sending each pitch class x to n - x
+    pub fn invert(&self, n: u8) -> Self {
+        let inverted = self
+            .pitch_classes()
+            .iter()
+            .map(|pc| (12 + n % 12 - pc) % 12)
+            .collect_vec();
+        Self::new(&inverted)
+    }
+
+    /// Pitch classes not in the set
+    pub fn complement(&self) -> Self {
+        PitchClassSet {
+            bits: !self.bits & 0xFFF,
+        }
+    }
+
+    pub fn is_subset(&self, other: &PitchClassSet) -> bool {
+        self.bits & other.bits == self.bits
+    }
+
+    pub fn is_superset(&self, other: &PitchClassSet) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Most packed rotation of the pitch classes, the smallest first one breaking ties.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::pitch_class_set::PitchClassSet;
+    /// let set = PitchClassSet::new(&[7, 11, 2, 5]); // G7
+    /// assert_eq!(set.normal_form(), vec![11, 2, 5, 7]);
+    /// ```
+    pub fn normal_form(&self) -> Vec<u8> {
+        let pitch_classes = self.pitch_classes();
+        (0..pitch_classes.len())
+            .map(|i| {
+                let mut rotation = pitch_classes.clone();
+                rotation.rotate_left(i);
+                rotation
+            })
+            .min_by_key(|rotation| (packing(rotation), rotation[0]))
+            .unwrap_or_default()
+    }
+
+    /// Prime form, the most packed normal form of the set or its inversion starting on 0.
+    ///
+    /// Packing is compared from the right after Rahn, so that a few set classes like 5-20
+    /// differ from Forte's own prime forms.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::pitch_class_set::PitchClassSet;
+    /// let major = PitchClassSet::new(&[0, 4, 7]);
+    /// assert_eq!(major.prime_form().pitch_classes(), vec![0, 3, 7]);
+    /// ```
+    pub fn prime_form(&self) -> Self {
+        [*self, self.invert(0)]
+            .iter()
+            .map(|set| {
+                let normal = set.normal_form();
+                let first = normal.first().copied().unwrap_or(0);
+                normal.iter().map(|pc| (pc + 12 - first) % 12).collect_vec()
+            })
+            .min_by_key(|form| packing(form))
+            .map(|form| Self::new(&form))
+            .unwrap()
+    }
+
+    /// Number of occurrences of each interval class, from minor seconds to tritones
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::pitch_class_set::PitchClassSet;
+    /// let diatonic = PitchClassSet::new(&[0, 2, 4, 5, 7, 9, 11]);
+    /// assert_eq!(diatonic.interval_vector(), [2, 5, 4, 3, 6, 1]);
+    /// ```
+    pub fn interval_vector(&self) -> [u8; 6] {
+        let mut vector = [0; 6];
+        for (a, b) in self.pitch_classes().iter().tuple_combinations() {
+            let interval = (b - a).min(12 - (b - a));
+            vector[interval as usize - 1] += 1;
+        }
+        vector
+    }
+
+    /// Forte name of the set class
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::pitch_class_set::PitchClassSet;
+    /// let dominant_seventh = PitchClassSet::new(&[7, 11, 2, 5]);
+    /// assert_eq!(dominant_seventh.forte_name(), "4-27");
+    /// ```
+    pub fn forte_name(&self) -> String {
+        let prime = self.prime_form();
+        forte_table()
+            .into_iter()
+            .find(|(_, set)| *set == prime)
+            .map(|(name, _)| name)
+            .unwrap()
+    }
+
+    /// Prime forms of the other set classes of the same size sharing the interval vector
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::pitch_class_set::PitchClassSet;
+    /// let all_interval = PitchClassSet::from_forte("4-Z15").unwrap();
+    /// let related = all_interval.z_related();
+    /// assert_eq!(related[0].forte_name(), "4-Z29");
+    /// ```
+    pub fn z_related(&self) -> Vec<Self> {
+        let (prime, vector) = (self.prime_form(), self.interval_vector());
+        forte_table()
+            .into_iter()
+            .map(|(_, set)| set)
+            .filter(|set| set.len() == prime.len() && *set != prime)
+            .filter(|set| set.interval_vector() == vector)
+            .collect()
+    }
+
+    /// Chord of the normal form, ascending from `octave`
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::pitch_class_set::PitchClassSet;
+    /// let chord = PitchClassSet::new(&[0, 4, 9]).to_chord(4);
+    /// assert_eq!(chord.to_string(), "Chord(Note(A4),Note(C5),Note(E5))");
+    /// ```
+    pub fn to_chord(&self, octave: i8) -> Chord {
+        let normal = self.normal_form();
+        let first = normal.first().copied().unwrap_or(0);
+        let notes = normal
+            .iter()
+            .map(|pc| {
+                let octave = octave + (pc < &first) as i8;
+                Note::new(KEYBOARD[*pc as usize], octave)
+            })
+            .collect();
+        Chord::new(notes)
+    }
+
+    /// Scale on the `root` pitch class, which should belong to the set, in `octave`
+    pub fn to_scale(&self, root: u8, octave: i8) -> Scale {
+        let intervals = self
+            .transpose(12 - root % 12)
+            .pitch_classes()
+            .iter()
+            .map(|pc| Interval::from_semitones(*pc))
+            .collect();
+        Scale::new(Note::new(KEYBOARD[root as usize % 12], octave), intervals)
+    }
+}
+
+/// Packing of a pitch class sequence, distances from the first one compared from the last
+fn packing(pitch_classes: &[u8]) -> Vec<u8> {
+    let first = pitch_classes.first().copied().unwrap_or(0);
+    pitch_classes
+        .iter()
+        .rev()
+        .map(|pc| (pc + 12 - first) % 12)
+        .collect()
+}
+
+/// Every set class with its Forte name, complements included
+fn forte_table() -> Vec<(String, PitchClassSet)> {
+    let parse = |form: &str| {
+        let pitch_classes = form
+            .chars()
+            .map(|c| match c {
+                'T' => 10,
+                'E' => 11,
+                c => c.to_digit(10).unwrap() as u8,
+            })
+            .collect_vec();
+        PitchClassSet::new(&pitch_classes).prime_form()
+    };
+    let sets = FORTE
+        .iter()
+        .map(|(name, form)| (name.to_string(), parse(form)))
+        .collect_vec();
+    let complements = sets
+        .iter()
+        .filter(|(_, set)| set.len() < 6)
+        .map(|(name, set)| {
+            let (cardinality, number) = name.split_once('-').unwrap();
+            let cardinality = 12 - cardinality.parse::<usize>().unwrap();
+            let name = format!("{}-{}", cardinality, number);
+            (name, set.complement().prime_form())
+        })
+        .collect_vec();
+    sets.into_iter().chain(complements).collect()
+}
+
+/// Set of the pitch classes of the notes
+impl From<&Chord> for PitchClassSet {
+    fn from(chord: &Chord) -> Self {
+        let letters = chord.notes.iter().map(|n| n.letter).collect_vec();
+        Self::from_letters(&letters)
+    }
+}
+
+/// Set of the pitch classes of the scale notes
+impl From<&Scale> for PitchClassSet {
+    fn from(scale: &Scale) -> Self {
+        let letters = scale.notes().iter().map(|n| n.letter).collect_vec();
+        Self::from_letters(&letters)
+    }
+}
+
+/// Displays a `PitchClassSet` like `{0,4,7,10}`
+impl fmt::Display for PitchClassSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{{}}}", self.pitch_classes().iter().join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(pitch_classes: &[u8]) -> PitchClassSet {
+        PitchClassSet::new(pitch_classes)
+    }
+
+    #[test]
+    fn forte_table_is_complete() {
+        let table = forte_table();
+        // Every set class appears once, Z in its name when it shares its interval vector
+        for bits in 0..1 << 12 {
+            let prime = PitchClassSet { bits }.prime_form();
+            let names = table.iter().filter(|(_, s)| *s == prime).collect_vec();
+            assert_eq!(names.len(), 1, "{}", prime);
+            let z = table.iter().any(|(_, s)| {
+                s.len() == prime.len()
+                    && *s != prime
+                    && s.interval_vector() == prime.interval_vector()
+            });
+            assert_eq!(names[0].0.contains('Z'), z, "{}", names[0].0);
+        }
+        assert_eq!(table.len(), 224);
+    }
+
+    #[test]
+    fn operations() {
+        let c_major = set(&[0, 4, 7]);
+        assert_eq!(c_major.transpose(5), set(&[5, 9, 0]));
+        assert_eq!(c_major.invert(0), set(&[0, 8, 5]));
+        assert_eq!(c_major.invert(7), set(&[7, 3, 0]));
+        assert_eq!(c_major.complement().len(), 9);
+        assert!(c_major.is_subset(&set(&[0, 2, 4, 5, 7, 9, 11])));
+        assert!(set(&[0, 2, 4, 5, 7, 9, 11]).is_superset(&c_major));
+        assert!(!c_major.is_subset(&set(&[0, 4])));
+        assert_eq!(c_major.to_string(), "{0,4,7}");
+        assert_eq!(set(&[]).normal_form(), Vec::<u8>::new());
+        assert_eq!(set(&[]).forte_name(), "0-1");
+    }
+
+    #[test]
+    fn set_classes() {
+        assert_eq!(set(&[0, 4, 8]).forte_name(), "3-12");
+        assert_eq!(set(&[0, 3, 6, 9]).normal_form(), vec![0, 3, 6, 9]);
+        assert_eq!(set(&[8, 0, 1, 4, 5]).normal_form(), vec![0, 1, 4, 5, 8]);
+        // Forte's 5-20 prime form is 01378, packed to the left
+        assert_eq!(set(&[0, 1, 3, 7, 8]).prime_form(), set(&[0, 1, 5, 6, 8]));
+        assert_eq!(set(&[0, 1, 3, 7, 8]).forte_name(), "5-20");
+        assert_eq!(set(&[0, 2, 4, 5, 7, 9, 11]).forte_name(), "7-35");
+        assert_eq!(set(&[0, 2, 4, 6, 8, 10]).forte_name(), "6-35");
+        assert_eq!(set(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]).forte_name(), "10-1");
+        assert_eq!(
+            PitchClassSet::from_forte("6-Z29").unwrap().z_related()[0].forte_name(),
+            "6-Z50"
+        );
+    }
+
+    #[test]
+    fn conversions() {
+        let chord = Chord::from(vec!["D4", "F#4", "A4", "C5"]);
+        let set = PitchClassSet::from(&chord);
+        assert_eq!(set.pitch_classes(), vec![0, 2, 6, 9]);
+        assert_eq!(
+            set.to_chord(3).to_string(),
+            "Chord(Note(Gb3),Note(A3),Note(C4),Note(D4))"
+        );
+        let scale = Scale::minor_harmonic(Note::try_from("A4").unwrap());
+        let set = PitchClassSet::from(&scale);
+        assert_eq!(set.forte_name(), "7-32");
+        let notes = set
+            .to_scale(9, 4)
+            .notes()
+            .iter()
+            .map(|n| n.letter)
+            .collect_vec();
+        assert_eq!(notes, scale.notes().iter().map(|n| n.letter).collect_vec());
+    }
+}
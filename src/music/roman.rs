@@ -0,0 +1,624 @@
+//! Roman numerals, chords named after their function in a key
+
+use crate::error::{Error, Result};
+use crate::music::chord::Chord;
+use crate::music::common::find_letter_idx;
+use crate::music::interval::Interval;
+use crate::music::key::{Key, Mode};
+use crate::music::note::Note;
+use itertools::Itertools;
+use std::fmt;
+
+/// Numerals of the seven degrees
+const NUMERALS: [&str; 7] = ["I", "II", "III", "IV", "V", "VI", "VII"];
+
+/// Figures of the inversions of triads and seventh chords
+const TRIAD_FIGURES: [&str; 3] = ["", "6", "6/4"];
+const SEVENTH_FIGURES: [&str; 4] = ["7", "6/5", "4/3", "4/2"];
+
+/// Quality of the chord a numeral stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChordQuality {
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    Dominant7,
+    Major7,
+    Minor7,
+    MinorMajor7,
+    HalfDiminished7,
+    Diminished7,
+    AugmentedMajor7,
+    /// Italian augmented sixth, built on the lowered sixth degree
+    Italian,
+    /// French augmented sixth, built on the lowered sixth degree
+    French,
+    /// German augmented sixth, built on the lowered sixth degree
+    German,
+}
+
+impl ChordQuality {
+    const ALL: [ChordQuality; 14] = [
+        ChordQuality::Major,
+        ChordQuality::Minor,
+        ChordQuality::Diminished,
+        ChordQuality::Augmented,
+        ChordQuality::Dominant7,
+        ChordQuality::Major7,
+        ChordQuality::Minor7,
+        ChordQuality::MinorMajor7,
+        ChordQuality::HalfDiminished7,
+        ChordQuality::Diminished7,
+        ChordQuality::AugmentedMajor7,
+        ChordQuality::Italian,
+        ChordQuality::French,
+        ChordQuality::German,
+    ];
+
+    /// Intervals of the chord tones from the root
+    pub fn intervals(&self) -> Vec<Interval> {
+        let (third, fifth, seventh) = match self {
+            ChordQuality::Major => (Interval::MAJOR_THIRD, Interval::FIFTH, None),
+            ChordQuality::Minor => (Interval::MINOR_THIRD, Interval::FIFTH, None),
+            ChordQuality::Diminished => (Interval::MINOR_THIRD, Interval::DIMINISHED_FIFTH, None),
+            ChordQuality::Augmented => (Interval::MAJOR_THIRD, Interval::AUGMENTED_FIFTH, None),
+            ChordQuality::Dominant7 => (
+                Interval::MAJOR_THIRD,
+                Interval::FIFTH,
+                Some(Interval::MINOR_SEVENTH),
+            ),
+            ChordQuality::Major7 => (
+                Interval::MAJOR_THIRD,
+                Interval::FIFTH,
+                Some(Interval::MAJOR_SEVENTH),
+            ),
+            ChordQuality::Minor7 => (
+                Interval::MINOR_THIRD,
+                Interval::FIFTH,
+                Some(Interval::MINOR_SEVENTH),
+            ),
+            ChordQuality::MinorMajor7 => (
+                Interval::MINOR_THIRD,
+                Interval::FIFTH,
+                Some(Interval::MAJOR_SEVENTH),
+            ),
+            ChordQuality::HalfDiminished7 => (
+                Interval::MINOR_THIRD,
+                Interval::DIMINISHED_FIFTH,
+                Some(Interval::MINOR_SEVENTH),
+            ),
+            ChordQuality::Diminished7 => (
+                Interval::MINOR_THIRD,
+                Interval::DIMINISHED_FIFTH,
+                Some(Interval::DIMINISHED_SEVENTH),
+            ),
+            ChordQuality::AugmentedMajor7 => (
+                Interval::MAJOR_THIRD,
+                Interval::AUGMENTED_FIFTH,
+                Some(Interval::MAJOR_SEVENTH),
+            ),
+            ChordQuality::Italian => {
+                return vec![
+                    Interval::UNISON,
+                    Interval::MAJOR_THIRD,
+                    Interval::AUGMENTED_SIXTH,
+                ]
+            }
+            ChordQuality::French => {
+                return vec![
+                    Interval::UNISON,
+                    Interval::MAJOR_THIRD,
+                    Interval::AUGMENTED_FOURTH,
+                    Interval::AUGMENTED_SIXTH,
+                ]
+            }
+            ChordQuality::German => {
+                return vec![
+                    Interval::UNISON,
+                    Interval::MAJOR_THIRD,
+                    Interval::FIFTH,
+                    Interval::AUGMENTED_SIXTH,
+                ]
+            }
+        };
+        [Interval::UNISON, third, fifth]
+            .into_iter()
+            .chain(seventh)
+            .collect()
+    }
+
+    /// Upper case numerals stand for chords with a major third
+    fn is_major(&self) -> bool {
+        self.intervals()[1] == Interval::MAJOR_THIRD
+    }
+
+    fn is_diminished(&self) -> bool {
+        matches!(
+            self,
+            ChordQuality::Diminished | ChordQuality::HalfDiminished7 | ChordQuality::Diminished7
+        )
+    }
+
+    fn is_augmented_sixth(&self) -> bool {
+        matches!(
+            self,
+            ChordQuality::Italian | ChordQuality::French | ChordQuality::German
+        )
+    }
+
+    fn has_seventh(&self) -> bool {
+        !self.is_augmented_sixth() && self.intervals().len() == 4
+    }
+}
+
+/// A chord named after the degree of its root in a key, like `V6/5`, `bIII` or `V7/V`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomanNumeral {
+    /// Degree of the root in the key scale, from 1 to 7
+    pub degree: u8,
+    /// Semitones altering the root from the key scale, -1 for `bVI` in a major key
+    pub alteration: i8,
+    pub quality: ChordQuality,
+    /// Chord tone in the bass, 0 for the root position
+    pub inversion: u8,
+    /// Chord tonicized by a secondary chord, `V` in `V7/V`
+    pub secondary: Option<Box<RomanNumeral>>,
+}
+
+impl RomanNumeral {
+    /// Creates a root position `RomanNumeral` on a diatonic `degree`
+    pub fn new(degree: u8, quality: ChordQuality) -> Self {
+        RomanNumeral {
+            degree,
+            alteration: 0,
+            quality,
+            inversion: 0,
+            secondary: None,
+        }
+    }
+
+    /// Builds the chord in `key`, its tonic in octave 4.
+    ///
+    /// Minor keys use the natural minor scale, the leading tone being raised
+    /// for diminished chords on the seventh degree.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::key::Key;
+    /// use mumuse::music::roman::RomanNumeral;
+    /// let key = Key::try_from("C major").unwrap();
+    /// let chord = RomanNumeral::try_from("V6/5/V").unwrap().realize(&key);
+    /// assert_eq!(chord.to_string(), "Chord(Note(F#5),Note(A5),Note(C6),Note(D6))");
+    /// ```
+    pub fn realize(&self, key: &Key) -> Chord {
+        let root = self.root(key);
+        let notes = self.quality.intervals().iter().map(|i| root + *i).collect();
+        Chord::new(notes).invert(self.inversion as usize)
+    }
+
+    /// Root of the chord in `key`
+    fn root(&self, key: &Key) -> Note {
+        if let Some(target) = &self.secondary {
+            let mode = if target.quality.is_major() {
+                Mode::Major
+            } else {
+                Mode::Minor
+            };
+            let tonicized = Key::new(target.root(key).spelling(), mode);
+            let numeral = RomanNumeral {
+                secondary: None,
+                ..self.clone()
+            };
+            return numeral.root(&tonicized);
+        }
+        let scale = key.scale(4);
+        if self.quality.is_augmented_sixth() {
+            return scale.root + Interval::MINOR_SIXTH;
+        }
+        let natural = scale.notes()[(self.degree as usize + 6) % 7];
+        let leading_tone =
+            key.mode == Mode::Minor && self.degree == 7 && self.quality.is_diminished();
+        natural.transpose((self.alteration + leading_tone as i8) as i32, 0)
+    }
+
+    /// Pitch classes of the chord in `key`, from the root
+    fn pitch_classes(&self, key: &Key) -> Vec<i32> {
+        let root = self.root(key);
+        self.quality
+            .intervals()
+            .iter()
+            .map(|i| pitch_class(&(root + *i)))
+            .collect()
+    }
+}
+
+fn pitch_class(note: &Note) -> i32 {
+    find_letter_idx(note.letter) as i32
+}
+
+/// Quality of the triad on a `degree` of the `key` scale, with a leading tone in minor
+fn diatonic_triad(key: &Key, degree: u8) -> ChordQuality {
+    match (key.mode, degree) {
+        (Mode::Minor, 5) => return ChordQuality::Major,
+        (Mode::Minor, 7) => return ChordQuality::Diminished,
+        _ => (),
+    }
+    let notes = key.scale(4).by_degree(degree as usize, 3).notes;
+    let third = (notes[1] - notes[0]).semitones();
+    let fifth = (notes[2] - notes[0]).semitones();
+    match (third, fifth) {
+        (4, 8) => ChordQuality::Augmented,
+        (4, _) => ChordQuality::Major,
+        (_, 6) => ChordQuality::Diminished,
+        _ => ChordQuality::Minor,
+    }
+}
+
+/// Roman numeral of `chord` in `key`, see `Key::analyze`
+pub(crate) fn analyze(key: &Key, chord: &Chord) -> Option<RomanNumeral> {
+    let classes = chord.notes.iter().map(pitch_class).unique().collect_vec();
+    let bass = chord
+        .notes
+        .iter()
+        .min_by_key(|n| (n.octave, pitch_class(n)))?;
+    let spellings = chord.notes.iter().map(|n| n.spelling()).collect_vec();
+    let scale = key.scale(4);
+    let tonic = pitch_class(&scale.root);
+    let mut diatonic = scale.notes().iter().map(pitch_class).collect_vec();
+    diatonic.push((tonic + 11) % 12);
+
+    // Chord tones spelled as in the chord
+    let agreement = |numeral: &RomanNumeral| {
+        numeral
+            .realize(key)
+            .notes
+            .iter()
+            .filter(|n| spellings.contains(&n.spelling()))
+            .count()
+    };
+
+    let mut candidates = vec![];
+    for quality in ChordQuality::ALL {
+        let roots = if quality.is_augmented_sixth() {
+            vec![(tonic + 8) % 12]
+        } else {
+            classes.clone()
+        };
+        for root in roots {
+            let intervals = quality.intervals();
+            let tones = intervals
+                .iter()
+                .map(|i| (root + i.semitones()).rem_euclid(12))
+                .collect_vec();
+            // The fifth of seventh chords may be left out
+            let sounding = |tone: &i32| classes.contains(tone);
+            let without_fifth = quality.has_seventh()
+                && [0, 1, 3].iter().all(|i| sounding(&tones[*i]))
+                && classes.len() == 3;
+            let complete = tones.iter().all(sounding) || without_fifth;
+            if !complete || classes.iter().any(|c| !tones.contains(c)) || !sounding(&root) {
+                continue;
+            }
+            let inversion = tones.iter().position(|t| *t == pitch_class(bass))? as u8;
+
+            if quality.is_augmented_sixth() {
+                let numeral = RomanNumeral {
+                    inversion,
+                    ..RomanNumeral::new(6, quality)
+                };
+                let agreeing = agreement(&numeral);
+                candidates.push(((std::cmp::Reverse(agreeing), 1, inversion), numeral));
+                continue;
+            }
+
+            // Chromatic numerals on every degree, then secondary chords of diatonic triads
+            let mut numerals = (1..=7)
+                .flat_map(|degree| {
+                    (-2..=2).map(move |alteration| RomanNumeral {
+                        degree,
+                        alteration,
+                        quality,
+                        inversion,
+                        secondary: None,
+                    })
+                })
+                .collect_vec();
+            for target in 2..=7 {
+                let target_quality = diatonic_triad(key, target);
+                if !matches!(target_quality, ChordQuality::Major | ChordQuality::Minor) {
+                    continue;
+                }
+                let degree = match quality {
+                    ChordQuality::Major | ChordQuality::Dominant7 => 5,
+                    _ if quality.is_diminished() => 7,
+                    _ => continue,
+                };
+                {
+                    numerals.push(RomanNumeral {
+                        degree,
+                        alteration: 0,
+                        quality,
+                        inversion,
+                        secondary: Some(Box::new(RomanNumeral::new(target, target_quality))),
+                    });
+                }
+            }
+
+            for numeral in numerals {
+                if numeral.pitch_classes(key) != tones {
+                    continue;
+                }
+                let chromatic = !tones.iter().all(|t| diatonic.contains(t));
+                let cost: u8 = match (&numeral.secondary, chromatic) {
+                    (None, false) => 0,
+                    (Some(_), _) => 1,
+                    (None, true) => 2,
+                } + numeral.alteration.unsigned_abs();
+                let agreeing = agreement(&numeral);
+                candidates.push(((std::cmp::Reverse(agreeing), cost, inversion), numeral));
+            }
+        }
+    }
+    candidates
+        .into_iter()
+        .min_by_key(|(rank, _)| *rank)
+        .map(|(_, numeral)| numeral)
+}
+
+/// Displays a `RomanNumeral` with its quality, figures and secondary function, like `viiø4/3/V`
+impl fmt::Display for RomanNumeral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let figure = if self.quality.has_seventh() {
+            SEVENTH_FIGURES[self.inversion as usize % 4]
+        } else {
+            TRIAD_FIGURES[self.inversion as usize % 3]
+        };
+        let neapolitan = self.degree == 2
+            && self.alteration == -1
+            && self.quality == ChordQuality::Major
+            && self.secondary.is_none();
+        match self.quality {
+            ChordQuality::Italian => write!(f, "It+6")?,
+            ChordQuality::French => write!(f, "Fr+6")?,
+            ChordQuality::German => write!(f, "Ger+6")?,
+            _ if neapolitan => write!(f, "N{}", figure)?,
+            quality => {
+                let accidental = match self.alteration {
+                    a if a < 0 => "b".repeat(a.unsigned_abs() as usize),
+                    a => "#".repeat(a as usize),
+                };
+                let numeral = NUMERALS[(self.degree as usize + 6) % 7];
+                let numeral = if quality.is_major() {
+                    numeral.to_string()
+                } else {
+                    numeral.to_lowercase()
+                };
+                let symbol = match quality {
+                    ChordQuality::Diminished | ChordQuality::Diminished7 => "°",
+                    ChordQuality::HalfDiminished7 => "ø",
+                    ChordQuality::Augmented => "+",
+                    ChordQuality::AugmentedMajor7 => "+maj",
+                    ChordQuality::Major7 | ChordQuality::MinorMajor7 => "maj",
+                    _ => "",
+                };
+                write!(f, "{}{}{}{}", accidental, numeral, symbol, figure)?
+            }
+        }
+        match &self.secondary {
+            Some(target) => write!(f, "/{}", target),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parses numerals like `ii7`, `V6/5`, `bVI`, `viio7/V`, `N6` or `Ger+6`
+impl TryFrom<&str> for RomanNumeral {
+    type Error = Error;
+    fn try_from(s: &str) -> Result<Self> {
+        let mut rest = s;
+        let position = |rest: &str| s.len() - rest.len();
+
+        let sharps = rest.bytes().take_while(|c| *c == b'#').count();
+        rest = &rest[sharps..];
+        let flats = rest.bytes().take_while(|c| *c == b'b').count();
+        rest = &rest[flats..];
+        if sharps + flats > 2 || (sharps > 0 && flats > 0) {
+            return Err(Error::parse(s, 0, "accidental"));
+        }
+
+        let mut numeral = if let Some(index) = eat(&mut rest, &["It", "Fr", "Ger"]) {
+            if eat(&mut rest, &["+6", "6"]).is_none() {
+                return Err(Error::parse(s, position(rest), "augmented sixth"));
+            }
+            let quality = [
+                ChordQuality::Italian,
+                ChordQuality::French,
+                ChordQuality::German,
+            ][index];
+            RomanNumeral::new(6, quality)
+        } else if eat(&mut rest, &["N"]).is_some() {
+            let inversion = eat(&mut rest, &["6/4", "64", "6"]).map_or(0, |i| [2, 2, 1][i]);
+            RomanNumeral {
+                alteration: -1,
+                inversion: inversion as u8,
+                ..RomanNumeral::new(2, ChordQuality::Major)
+            }
+        } else {
+            let upper = ["VII", "VI", "V", "IV", "III", "II", "I"];
+            let lower = ["vii", "vi", "v", "iv", "iii", "ii", "i"];
+            let (degree, major) = match (eat(&mut rest, &upper), eat(&mut rest, &lower)) {
+                (Some(index), _) => (7 - index as u8, true),
+                (_, Some(index)) => (7 - index as u8, false),
+                _ => return Err(Error::parse(s, position(rest), "numeral")),
+            };
+            let symbol = eat(&mut rest, &["°", "o", "ø", "+"]);
+            let maj = eat(&mut rest, &["maj"]).is_some();
+            let quality_position = position(rest);
+            let figure = eat(
+                &mut rest,
+                &[
+                    "7", "6/5", "65", "4/3", "43", "4/2", "42", "2", "6/4", "64", "6",
+                ],
+            );
+            let (seventh, inversion) = match figure {
+                Some(index @ 0..=7) => (true, [0, 1, 1, 2, 2, 3, 3, 3][index]),
+                Some(index) => (maj || symbol == Some(2), [2, 2, 1][index - 8]),
+                None => (maj || symbol == Some(2), 0),
+            };
+            let quality = match (major, symbol, maj, seventh) {
+                (true, None, false, false) => ChordQuality::Major,
+                (true, None, false, true) => ChordQuality::Dominant7,
+                (true, None, true, _) => ChordQuality::Major7,
+                (true, Some(3), false, false) => ChordQuality::Augmented,
+                (true, Some(3), true, _) => ChordQuality::AugmentedMajor7,
+                (false, None, false, false) => ChordQuality::Minor,
+                (false, None, false, true) => ChordQuality::Minor7,
+                (false, None, true, _) => ChordQuality::MinorMajor7,
+                (false, Some(0 | 1), false, false) => ChordQuality::Diminished,
+                (false, Some(0 | 1), false, true) => ChordQuality::Diminished7,
+                (false, Some(2), false, _) => ChordQuality::HalfDiminished7,
+                _ => return Err(Error::parse(s, quality_position, "chord quality")),
+            };
+            RomanNumeral {
+                alteration: sharps as i8 - flats as i8,
+                inversion,
+                ..RomanNumeral::new(degree, quality)
+            }
+        };
+
+        if let Some(target) = rest.strip_prefix('/') {
+            let target = RomanNumeral::try_from(target)
+                .map_err(|_| Error::parse(s, position(rest) + 1, "secondary numeral"))?;
+            numeral.secondary = Some(Box::new(target));
+        } else if !rest.is_empty() {
+            return Err(Error::parse(s, position(rest), "figure"));
+        }
+        Ok(numeral)
+    }
+}
+
+/// Removes the first of `tokens` starting `rest`, returning its index
+fn eat(rest: &mut &str, tokens: &[&str]) -> Option<usize> {
+    let index = tokens.iter().position(|token| rest.starts_with(token))?;
+    *rest = &rest[tokens[index].len()..];
+    Some(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analyze(key: &str, notes: &[&str]) -> String {
+        let key = Key::try_from(key).unwrap();
+        key.analyze(&Chord::from(notes.to_vec()))
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn diatonic_analysis() {
+        let c = Note::try_from("C4").unwrap();
+        let key = Key::try_from("C major").unwrap();
+        let scale = key.scale(4);
+        let progression = [scale.two(4), scale.five(4), scale.one(4)]
+            .iter()
+            .map(|chord| key.analyze(chord).unwrap().to_string())
+            .join(" ");
+        assert_eq!(progression, "ii7 V7 Imaj7");
+        assert_eq!(key.analyze(&Chord::new(vec![c])), None);
+
+        assert_eq!(analyze("C major", &["B3", "D4", "F4", "G4"]), "V6/5");
+        assert_eq!(analyze("C major", &["F3", "G3", "B3", "D4"]), "V4/2");
+        assert_eq!(analyze("C major", &["G3", "C4", "E4"]), "I6/4");
+        assert_eq!(analyze("C major", &["G3", "B3", "F4"]), "V7");
+        assert_eq!(analyze("C major", &["B3", "D4", "F4", "A4"]), "viiø7");
+        assert_eq!(analyze("A minor", &["G#3", "B3", "D4", "F4"]), "vii°7");
+        assert_eq!(analyze("A minor", &["E3", "G#3", "B3"]), "V");
+        assert_eq!(analyze("A minor", &["C4", "E4", "G#4"]), "III+");
+    }
+
+    #[test]
+    fn chromatic_analysis() {
+        assert_eq!(analyze("C major", &["D4", "F#4", "A4", "C5"]), "V7/V");
+        assert_eq!(analyze("C major", &["C#4", "E4", "G4", "Bb4"]), "vii°7/ii");
+        assert_eq!(analyze("C major", &["E4", "G#4", "B4"]), "V/vi");
+        assert_eq!(analyze("C major", &["Eb4", "G4", "Bb4"]), "bIII");
+        assert_eq!(analyze("C major", &["Ab3", "C4", "Eb4"]), "bVI");
+        assert_eq!(analyze("C major", &["F4", "Ab4", "C5"]), "iv");
+        assert_eq!(analyze("C minor", &["F3", "Ab3", "Db4"]), "N6");
+        assert_eq!(analyze("C major", &["Ab3", "C4", "Eb4", "F#4"]), "Ger+6");
+        assert_eq!(analyze("C major", &["Ab3", "C4", "D4", "F#4"]), "Fr+6");
+        assert_eq!(analyze("A minor", &["F3", "A3", "D#4"]), "It+6");
+        assert_eq!(analyze("C major", &["Ab3", "C4", "Eb4", "Gb4"]), "bVI7");
+    }
+
+    #[test]
+    fn realization() {
+        let realize = |key: &str, numeral: &str| {
+            let key = Key::try_from(key).unwrap();
+            RomanNumeral::try_from(numeral)
+                .unwrap()
+                .realize(&key)
+                .notes
+                .iter()
+                .map(|n| n.to_string())
+                .join(" ")
+        };
+        assert_eq!(
+            realize("C major", "ii7"),
+            "Note(D4) Note(F4) Note(A4) Note(C5)"
+        );
+        assert_eq!(
+            realize("Eb major", "V4/3"),
+            "Note(F5) Note(Ab5) Note(Bb5) Note(D6)"
+        );
+        assert_eq!(
+            realize("E minor", "viio7"),
+            "Note(D#5) Note(F#5) Note(A5) Note(C6)"
+        );
+        assert_eq!(realize("E minor", "VII"), "Note(D5) Note(F#5) Note(A5)");
+        assert_eq!(realize("D major", "V/ii"), "Note(B4) Note(D#5) Note(F#5)");
+        assert_eq!(realize("G minor", "N6"), "Note(C5) Note(Eb5) Note(Ab5)");
+        assert_eq!(
+            realize("C major", "Fr+6"),
+            "Note(Ab4) Note(C5) Note(D5) Note(F#5)"
+        );
+
+        // Analysis of the realization gives the numeral back
+        let key = Key::try_from("F# minor").unwrap();
+        for numeral in [
+            "i", "iiø6/5", "V7", "viio4/2", "bII6", "iv6/4", "V6/V", "It+6",
+        ] {
+            let numeral = RomanNumeral::try_from(numeral).unwrap();
+            assert_eq!(key.analyze(&numeral.realize(&key)).unwrap(), numeral);
+        }
+    }
+
+    #[test]
+    fn parsing() {
+        let parse = |s| RomanNumeral::try_from(s).unwrap().to_string();
+        assert_eq!(parse("V65"), "V6/5");
+        assert_eq!(parse("viio7"), "vii°7");
+        assert_eq!(parse("viiø"), "viiø7");
+        assert_eq!(parse("Imaj7"), "Imaj7");
+        assert_eq!(parse("bVII"), "bVII");
+        assert_eq!(parse("bII6"), "N6");
+        assert_eq!(parse("V42/IV"), "V4/2/IV");
+        assert_eq!(parse("III+"), "III+");
+        assert!(RomanNumeral::try_from("X").is_err());
+        assert!(RomanNumeral::try_from("Io").is_err());
+        assert!(RomanNumeral::try_from("V9").is_err());
+        assert!(RomanNumeral::try_from("V/X").is_err());
+        assert!(RomanNumeral::try_from("#bVI").is_err());
+        assert!(RomanNumeral::try_from("bbbVI").is_err());
+        let accidentals = "#".repeat(200) + "IV";
+        assert!(matches!(
+            RomanNumeral::try_from(accidentals.as_str()),
+            Err(Error::Parse { position: 0, .. })
+        ));
+    }
+}
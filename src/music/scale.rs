@@ -4,10 +4,99 @@ use itertools::Itertools;
 use std::fmt;
 
 use crate::music::chord::Chord;
-use crate::music::common::Interval;
-use crate::music::common::Interval::*;
+use crate::music::common::{find_letter_idx, Letter, KEYBOARD};
+use crate::music::interval::Interval;
 use crate::music::note::Note;
 
+/// Catalogued scales, names first then intervals from the root
+const CATALOGUE: [(&[&str], &str); 45] = [
+    // Major modes
+    (&["major", "ionian"], "P1 M2 M3 P4 P5 M6 M7"),
+    (&["dorian"], "P1 M2 m3 P4 P5 M6 m7"),
+    (&["phrygian"], "P1 m2 m3 P4 P5 m6 m7"),
+    (&["lydian"], "P1 M2 M3 A4 P5 M6 M7"),
+    (&["mixolydian"], "P1 M2 M3 P4 P5 M6 m7"),
+    (
+        &["minor", "aeolian", "natural minor"],
+        "P1 M2 m3 P4 P5 m6 m7",
+    ),
+    (&["locrian"], "P1 m2 m3 P4 d5 m6 m7"),
+    // Melodic minor modes
+    (&["melodic minor", "jazz minor"], "P1 M2 m3 P4 P5 M6 M7"),
+    (&["dorian b2", "phrygian #6"], "P1 m2 m3 P4 P5 M6 m7"),
+    (&["lydian augmented"], "P1 M2 M3 A4 A5 M6 M7"),
+    (
+        &["lydian dominant", "acoustic", "overtone"],
+        "P1 M2 M3 A4 P5 M6 m7",
+    ),
+    (
+        &["mixolydian b6", "aeolian dominant"],
+        "P1 M2 M3 P4 P5 m6 m7",
+    ),
+    (&["locrian #2", "half diminished"], "P1 M2 m3 P4 d5 m6 m7"),
+    (&["altered", "super locrian"], "P1 m2 m3 d4 d5 m6 m7"),
+    // Harmonic minor modes
+    (&["harmonic minor"], "P1 M2 m3 P4 P5 m6 M7"),
+    (&["locrian #6"], "P1 m2 m3 P4 d5 M6 m7"),
+    (&["ionian #5"], "P1 M2 M3 P4 A5 M6 M7"),
+    (&["dorian #4", "ukrainian dorian"], "P1 M2 m3 A4 P5 M6 m7"),
+    (
+        &["phrygian dominant", "spanish gypsy"],
+        "P1 m2 M3 P4 P5 m6 m7",
+    ),
+    (&["lydian #2"], "P1 A2 M3 A4 P5 M6 M7"),
+    (
+        &["ultralocrian", "altered diminished"],
+        "P1 m2 m3 d4 d5 m6 d7",
+    ),
+    // Other heptatonic scales
+    (&["harmonic major"], "P1 M2 M3 P4 P5 m6 M7"),
+    (
+        &["double harmonic", "byzantine", "bhairav"],
+        "P1 m2 M3 P4 P5 m6 M7",
+    ),
+    (&["hungarian minor", "gypsy minor"], "P1 M2 m3 A4 P5 m6 M7"),
+    (&["hungarian major"], "P1 A2 M3 A4 P5 M6 m7"),
+    (&["neapolitan minor"], "P1 m2 m3 P4 P5 m6 M7"),
+    (&["neapolitan major"], "P1 m2 m3 P4 P5 M6 M7"),
+    (&["persian"], "P1 m2 M3 P4 d5 m6 M7"),
+    (&["enigmatic"], "P1 m2 M3 A4 A5 A6 M7"),
+    // Pentatonic and blues scales
+    (&["major pentatonic"], "P1 M2 M3 P5 M6"),
+    (&["minor pentatonic"], "P1 m3 P4 P5 m7"),
+    (&["blues", "minor blues"], "P1 m3 P4 d5 P5 m7"),
+    (&["major blues"], "P1 M2 m3 M3 P5 M6"),
+    // Bebop scales
+    (&["bebop dominant"], "P1 M2 M3 P4 P5 M6 m7 M7"),
+    (&["bebop major"], "P1 M2 M3 P4 P5 A5 M6 M7"),
+    (&["bebop dorian"], "P1 M2 m3 M3 P4 P5 M6 m7"),
+    // Symmetric scales
+    (&["whole tone"], "P1 M2 M3 A4 A5 A6"),
+    (
+        &["half-whole diminished", "dominant diminished"],
+        "P1 m2 A2 M3 A4 P5 M6 m7",
+    ),
+    (
+        &["whole-half diminished", "diminished"],
+        "P1 M2 m3 P4 d5 m6 M6 M7",
+    ),
+    (&["augmented"], "P1 A2 M3 P5 A5 M7"),
+    (&["chromatic"], "P1 m2 M2 m3 M3 P4 A4 P5 m6 M6 m7 M7"),
+    // Non western scales
+    (&["hirajoshi"], "P1 M2 m3 P5 m6"),
+    (&["in sen"], "P1 m2 P4 P5 m7"),
+    (&["iwato"], "P1 m2 P4 d5 m7"),
+    (&["kumoi"], "P1 M2 m3 P5 M6"),
+];
+
+/// Intervals of a catalogue entry
+fn catalogue_intervals(intervals: &str) -> Vec<Interval> {
+    intervals
+        .split(' ')
+        .map(|i| Interval::try_from(i).unwrap())
+        .collect()
+}
+
 /// A scale consists in a root Note and a vector of Intervals
 pub struct Scale {
     pub root: Note,
@@ -22,35 +111,164 @@ impl Scale {
 
     /// Major scale intervals
     pub const MAJOR: [Interval; 7] = [
-        Unison,
-        MajorSecond,
-        MajorThird,
-        Fourth,
-        Fifth,
-        MajorSixth,
-        MajorSeventh,
+        Interval::UNISON,
+        Interval::MAJOR_SECOND,
+        Interval::MAJOR_THIRD,
+        Interval::FOURTH,
+        Interval::FIFTH,
+        Interval::MAJOR_SIXTH,
+        Interval::MAJOR_SEVENTH,
     ];
     /// Minor (natural) scale intervals
     pub const MINOR: [Interval; 7] = [
-        Unison,
-        MajorSecond,
-        MinorThird,
-        Fourth,
-        Fifth,
-        MinorSixth,
-        MinorSeventh,
+        Interval::UNISON,
+        Interval::MAJOR_SECOND,
+        Interval::MINOR_THIRD,
+        Interval::FOURTH,
+        Interval::FIFTH,
+        Interval::MINOR_SIXTH,
+        Interval::MINOR_SEVENTH,
     ];
     /// Minor (harmonic) scale intervals
     pub const MINOR_HARMONIC: [Interval; 7] = [
-        Unison,
-        MajorSecond,
-        MinorThird,
-        Fourth,
-        Fifth,
-        MinorSixth,
-        MajorSeventh,
+        Interval::UNISON,
+        Interval::MAJOR_SECOND,
+        Interval::MINOR_THIRD,
+        Interval::FOURTH,
+        Interval::FIFTH,
+        Interval::MINOR_SIXTH,
+        Interval::MAJOR_SEVENTH,
     ];
 
+    /// Get a catalogued scale from its name, case and dashes not mattering.
+    ///
+    /// The catalogue holds modes of the major, melodic and harmonic minor scales,
+    /// pentatonic, blues, bebop and symmetric scales, along with some non western ones.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::scale::Scale;
+    /// use mumuse::music::note::Note;
+    /// let root = Note::try_from("C4").unwrap();
+    /// let scale = Scale::by_name("Lydian dominant", root).unwrap();
+    /// assert_eq!(scale.notes()[3].to_string(), "Note(F#4)");
+    /// assert!(Scale::by_name("unknown", root).is_none());
+    /// ```
+    pub fn by_name(name: &str, root: Note) -> Option<Self> {
+        let normalize = |name: &str| name.to_lowercase().replace(['-', '_'], " ");
+        let name = normalize(name);
+        CATALOGUE
+            .iter()
+            .find(|(names, _)| names.iter().any(|n| normalize(n) == name))
+            .map(|(_, intervals)| Self::new(root, catalogue_intervals(intervals)))
+    }
+
+    /// Every catalogued scale built on `root`
+    pub fn catalogue(root: Note) -> Vec<Self> {
+        CATALOGUE
+            .iter()
+            .map(|(_, intervals)| Self::new(root, catalogue_intervals(intervals)))
+            .collect()
+    }
+
+    /// Catalogued scales containing every note, ranked from the best fit.
+    ///
+    /// Scales with the fewest notes outside of the collection come first, then those whose
+    /// root is played, preferably as the lowest note.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::scale::Scale;
+    /// use mumuse::music::note::Note;
+    /// let notes: Vec<Note> = ["D4", "E4", "F4", "G4", "A4", "B4", "C5"]
+    ///     .iter()
+    ///     .map(|n| Note::try_from(*n).unwrap())
+    ///     .collect();
+    /// let candidates = Scale::candidates(&notes);
+    /// assert_eq!(candidates[0].root, notes[0]);
+    /// assert_eq!(candidates[0].name(), Some("dorian"));
+    /// ```
+    pub fn candidates(notes: &[Note]) -> Vec<Self> {
+        let pitch_class = |note: &Note| find_letter_idx(note.letter) as i32;
+        let classes = notes.iter().map(pitch_class).unique().collect_vec();
+        let lowest = match notes.iter().min_by_key(|n| (n.octave, pitch_class(n))) {
+            Some(note) => *note,
+            None => return vec![],
+        };
+
+        let mut candidates = vec![];
+        for (index, (_, intervals)) in CATALOGUE.iter().enumerate() {
+            let intervals = catalogue_intervals(intervals);
+            for root in 0..12i32 {
+                let tones = intervals
+                    .iter()
+                    .map(|i| (root + i.semitones()).rem_euclid(12))
+                    .collect_vec();
+                if !classes.iter().all(|c| tones.contains(c)) {
+                    continue;
+                }
+                // Spelled as played, when the root is
+                let played = notes.iter().find(|n| pitch_class(n) == root);
+                let root_note = match played {
+                    Some(note) => *note,
+                    None => Note::new(KEYBOARD[root as usize], lowest.octave),
+                };
+                let rank = (
+                    tones.len() - classes.len(),
+                    played.is_none(),
+                    pitch_class(&lowest) != root,
+                    index,
+                );
+                candidates.push((rank, Self::new(root_note, intervals.clone())));
+            }
+        }
+        candidates
+            .into_iter()
+            .sorted_by_key(|(rank, _)| *rank)
+            .map(|(_, scale)| scale)
+            .collect()
+    }
+
+    /// Name of the scale if catalogued, enharmonic intervals being accepted.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::scale::Scale;
+    /// use mumuse::music::note::Note;
+    /// let root = Note::try_from("C4").unwrap();
+    /// assert_eq!(Scale::major(root).mode(2).name(), Some("dorian"));
+    /// ```
+    pub fn name(&self) -> Option<&'static str> {
+        let semitones = |intervals: &[Interval]| {
+            intervals
+                .iter()
+                .map(|i| i.semitones().rem_euclid(12))
+                .sorted()
+                .collect_vec()
+        };
+        let entries = CATALOGUE
+            .iter()
+            .map(|(names, intervals)| (names[0], catalogue_intervals(intervals)))
+            .collect_vec();
+        entries
+            .iter()
+            .find(|(_, intervals)| *intervals == self.intervals)
+            .or_else(|| {
+                let own = semitones(&self.intervals);
+                entries.iter().find(|(_, i)| semitones(i) == own)
+            })
+            .map(|(name, _)| *name)
+    }
+
     /// Get mode n of current scale.
     ///
     /// The mode specifies the starting point of the sequence
@@ -65,37 +283,21 @@ impl Scale {
     /// use mumuse::music::note::Note;
     /// let root = Note::try_from("C0").unwrap();
     /// let major_scale = Scale::major(root);
-    /// let ionian = major_scale.mode(1); 
+    /// let ionian = major_scale.mode(1);
     /// ```
     pub fn mode(&self, n: i8) -> Self {
-        let mut mode = n as i8 - 1;
-        while mode < 0 {
-            // wrap around invalid modes identifiers
-            mode += self.intervals.len() as i8;
+        let len = self.intervals.len();
+        if len == 0 {
+            return Self::new(self.root, vec![]);
         }
-
-        // Convert intervals to int for computations
-        let reference = self.intervals.clone()[mode as usize] as i8;
-        let mut int_intervals = self
-            .intervals
-            .clone()
-            .iter()
-            .map(|x| {
-                let mut a = *x as i8 - reference;
-                if a < 0 {
-                    a += 12;
-                }
-                a
-            })
-            .collect_vec();
-
-        // Place origin of mode in first place (which will be Unison)
-        int_intervals.rotate_left(mode as usize);
-
-        // Convert back to intervals
-        let new_intervals: Vec<Interval> = int_intervals
-            .into_iter()
-            .filter_map(|i| num::FromPrimitive::from_u32(i as u32))
+        // Wrap around invalid modes identifiers
+        let mode = (n as i32 - 1).rem_euclid(len as i32) as usize;
+
+        // Intervals from the mode origin, measured on a scale built on C
+        let reference = Self::new(Note::new(Letter::C, 4), self.intervals.clone());
+        let origin = reference.degree_note(mode);
+        let new_intervals = (mode..mode + len)
+            .map(|i| reference.degree_note(i) - origin)
             .collect_vec();
 
         Self::new(self.root, new_intervals)
@@ -279,32 +481,31 @@ impl Scale {
         Chord::new(self.build_by_steps(6, 2, len))
     }
 
-    /// Build notes of scale from intervals and steps
+    /// Build notes of scale from intervals and steps, going up the octaves as needed
+    ///
+    /// A scale without intervals has no notes to build from.
     fn build_by_steps(&self, root: usize, step: usize, length: usize) -> Vec<Note> {
-        // We add all intervals of the scale one octave higher for chord creation
-        let mut intervals_octave_up: Vec<Interval> = self
-            .intervals
-            .clone()
-            .into_iter()
-            .filter_map(|i| num::FromPrimitive::from_u32(i as u32 + 12))
-            .collect_vec();
-
-        // Concatenate all intervals
-        let mut intervals = self.intervals.clone();
-        intervals.append(&mut intervals_octave_up);
-
-        // Step in interval vector
-        intervals
-            .clone()
-            .into_iter()
-            .cycle() // Wraps around if step / length combination exceeds two octave span
-            .skip(root)
+        if self.intervals.is_empty() {
+            return vec![];
+        }
+        (root..)
             .step_by(step)
-            .map(|n| self.root + n)
+            .map(|i| self.degree_note(i))
             .take(length)
             .collect_vec()
     }
 
+    /// Note at `index` of the scale, counting on in the next octaves.
+    fn degree_note(&self, index: usize) -> Note {
+        let len = self.intervals.len();
+        let octaves = (index / len) as i32;
+        let interval = self.intervals[index % len];
+        self.root.transpose(
+            interval.semitones() + 12 * octaves,
+            interval.steps() as i32 + 7 * octaves,
+        )
+    }
+
     /// Get `Note` vector from Scale
     ///
     /// # examples
@@ -314,15 +515,14 @@ impl Scale {
     /// ```
     /// use mumuse::music::scale::Scale;
     /// use mumuse::music::note::Note;
-    /// let root = Note::try_from("A3").unwrap();
+    /// let root = Note::try_from("F#3").unwrap();
     /// let scale = Scale::major(root);
     /// let notes = scale.notes();
+    /// assert_eq!(notes[6].to_string(), "Note(E#4)");
     /// ```
     pub fn notes(&self) -> Vec<Note> {
-        self.intervals
-            .clone()
-            .into_iter()
-            .map(|interval| self.root + interval)
+        (0..self.intervals.len())
+            .map(|i| self.degree_note(i))
             .collect_vec()
     }
 }
@@ -331,7 +531,7 @@ impl Scale {
 impl fmt::Display for Scale {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut notes: String = "".to_string();
-        for (i, note) in (&self.notes()).iter().enumerate() {
+        for (i, note) in self.notes().iter().enumerate() {
             notes += &format!("{}", note).to_string();
             if i != self.notes().len() - 1 {
                 notes += ","
@@ -342,14 +542,14 @@ impl fmt::Display for Scale {
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_range_loop, clippy::unnecessary_cast)]
 mod tests {
     use super::*;
-    use crate::music::common::Letter;
 
     #[test]
     fn get_notes() {
         let root = Note::try_from("C0").unwrap();
-        let intervals = vec![Unison, MajorSecond];
+        let intervals = vec![Interval::UNISON, Interval::MAJOR_SECOND];
         let scale = Scale::new(root, intervals);
         let notes = scale.notes();
         assert_eq!(notes[0].letter, Letter::C);
@@ -412,6 +612,137 @@ mod tests {
         }
     }
 
+    #[test]
+    fn spelled_scales() {
+        let spelled = |scale: Scale| {
+            scale
+                .notes()
+                .iter()
+                .map(|n| n.spelling().to_string())
+                .join(" ")
+        };
+        let root = |s| Note::try_from(s).unwrap();
+        assert_eq!(spelled(Scale::major(root("F#4"))), "F# G# A# B C# D# E#");
+        assert_eq!(spelled(Scale::major(root("Gb4"))), "Gb Ab Bb Cb Db Eb F");
+        assert_eq!(spelled(Scale::minor(root("D#4"))), "D# E# F# G# A# B C#");
+        assert_eq!(
+            spelled(Scale::minor_harmonic(root("G#4"))),
+            "G# A# B C# D# E F##"
+        );
+        assert_eq!(spelled(Scale::major(root("C4")).mode(4)), "C D E F# G A B");
+        let f_sharp_major = Scale::major(root("F#4"));
+        assert_eq!(
+            spelled_chord(&f_sharp_major.five(4)),
+            vec!["Note(C#5)", "Note(E#5)", "Note(G#5)", "Note(B5)"]
+        );
+    }
+
+    fn spelled_chord(chord: &Chord) -> Vec<String> {
+        chord.notes.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn catalogue() {
+        let root = Note::try_from("C4").unwrap();
+        let scales = Scale::catalogue(root);
+        assert_eq!(scales.len(), CATALOGUE.len());
+        // Names are unique up to enharmonic intervals
+        for (scale, (names, _)) in scales.iter().zip(CATALOGUE) {
+            assert_eq!(scale.name(), Some(names[0]));
+        }
+        let spelled = |name| {
+            Scale::by_name(name, root)
+                .unwrap()
+                .notes()
+                .iter()
+                .map(|n| n.spelling().to_string())
+                .join(" ")
+        };
+        assert_eq!(spelled("altered"), "C Db Eb Fb Gb Ab Bb");
+        assert_eq!(spelled("whole tone"), "C D E F# G# A#");
+        assert_eq!(spelled("Minor_Pentatonic"), "C Eb F G Bb");
+        assert_eq!(spelled("bebop dominant"), "C D E F G A Bb B");
+        assert!(Scale::by_name("ionian #7", root).is_none());
+    }
+
+    #[test]
+    fn candidates() {
+        let notes = |names: &[&str]| {
+            names
+                .iter()
+                .map(|n| Note::try_from(*n).unwrap())
+                .collect_vec()
+        };
+        let names = |notes: &[Note]| {
+            Scale::candidates(notes)
+                .iter()
+                .map(|s| format!("{} {}", s.root.spelling(), s.name().unwrap()))
+                .collect_vec()
+        };
+
+        // A bar of blues licks over A
+        let lick = names(&notes(&["A3", "C4", "D4", "Eb4", "E4", "G4"]));
+        assert_eq!(lick[0], "A blues");
+        // Major or minor pentatonic depend on the lowest note
+        let pentatonic = names(&notes(&["A3", "C4", "D4", "E4", "G4"]));
+        assert_eq!(pentatonic[0], "A minor pentatonic");
+        assert_eq!(pentatonic[1], "C major pentatonic");
+        // Altered dominant line, the root not played
+        let altered = names(&notes(&["Db4", "Eb4", "E4", "F#4", "Ab4"]));
+        assert!(altered.contains(&"C altered".to_string()));
+
+        let chromatic = names(&notes(&["C4", "C#4", "D4", "D#4", "E4", "F4", "F#4", "G4"]));
+        assert_eq!(chromatic[0], "C chromatic");
+        assert!(chromatic.iter().all(|s| s.ends_with(" chromatic")));
+        assert!(Scale::candidates(&[]).is_empty());
+    }
+
+    #[test]
+    fn named_modes() {
+        let root = Note::try_from("D4").unwrap();
+        let names = |scale: Scale| (1..=7).map(|n| scale.mode(n).name().unwrap()).join(", ");
+        assert_eq!(
+            names(Scale::major(root)),
+            "major, dorian, phrygian, lydian, mixolydian, minor, locrian"
+        );
+        assert_eq!(
+            names(Scale::by_name("melodic minor", root).unwrap()),
+            "melodic minor, dorian b2, lydian augmented, lydian dominant, mixolydian b6, \
+             locrian #2, altered"
+        );
+        assert_eq!(
+            names(Scale::minor_harmonic(root)),
+            "harmonic minor, locrian #6, ionian #5, dorian #4, phrygian dominant, lydian #2, \
+             ultralocrian"
+        );
+        let unnamed = Scale::new(root, vec![Interval::UNISON, Interval::AUGMENTED_SECOND]);
+        assert_eq!(unnamed.name(), None);
+    }
+
+    #[test]
+    fn any_length() {
+        let root = Note::try_from("C4").unwrap();
+        let pentatonic = Scale::by_name("major pentatonic", root).unwrap();
+        assert_eq!(
+            spelled_chord(&pentatonic.one(7)),
+            vec![
+                "Note(C4)", "Note(E4)", "Note(A4)", "Note(D5)", "Note(G5)", "Note(C6)", "Note(E6)"
+            ]
+        );
+        let major = Scale::major(root);
+        assert_eq!(spelled_chord(&major.seven(7))[6], "Note(G6)");
+    }
+
+    #[test]
+    fn empty() {
+        let root = Note::try_from("C4").unwrap();
+        let empty = Scale::new(root, vec![]);
+        assert!(empty.mode(3).intervals.is_empty());
+        assert!(empty.notes().is_empty());
+        assert!(empty.one(3).notes.is_empty());
+        assert!(empty.seven(4).notes.is_empty());
+    }
+
     #[test]
     fn modes() {
         let root = Note::try_from("C0").unwrap();
@@ -1,18 +1,30 @@
 //! Stream of notes
 
-use crate::messages::Status;
+use crate::error::Result;
+use crate::messages::{DecodeError, Midi, Status};
 use crate::midi::MidiSend;
+use crate::music::common::find_letter_idx;
 use crate::music::duration::Duration;
+use crate::music::key::{Key, KeyEstimate, KeyProfile};
+use crate::music::meter::{Meter, TimeSignature};
 use crate::music::note::Note;
+use crate::music::tempo::{Ramp, TempoMap};
 use crate::music::time::Time;
+use crate::smf::{self, EventKind, Format, Smf, Track, DEFAULT_PPQ, DEFAULT_VELOCITY};
 use itertools::Itertools;
 use midir::MidiOutputConnection;
+use num::rational::Ratio;
+use std::collections::HashMap;
 use tokio::time::{self, Duration as TDuration};
 
 /// Temporal arrangement of events
 #[derive(Debug, Default, Clone)]
 pub struct Stream {
     pub events: Vec<Event>,
+    /// Time signatures of the stream, 4/4 by default
+    pub meter: Meter,
+    /// Tempo changes of the stream, 120 bpm by default
+    pub tempo: TempoMap,
 }
 
 /// An event is a timed command
@@ -38,10 +50,14 @@ impl Stream {
     ///
     /// ```
     /// use mumuse::music::stream::Stream;
-    /// let s = Stream::new(); 
+    /// let s = Stream::new();
     /// ```
     pub fn new() -> Self {
-        Self { events: vec![] }
+        Self {
+            events: vec![],
+            meter: Meter::default(),
+            tempo: TempoMap::default(),
+        }
     }
 
     /// Adds event to stream
@@ -85,8 +101,7 @@ impl Stream {
 
     /// Converts Events to seconds timeline
     ///
-    /// In order to convert the stream with `Time` events, one need to declare a `bpm`
-    /// (beats per minutes) and a `bpb` (beats per bar).
+    /// Times are converted along the tempo map of the stream.
     ///
     /// # Examples
     ///
@@ -99,33 +114,286 @@ impl Stream {
     /// let time: Time = Time::new(1, 16, 1);
     /// let duration: Duration = Duration::new(16, 1);
     /// stream.add_note(note, time, duration);
-    /// let stream_seconds = stream.to_seconds(120.0, 4);
+    /// let stream_seconds = stream.to_seconds();
     /// ```
-    pub fn to_seconds(&self, bpm: f64, bpb: u32) -> Vec<(f64, Status, Note)> {
+    pub fn to_seconds(&self) -> Vec<(f64, Status, Note)> {
         let mut events_seconds: Vec<(f64, Status, Note)> = vec![];
         for event in self.events.iter() {
-            events_seconds.push((event.time.to_seconds(bpm, bpb), event.status, event.note));
+            events_seconds.push((self.tempo.to_seconds(event.time), event.status, event.note));
         }
         // Sort by time
         events_seconds.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
         events_seconds
     }
 
+    /// Converts the stream into a Standard MIDI File
+    ///
+    /// Tempo and time signature meta events are derived from the tempo map and the meter
+    /// of the stream, tempo ramps changing every sixteenth note.
+    /// With `Format::MultiTrack`, the first track holds the tempo map and the second one the notes,
+    /// otherwise everything is written in a single track. Notes outside of the key range are skipped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::{time::Time, note::Note, stream::Stream, duration::Duration};
+    /// use mumuse::smf::Format;
+    /// let mut stream: Stream = Stream::new();
+    /// let note: Note = Note::try_from("A3").unwrap();
+    /// stream.add_note(note, Time::new(1, 4, 1), Duration::new(4, 1));
+    /// let smf = stream.to_smf(Format::SingleTrack);
+    /// let bytes = smf.to_bytes();
+    /// ```
+    pub fn to_smf(&self, format: Format) -> Smf {
+        let mut smf = Smf::new(format, DEFAULT_PPQ);
+        let mut meta = self
+            .meter
+            .changes()
+            .iter()
+            .map(|(bar, signature)| {
+                let tick = self.meter.bar_start(*bar).to_ticks(smf.ppq);
+                let kind =
+                    EventKind::time_signature(signature.numerator(), signature.denominator());
+                (tick, kind)
+            })
+            .collect_vec();
+        let tempos = self.tempo.instant_changes(Ratio::new(1, 16));
+        let tempos = tempos
+            .iter()
+            .map(|(time, bpm)| (time.to_ticks(smf.ppq), EventKind::tempo(*bpm)));
+        meta.splice(1..1, tempos);
+
+        // Note offs are placed before note ons of the same tick so repeated notes are not cut
+        let notes = self
+            .events
+            .iter()
+            .filter_map(|event| {
+                let kn = event.note.to_midi().ok()?;
+                let message = match event.status {
+                    Status::NoteOn => Midi::note_on(0, kn, DEFAULT_VELOCITY),
+                    Status::NoteOff => Midi::note_off(0, kn, DEFAULT_VELOCITY),
+                    _ => return None,
+                };
+                let tick = event.time.to_ticks(smf.ppq);
+                Some((tick, message.to_bytes()))
+            })
+            .sorted_by_key(|(tick, bytes)| (*tick, bytes[0]))
+            .map(|(tick, bytes)| (tick, EventKind::Midi(bytes)))
+            .collect_vec();
+
+        match format {
+            Format::MultiTrack => {
+                smf.tracks.push(Track::from_absolute(meta));
+                smf.tracks.push(Track::from_absolute(notes));
+            }
+            Format::SingleTrack | Format::MultiSequence => {
+                smf.tracks.push(Track::from_absolute(
+                    meta.into_iter().chain(notes).collect(),
+                ));
+            }
+        }
+        smf
+    }
+
+    /// Creates a stream from the notes of a Standard MIDI File
+    ///
+    /// Note events of all tracks and channels are merged, a note on with null velocity
+    /// being read as a note off. Other events are ignored.
+    /// The tempo map and the time signatures are read too,
+    /// time signatures changing within a bar starting at the next one.
+    /// Tracks of format 2 files are placed one after another.
+    ///
+    /// An error is returned for a time division of zero ticks, a truncated note message
+    /// or a key number above 127.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::{time::Time, note::Note, stream::Stream, duration::Duration};
+    /// use mumuse::smf::{Format, Smf};
+    /// let mut stream: Stream = Stream::new();
+    /// let note: Note = Note::try_from("A3").unwrap();
+    /// stream.add_note(note, Time::new(1, 4, 1), Duration::new(4, 1));
+    /// let bytes = stream.to_smf(Format::SingleTrack).to_bytes();
+    /// let imported = Stream::from_smf(&Smf::parse(&bytes).unwrap()).unwrap();
+    /// assert_eq!(imported.events.len(), 2);
+    /// ```
+    pub fn from_smf(smf: &Smf) -> Result<Self> {
+        if smf.ppq == 0 {
+            return Err(smf::Error::ZeroDivision.into());
+        }
+        let mut stream = Stream::new();
+        for (tick, bpm) in smf.tempo_map() {
+            let time = Time::from_ticks(tick, smf.ppq);
+            stream.tempo.set(time, bpm, Ramp::Instant)?;
+        }
+        for (tick, numerator, denominator) in smf.time_signatures() {
+            let signature = match TimeSignature::new(numerator, denominator) {
+                Ok(signature) => signature,
+                Err(_) => continue,
+            };
+            let position = stream
+                .meter
+                .bar_beat_tick(Time::from_ticks(tick, smf.ppq), smf.ppq);
+            let on_bar = (position.beat, position.tick) == (1, 0);
+            let bar = if on_bar {
+                position.bar
+            } else {
+                position.bar + 1
+            };
+            stream.meter.set(bar, signature);
+        }
+
+        let mut track_offset: u32 = 0;
+        for track in &smf.tracks {
+            let mut last_tick = 0;
+            for (tick, kind) in track.absolute() {
+                last_tick = tick;
+                let message = match kind {
+                    EventKind::Midi(message) => message,
+                    _ => continue,
+                };
+                let (status, key) = match message.as_slice() {
+                    [status @ 0x80..=0x9F, key, velocity, ..] => match (status & 0xF0, velocity) {
+                        (0x90, 0) | (0x80, _) => (Status::NoteOff, *key),
+                        _ => (Status::NoteOn, *key),
+                    },
+                    [status @ 0x80..=0x9F, ..] => {
+                        return Err(DecodeError::Truncated {
+                            position: message.len(),
+                            status: *status,
+                        }
+                        .into())
+                    }
+                    _ => continue,
+                };
+                let time = Time::from_ticks(track_offset.saturating_add(tick), smf.ppq);
+                stream.add_event(Event::new(time, status, Note::from_midi(key)?));
+            }
+            if smf.format == smf::Format::MultiSequence {
+                track_offset = track_offset.saturating_add(last_tick);
+            }
+        }
+        Ok(stream)
+    }
+
+    /// Estimates the key of the whole stream.
+    ///
+    /// Durations of the pitch classes are correlated with the `profile` of every key,
+    /// see `Key::rank`. Nothing is estimated for a stream without notes.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::{time::Time, note::Note, stream::Stream, duration::Duration};
+    /// use mumuse::music::key::KeyProfile;
+    /// let mut stream: Stream = Stream::new();
+    /// for (i, n) in ["A3", "C4", "E4", "G#4", "A4"].iter().enumerate() {
+    ///     let note = Note::try_from(*n).unwrap();
+    ///     stream.add_note(note, Time::new(1, 8, i as u32 + 1), Duration::new(8, 1));
+    /// }
+    /// let estimate = stream.estimate_key(KeyProfile::KrumhanslKessler).unwrap();
+    /// assert_eq!(estimate.key.to_string(), "A minor");
+    /// ```
+    pub fn estimate_key(&self, profile: KeyProfile) -> Option<KeyEstimate> {
+        let weights = pitch_class_durations(&self.note_spans(), 0., f64::INFINITY);
+        Key::estimate(&weights, profile)
+    }
+
+    /// Estimates the key over time, in windows of `window` bars starting at every bar.
+    ///
+    /// Bars follow the meter of the stream.
+    /// The bar number of each window start is returned along with its key,
+    /// windows without notes being skipped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::{chord::Chord, time::Time, stream::Stream, duration::Duration};
+    /// use mumuse::music::key::KeyProfile;
+    /// let mut stream: Stream = Stream::new();
+    /// let chords = [["C4", "E4", "G4"], ["B3", "D4", "G4"], ["C4", "E4", "G4"]];
+    /// for (bar, chord) in chords.iter().enumerate() {
+    ///     for note in Chord::from(chord.to_vec()).notes {
+    ///         stream.add_note(note, Time::new(bar as u32 + 1, 2, 1), Duration::new(2, 1));
+    ///     }
+    /// }
+    /// let keys = stream.estimate_keys(KeyProfile::Temperley, 2);
+    /// assert_eq!(keys.len(), 3);
+    /// assert_eq!((keys[0].0, keys[0].1.key.to_string()), (1, "C major".to_string()));
+    /// ```
+    pub fn estimate_keys(&self, profile: KeyProfile, window: u32) -> Vec<(u32, KeyEstimate)> {
+        let spans = self.note_spans();
+        let end = spans.iter().map(|(_, end, _)| *end).fold(0., f64::max);
+        (1..)
+            .map(|bar| (bar, self.meter.bar_start(bar).to_f64()))
+            .take_while(|(_, start)| *start < end)
+            .filter_map(|(bar, start)| {
+                let stop = self.meter.bar_start(bar + window).to_f64();
+                let weights = pitch_class_durations(&spans, start, stop);
+                Some((bar, Key::estimate(&weights, profile)?))
+            })
+            .collect()
+    }
+
+    /// Notes with their start time, in time order
+    pub(crate) fn onsets(&self) -> Vec<(Time, Note)> {
+        self.events
+            .iter()
+            .filter(|e| e.status == Status::NoteOn)
+            .map(|e| (e.time, e.note))
+            .sorted_by_key(|(time, _)| *time)
+            .collect()
+    }
+
+    /// Start and end in whole notes of the notes, along with their pitch class
+    ///
+    /// A note off ends the earliest pending note on of the same pitch,
+    /// notes never ended being ignored.
+    fn note_spans(&self) -> Vec<(f64, f64, usize)> {
+        let mut pending: HashMap<(i8, i8), Vec<f64>> = HashMap::new();
+        let mut spans = vec![];
+        let events = self
+            .events
+            .iter()
+            .sorted_by_key(|e| (e.time, e.status != Status::NoteOff));
+        for event in events {
+            let pitch_class = find_letter_idx(event.note.letter);
+            let starts = pending.entry((pitch_class, event.note.octave)).or_default();
+            match event.status {
+                Status::NoteOn => starts.push(event.time.to_f64()),
+                Status::NoteOff if !starts.is_empty() => {
+                    let start = starts.remove(0);
+                    spans.push((start, event.time.to_f64(), pitch_class as usize));
+                }
+                _ => (),
+            }
+        }
+        spans
+    }
+
     /// Plays stream of events in real time
     ///
     /// Use ticking for playing the stream of events at regular intervals.
     /// For each tick, the events with Time to seconds lying in the tick window are
-    /// sent as MIDI. 
-    /// The `bpm` (beats per minutes) and `bpb` (beats per bar) are needed for the conversion
-    /// to seconds.
+    /// sent as MIDI.
+    /// Times are converted to seconds along the tempo map of the stream.
     #[tokio::main]
-    pub async fn play(&self, conn_out: &mut MidiOutputConnection, bpm: f64, bpb: u32) {
-        let events_seconds = self.to_seconds(bpm, bpb); // Vector of events with seconds unit
+    pub async fn play(&self, conn_out: &mut MidiOutputConnection) {
+        let events_seconds = self.to_seconds(); // Vector of events with seconds unit
         let interval_time = 10.0; // in ms
         let mut played_events = 0; // Count of sent event
         let mut n_tick = 0; // Tick number counter
         let total_events = events_seconds.len(); // Number of total events to send
-        let mut interval = time::interval(TDuration::from_millis(interval_time as u64)); 
+        let mut interval = time::interval(TDuration::from_millis(interval_time as u64));
 
         // Async function to send midi events with constant tick time
         // Constant tick time is managed by Tokio interval ticking with Burst missed tick strategy
@@ -134,7 +402,7 @@ impl Stream {
             conn_out: &mut MidiOutputConnection,
             n_tick: usize,
             interval_time: f64,
-            events_seconds: &Vec<(f64, Status, Note)>,
+            events_seconds: &[(f64, Status, Note)],
         ) -> usize {
             let last_tick_time = n_tick as f64 * interval_time / 1000.0; // in sec
             let next_tick_time = (n_tick + 1) as f64 * interval_time / 1000.0; // in sec
@@ -146,7 +414,6 @@ impl Stream {
                 .filter(|event| (event.0 >= last_tick_time) && (event.0 < next_tick_time))
                 .collect_vec();
 
-            
             // Send events, currently only NoteOn and Note Off are handled.
             // Add match arms for additional case handling
             for current_event in &current_events {
@@ -172,9 +439,20 @@ impl Stream {
     }
 }
 
+/// Durations of the pitch classes of note `spans` clipped between `from` and `to` whole notes
+fn pitch_class_durations(spans: &[(f64, f64, usize)], from: f64, to: f64) -> [f64; 12] {
+    let mut durations = [0.; 12];
+    for (start, end, pitch_class) in spans {
+        durations[*pitch_class] += (end.min(to) - start.max(from)).max(0.);
+    }
+    durations
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::Error;
+    use crate::music::common::Letter;
 
     #[test]
     fn add_event() {
@@ -182,9 +460,9 @@ mod tests {
         let time: Time = Time::new(1, 16, 1);
         let note: Note = Note::try_from("A3").unwrap();
         stream.add_event(Event::new(time, Status::NoteOn, note));
-        assert_eq!(stream.events.get(0).unwrap().time.bar, 1);
-        assert_eq!(stream.events.get(0).unwrap().time.divisions, 16);
-        assert_eq!(stream.events.get(0).unwrap().time.position, 1);
+        assert_eq!(stream.events[0].time.bar(), 1);
+        assert_eq!(stream.events[0].time.divisions(), 1);
+        assert_eq!(stream.events[0].time.position(), 1);
     }
 
     #[test]
@@ -199,9 +477,181 @@ mod tests {
         stream.add_event(Event::new(Time::new(1, 4, 1), Status::NoteOn, note));
 
         // time_stream time should be ordered
-        let time_stream = stream.to_seconds(120.0, 4);
+        let time_stream = stream.to_seconds();
         assert!(time_stream[0].0 < time_stream[1].0);
         assert!(time_stream[1].0 < time_stream[2].0);
         assert!(time_stream[2].0 < time_stream[3].0);
     }
+
+    #[test]
+    fn to_smf_single_track() {
+        let mut stream: Stream = Stream::new();
+        let note: Note = Note::try_from("C4").unwrap();
+        stream.add_note(note, Time::new(1, 4, 2), Duration::new(4, 1));
+        stream.add_note(note, Time::new(1, 4, 1), Duration::new(4, 1));
+
+        stream.meter = Meter::new(TimeSignature::new(3, 4).unwrap());
+        let smf = stream.to_smf(Format::SingleTrack);
+        assert_eq!(smf.tracks.len(), 1);
+        let events = &smf.tracks[0].events;
+        assert_eq!(events[0].kind, EventKind::time_signature(3, 4));
+        assert_eq!(events[1].kind, EventKind::tempo(120.0));
+
+        // A quarter note lasts 480 ticks, second note on follows the first note off
+        let deltas = events[2..].iter().map(|e| e.delta).collect_vec();
+        assert_eq!(deltas, vec![0, 480, 0, 480]);
+        assert_eq!(
+            events[3].kind,
+            EventKind::Midi(vec![0x80, 60, DEFAULT_VELOCITY])
+        );
+        assert_eq!(
+            events[4].kind,
+            EventKind::Midi(vec![0x90, 60, DEFAULT_VELOCITY])
+        );
+    }
+
+    #[test]
+    fn from_smf_round_trip() {
+        let mut stream: Stream = Stream::new();
+        stream.tempo = TempoMap::new(100.0).unwrap();
+        let notes = ["C0", "A3", "Eb5", "G9"];
+        for (i, n) in notes.iter().enumerate() {
+            let note = Note::try_from(*n).unwrap();
+            stream.add_note(note, Time::new(i as u32 + 1, 3, 2), Duration::new(3, 1));
+        }
+
+        for format in [Format::SingleTrack, Format::MultiTrack] {
+            let smf = stream.to_smf(format);
+            let imported = Stream::from_smf(&smf::Smf::parse(&smf.to_bytes()).unwrap()).unwrap();
+            assert_eq!(imported.events.len(), stream.events.len());
+            let originals = stream.to_seconds();
+            for (event, original) in imported.to_seconds().iter().zip(originals) {
+                assert!((event.0 - original.0).abs() < 1e-9);
+                assert_eq!(event.2.letter, original.2.letter);
+                assert_eq!(event.2.octave, original.2.octave);
+            }
+        }
+    }
+
+    #[test]
+    fn from_smf_malformed() {
+        let mut smf = Smf::new(Format::MultiSequence, 480);
+        for _ in 0..2 {
+            let mut track = Track::new();
+            track.push(u32::MAX, EventKind::Midi(vec![0x90, 60, 64]));
+            smf.tracks.push(track);
+        }
+        let imported = Stream::from_smf(&smf).unwrap();
+        assert_eq!(imported.events.len(), 2);
+        assert_eq!(imported.events[1].time, Time::from_ticks(u32::MAX, 480));
+
+        smf.tracks[1].push(0, EventKind::Midi(vec![0x90]));
+        assert!(matches!(
+            Stream::from_smf(&smf),
+            Err(Error::Decode(DecodeError::Truncated { status: 0x90, .. }))
+        ));
+        smf.ppq = 0;
+        assert!(matches!(
+            Stream::from_smf(&smf),
+            Err(Error::Smf(smf::Error::ZeroDivision))
+        ));
+    }
+
+    #[test]
+    fn meter_round_trip() {
+        let mut stream: Stream = Stream::new();
+        stream.meter = Meter::new(TimeSignature::new(3, 4).unwrap());
+        stream.meter.set(3, TimeSignature::new(7, 8).unwrap());
+        let note = Note::try_from("E4").unwrap();
+        stream.add_note(note, stream.meter.bar_start(4), Duration::new(8, 7));
+
+        let smf = stream.to_smf(Format::MultiTrack);
+        assert_eq!(smf.time_signatures(), vec![(0, 3, 4), (2 * 3 * 480, 7, 8)]);
+        let imported = Stream::from_smf(&smf::Smf::parse(&smf.to_bytes()).unwrap()).unwrap();
+        assert_eq!(imported.meter, stream.meter);
+        let position = imported.meter.bar_beat_tick(imported.onsets()[0].0, 480);
+        assert_eq!((position.bar, position.beat, position.tick), (4, 1, 0));
+    }
+
+    #[test]
+    fn tempo_round_trip() {
+        let mut stream: Stream = Stream::new();
+        stream
+            .tempo
+            .set(Time::new(2, 1, 1), 120.0, Ramp::Instant)
+            .unwrap();
+        stream
+            .tempo
+            .set(Time::new(4, 1, 1), 60.0, Ramp::Exponential)
+            .unwrap();
+        stream
+            .tempo
+            .set(Time::new(5, 1, 1), 150.0, Ramp::Instant)
+            .unwrap();
+        let note = Note::try_from("G4").unwrap();
+        for i in 0..40 {
+            stream.add_note(note, Time::new(1, 8, i + 1), Duration::new(8, 1));
+        }
+
+        let smf = stream.to_smf(Format::SingleTrack);
+        // Two bars of ramp in sixteenth notes between the tempos before and after it
+        assert_eq!(smf.tempo_map().len(), 1 + 32 + 2);
+        let imported = Stream::from_smf(&smf::Smf::parse(&smf.to_bytes()).unwrap()).unwrap();
+        for (event, original) in imported.to_seconds().iter().zip(stream.to_seconds()) {
+            assert!((event.0 - original.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn key_estimation() {
+        let mut stream: Stream = Stream::new();
+        // Two bars in D major, then two in Bb major
+        let bars = [
+            ["D4", "F#4", "A4", "C#5"],
+            ["G4", "B4", "D5", "E4"],
+            ["Bb3", "D4", "F4", "A4"],
+            ["Eb4", "G4", "Bb4", "F4"],
+        ];
+        for (bar, notes) in bars.iter().enumerate() {
+            for (i, note) in notes.iter().enumerate() {
+                let note = Note::try_from(*note).unwrap();
+                let time = Time::new(bar as u32 + 1, 4, i as u32 + 1);
+                stream.add_note(note, time, Duration::new(4, 1));
+            }
+        }
+        let keys = stream
+            .estimate_keys(KeyProfile::KrumhanslKessler, 2)
+            .iter()
+            .map(|(bar, estimate)| (*bar, estimate.key.to_string()))
+            .collect_vec();
+        assert_eq!(keys[0], (1, "D major".to_string()));
+        assert_eq!(keys[2], (3, "Bb major".to_string()));
+        assert_eq!(keys.len(), 4);
+
+        // An unended note does not count
+        stream.add_event(Event::new(
+            Time::new(5, 1, 1),
+            Status::NoteOn,
+            Note::new(Letter::B, 4),
+        ));
+        assert_eq!(stream.estimate_keys(KeyProfile::Temperley, 1).len(), 4);
+        // Bars of 2/4 hold half of the notes
+        stream.meter = Meter::new(TimeSignature::new(2, 4).unwrap());
+        assert_eq!(stream.estimate_keys(KeyProfile::Temperley, 1).len(), 8);
+        assert!(Stream::new().estimate_key(KeyProfile::Temperley).is_none());
+    }
+
+    #[test]
+    fn to_smf_multi_track() {
+        let mut stream: Stream = Stream::new();
+        let note: Note = Note::try_from("A3").unwrap();
+        stream.add_note(note, Time::new(2, 4, 1), Duration::new(4, 1));
+
+        stream.tempo = TempoMap::new(90.0).unwrap();
+        let smf = stream.to_smf(Format::MultiTrack);
+        assert_eq!(smf.tracks.len(), 2);
+        assert_eq!(smf.tracks[0].events.len(), 2);
+        assert_eq!(smf.tracks[1].events[0].delta, 4 * 480);
+        assert_eq!(&smf.to_bytes()[8..12], &[0, 1, 0, 2]);
+    }
 }
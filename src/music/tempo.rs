@@ -0,0 +1,378 @@
+//! Tempo changes along a piece, instant or gradual
+
+use crate::error::{Error, Result};
+use crate::music::time::Time;
+use num::rational::Ratio;
+
+/// Tempo used until the first change, in beats (quarter notes) per minute
+pub const DEFAULT_BPM: f64 = 120.;
+
+/// How the tempo reaches a change from the previous one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ramp {
+    /// Previous tempo kept until the change
+    Instant,
+    /// Tempo moving by the same amount every beat, accelerando or ritardando
+    Linear,
+    /// Tempo moving by the same ratio every beat
+    Exponential,
+}
+
+/// A tempo reached at `time`, in beats (quarter notes) per minute
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TempoChange {
+    pub time: Time,
+    pub bpm: f64,
+    pub ramp: Ramp,
+}
+
+/// Tempo changes of a piece, the first one at the start
+#[derive(Debug, Clone, PartialEq)]
+pub struct TempoMap {
+    changes: Vec<TempoChange>,
+}
+
+impl TempoMap {
+    /// Creates a `TempoMap` at `bpm` all along
+    ///
+    /// An error is returned if `bpm` is not a positive finite number.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tempo::TempoMap;
+    /// assert_eq!(TempoMap::new(90.).unwrap().changes()[0].bpm, 90.);
+    /// assert!(TempoMap::new(0.).is_err());
+    /// assert!(TempoMap::new(f64::NAN).is_err());
+    /// ```
+    pub fn new(bpm: f64) -> Result<Self> {
+        Ok(TempoMap {
+            changes: vec![TempoChange {
+                time: Time::default(),
+                bpm: check_bpm(bpm)?,
+                ramp: Ramp::Instant,
+            }],
+        })
+    }
+
+    /// Changes the tempo to `bpm` at `time`, reached from the previous change along `ramp`.
+    ///
+    /// A change at the start is always instant. An error is returned if `bpm`
+    /// is not a positive finite number, the map being left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tempo::{Ramp, TempoMap};
+    /// use mumuse::music::time::Time;
+    /// let mut tempo = TempoMap::new(60.).unwrap();
+    /// tempo.set(Time::new(2, 1, 1), 60., Ramp::Instant).unwrap();
+    /// tempo.set(Time::new(3, 1, 1), 120., Ramp::Linear).unwrap(); // Accelerando during bar 2
+    /// assert_eq!(tempo.bpm(Time::new(2, 2, 2)), 90.);
+    /// assert_eq!(tempo.bpm(Time::new(4, 1, 1)), 120.);
+    /// ```
+    pub fn set(&mut self, time: Time, bpm: f64, ramp: Ramp) -> Result<()> {
+        let bpm = check_bpm(bpm)?;
+        let ramp = if time == Time::default() {
+            Ramp::Instant
+        } else {
+            ramp
+        };
+        let change = TempoChange { time, bpm, ramp };
+        match self.changes.binary_search_by_key(&time, |c| c.time) {
+            Ok(i) => self.changes[i] = change,
+            Err(i) => self.changes.insert(i, change),
+        }
+        Ok(())
+    }
+
+    /// Tempo changes, in time order
+    pub fn changes(&self) -> &[TempoChange] {
+        &self.changes
+    }
+
+    /// Tempo at `time`, in beats per minute
+    pub fn bpm(&self, time: Time) -> f64 {
+        let (start, end) = self.segment(time);
+        match end {
+            Some(end) => {
+                let segment = Segment::new(start, end);
+                segment.bpm(quarters(time) - quarters(start.time))
+            }
+            None => start.bpm,
+        }
+    }
+
+    /// Seconds elapsed from the start until `time`
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tempo::{Ramp, TempoMap};
+    /// use mumuse::music::time::Time;
+    /// let mut tempo = TempoMap::new(120.).unwrap();
+    /// tempo.set(Time::new(2, 1, 1), 60., Ramp::Instant).unwrap();
+    /// assert_eq!(tempo.to_seconds(Time::new(2, 1, 1)), 2.);
+    /// assert_eq!(tempo.to_seconds(Time::new(3, 1, 1)), 6.);
+    /// ```
+    pub fn to_seconds(&self, time: Time) -> f64 {
+        let mut seconds = 0.;
+        for (start, end) in self.changes.iter().zip(self.changes.iter().skip(1)) {
+            let segment = Segment::new(start, end);
+            if end.time >= time {
+                return seconds + segment.seconds(quarters(time) - quarters(start.time));
+            }
+            seconds += segment.seconds(segment.quarters);
+        }
+        let last = self.changes.last().unwrap();
+        seconds + (quarters(time) - quarters(last.time)) * 60. / last.bpm
+    }
+
+    /// Time reached after `seconds`, rounded to the nearest of `ppq` ticks per quarter note
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tempo::{Ramp, TempoMap};
+    /// use mumuse::music::time::Time;
+    /// let mut tempo = TempoMap::new(90.).unwrap();
+    /// tempo.set(Time::new(5, 1, 1), 45., Ramp::Exponential).unwrap();
+    /// let time = Time::new(3, 8, 3);
+    /// assert_eq!(tempo.to_time(tempo.to_seconds(time), 480), time);
+    /// ```
+    pub fn to_time(&self, seconds: f64, ppq: u16) -> Time {
+        let mut elapsed = 0.;
+        let mut position = None;
+        for (start, end) in self.changes.iter().zip(self.changes.iter().skip(1)) {
+            let segment = Segment::new(start, end);
+            let duration = segment.seconds(segment.quarters);
+            if elapsed + duration >= seconds {
+                position = Some(quarters(start.time) + segment.quarters_in(seconds - elapsed));
+                break;
+            }
+            elapsed += duration;
+        }
+        let quarters = position.unwrap_or_else(|| {
+            let last = self.changes.last().unwrap();
+            quarters(last.time) + (seconds - elapsed) * last.bpm / 60.
+        });
+        let ticks = (quarters.max(0.) * ppq as f64).round() as u32;
+        Time::from_ticks(ticks, ppq)
+    }
+
+    /// Tempo changes approximating the map with instant ones, a ramp moving every `step`.
+    ///
+    /// Each step keeps the duration it has in the map.
+    pub(crate) fn instant_changes(&self, step: Ratio<u32>) -> Vec<(Time, f64)> {
+        let mut changes = vec![];
+        for (start, end) in self.changes.iter().zip(self.changes.iter().skip(1)) {
+            if end.ramp == Ramp::Instant {
+                changes.push((start.time, start.bpm));
+                continue;
+            }
+            let mut time = start.time;
+            while time < end.time {
+                let next =
+                    Time::from_whole_notes((time.whole_notes() + step).min(end.time.whole_notes()));
+                let seconds = self.to_seconds(next) - self.to_seconds(time);
+                changes.push((time, (quarters(next) - quarters(time)) * 60. / seconds));
+                time = next;
+            }
+        }
+        let last = self.changes.last().unwrap();
+        changes.push((last.time, last.bpm));
+        changes
+    }
+
+    /// Change starting the segment of `time`, along with the change ending it
+    fn segment(&self, time: Time) -> (&TempoChange, Option<&TempoChange>) {
+        let i = self.changes.partition_point(|c| c.time <= time).max(1);
+        (&self.changes[i - 1], self.changes.get(i))
+    }
+}
+
+impl Default for TempoMap {
+    fn default() -> Self {
+        TempoMap::new(DEFAULT_BPM).expect("valid default tempo")
+    }
+}
+
+/// `bpm` if it is a positive finite number of beats per minute
+fn check_bpm(bpm: f64) -> Result<f64> {
+    if bpm.is_finite() && bpm > 0. {
+        Ok(bpm)
+    } else {
+        Err(Error::InvalidTempo(bpm))
+    }
+}
+
+/// Quarter notes from the start until `time`
+fn quarters(time: Time) -> f64 {
+    time.to_f64() * 4.
+}
+
+/// Tempo between two changes, as a function of the quarter notes into it
+struct Segment {
+    from: f64,
+    to: f64,
+    ramp: Ramp,
+    quarters: f64,
+}
+
+impl Segment {
+    fn new(start: &TempoChange, end: &TempoChange) -> Self {
+        let ramp = if (end.bpm - start.bpm).abs() < 1e-9 {
+            Ramp::Instant
+        } else {
+            end.ramp
+        };
+        Segment {
+            from: start.bpm,
+            to: end.bpm,
+            ramp,
+            quarters: quarters(end.time) - quarters(start.time),
+        }
+    }
+
+    /// Tempo after `x` quarter notes
+    fn bpm(&self, x: f64) -> f64 {
+        let progress = x / self.quarters;
+        match self.ramp {
+            Ramp::Instant => self.from,
+            Ramp::Linear => self.from + (self.to - self.from) * progress,
+            Ramp::Exponential => self.from * (self.to / self.from).powf(progress),
+        }
+    }
+
+    /// Seconds lasted by the first `x` quarter notes
+    fn seconds(&self, x: f64) -> f64 {
+        match self.ramp {
+            Ramp::Instant => x * 60. / self.from,
+            Ramp::Linear => {
+                let slope = (self.to - self.from) / self.quarters;
+                60. / slope * (self.bpm(x) / self.from).ln()
+            }
+            Ramp::Exponential => {
+                let rate = (self.to / self.from).ln() / self.quarters;
+                60. / (self.from * rate) * (1. - (-rate * x).exp())
+            }
+        }
+    }
+
+    /// Quarter notes lasting `seconds`, inverse of `seconds`
+    fn quarters_in(&self, seconds: f64) -> f64 {
+        match self.ramp {
+            Ramp::Instant => seconds * self.from / 60.,
+            Ramp::Linear => {
+                let slope = (self.to - self.from) / self.quarters;
+                self.from * ((seconds * slope / 60.).exp() - 1.) / slope
+            }
+            Ramp::Exponential => {
+                let rate = (self.to / self.from).ln() / self.quarters;
+                -(1. - seconds * self.from * rate / 60.).ln() / rate
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn instant_changes() {
+        let mut tempo = TempoMap::default();
+        tempo.set(Time::new(3, 1, 1), 60., Ramp::Instant).unwrap();
+        tempo.set(Time::new(2, 1, 1), 240., Ramp::Instant).unwrap();
+        tempo.set(Time::default(), 60., Ramp::Linear).unwrap();
+        assert_eq!(tempo.changes().len(), 3);
+        assert_eq!(tempo.changes()[0].ramp, Ramp::Instant);
+        // One bar at 60, one at 240 and the rest at 60
+        assert!(close(tempo.to_seconds(Time::new(2, 1, 1)), 4.));
+        assert!(close(tempo.to_seconds(Time::new(2, 2, 2)), 4.5));
+        assert!(close(tempo.to_seconds(Time::new(4, 4, 2)), 10.));
+        assert_eq!(tempo.bpm(Time::new(2, 4, 4)), 240.);
+        assert_eq!(tempo.to_time(4.5, 480), Time::new(2, 2, 2));
+        assert_eq!(tempo.to_time(0., 480), Time::default());
+    }
+
+    #[test]
+    fn invalid_tempo() {
+        let mut tempo = TempoMap::default();
+        for bpm in [0., -60., f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                tempo.set(Time::new(2, 1, 1), bpm, Ramp::Linear),
+                Err(Error::InvalidTempo(_))
+            ));
+            assert!(TempoMap::new(bpm).is_err());
+        }
+        assert_eq!(tempo, TempoMap::default());
+        assert!(close(tempo.to_seconds(Time::new(2, 1, 1)), 2.));
+    }
+
+    #[test]
+    fn ramps() {
+        for ramp in [Ramp::Linear, Ramp::Exponential] {
+            let mut tempo = TempoMap::new(60.).unwrap();
+            tempo.set(Time::new(3, 1, 1), 180., ramp).unwrap();
+            // Faster than the start tempo and slower than the end one
+            let seconds = tempo.to_seconds(Time::new(3, 1, 1));
+            assert!(seconds < 8. && seconds > 8. / 3.);
+            assert!(close(tempo.bpm(Time::new(3, 1, 1)), 180.));
+            // Exact seconds of a small step, at the tempo of its middle
+            let (time, step) = (Time::new(2, 1, 1), Time::new(1, 1024, 2));
+            let elapsed = tempo.to_seconds(Time::from_whole_notes(
+                time.whole_notes() + step.whole_notes(),
+            )) - tempo.to_seconds(time);
+            let middle = tempo.bpm(Time::new(2, 2048, 2));
+            assert!((elapsed - 4. / 1024. * 60. / middle).abs() < 1e-7);
+            for ticks in (0..4000).step_by(37) {
+                let time = Time::from_ticks(ticks, 96);
+                assert_eq!(tempo.to_time(tempo.to_seconds(time), 96), time);
+            }
+        }
+        let mut linear = TempoMap::new(60.).unwrap();
+        linear.set(Time::new(2, 1, 1), 120., Ramp::Linear).unwrap();
+        assert!(close(linear.to_seconds(Time::new(2, 1, 1)), 4. * 2f64.ln()));
+        let mut exponential = TempoMap::new(60.).unwrap();
+        exponential
+            .set(Time::new(2, 1, 1), 120., Ramp::Exponential)
+            .unwrap();
+        assert!(close(
+            exponential.bpm(Time::new(1, 2, 2)),
+            60. * 2f64.sqrt()
+        ));
+        assert!(close(
+            exponential.to_seconds(Time::new(2, 1, 1)),
+            2. / 2f64.ln()
+        ));
+    }
+
+    #[test]
+    fn approximation() {
+        let mut tempo = TempoMap::new(100.).unwrap();
+        tempo.set(Time::new(2, 1, 1), 50., Ramp::Linear).unwrap();
+        tempo.set(Time::new(3, 1, 1), 80., Ramp::Instant).unwrap();
+        let changes = tempo.instant_changes(Ratio::new(1, 16));
+        assert_eq!(changes.len(), 18);
+        assert_eq!(changes[17], (Time::new(3, 1, 1), 80.));
+        // Seconds match the map at every step
+        let mut elapsed = 0.;
+        for (change, next) in changes.iter().zip(changes.iter().skip(1)) {
+            elapsed += (quarters(next.0) - quarters(change.0)) * 60. / change.1;
+            assert!(close(elapsed, tempo.to_seconds(next.0)));
+        }
+    }
+}
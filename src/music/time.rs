@@ -1,25 +1,27 @@
 //! Time references
 
 use crate::music::duration::Duration;
-use num::integer::lcm;
-use std::ops;
+use num::rational::Ratio;
+use num::{CheckedAdd, CheckedSub};
+use std::{fmt, ops};
 
-/// Time reference
-#[derive(Clone, Copy, Debug)]
+/// Time reference, an exact number of whole notes since the start
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Time {
-    /// starts at 1
-    pub bar: u32,
-    /// divide the bar in equal parts
-    pub divisions: u32,
-    /// nth division placement
-    pub position: u32,
+    whole_notes: Ratio<u32>,
 }
 
 impl Time {
     /// Creates a new `Time` from a bar, divisions and position.
     ///
-    /// A `Time` locates an event by dividing a given var in `divisions` equal pieces
-    /// and by specifying a `position` for the event.
+    /// A `Time` locates an event by dividing a given bar in `divisions` equal pieces
+    /// and by specifying a `position` for the event. Bars and positions start at 1,
+    /// a bar lasting a whole note.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bar`, `divisions` or `position` is zero, or if the time is too far
+    /// to be represented. See [`Time::checked_new`] for a non-panicking version.
     ///
     /// # Examples
     ///
@@ -28,19 +30,83 @@ impl Time {
     /// ```
     /// use mumuse::music::time::Time;
     /// let t = Time::new(1, 4, 1);
+    /// assert_eq!(Time::new(2, 8, 5), Time::new(2, 2, 2));
     /// ```
     pub fn new(bar: u32, divisions: u32, position: u32) -> Self {
-        Time {
-            bar, 
-            divisions, 
-            position, 
+        Time::checked_new(bar, divisions, position).expect("invalid time")
+    }
+
+    /// Creates a new `Time` from a bar, divisions and position, `None` if
+    /// `bar`, `divisions` or `position` is zero or if the time is too far.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::time::Time;
+    /// assert_eq!(Time::checked_new(2, 4, 3), Some(Time::new(2, 2, 2)));
+    /// assert_eq!(Time::checked_new(0, 4, 1), None);
+    /// assert_eq!(Time::checked_new(1, 0, 1), None);
+    /// ```
+    pub fn checked_new(bar: u32, divisions: u32, position: u32) -> Option<Self> {
+        if bar == 0 || divisions == 0 || position == 0 {
+            return None;
         }
+        let whole_notes =
+            Ratio::from_integer(bar - 1).checked_add(&Ratio::new(position - 1, divisions))?;
+        Some(Time { whole_notes })
+    }
+
+    /// Creates a `Time` from a number of whole notes since the start.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::time::Time;
+    /// use num::rational::Ratio;
+    /// let t = Time::from_whole_notes(Ratio::new(5, 4));
+    /// assert_eq!(t, Time::new(2, 4, 2));
+    /// ```
+    pub fn from_whole_notes(whole_notes: Ratio<u32>) -> Self {
+        Time { whole_notes }
+    }
+
+    /// Whole notes since the start, in lowest terms
+    pub fn whole_notes(&self) -> Ratio<u32> {
+        self.whole_notes
+    }
+
+    /// Bar of a whole note, starting at 1
+    pub fn bar(&self) -> u32 {
+        self.whole_notes.to_integer() + 1
+    }
+
+    /// Divisions of the bar locating the time, the fewest possible
+    pub fn divisions(&self) -> u32 {
+        *self.whole_notes.denom()
+    }
+
+    /// Position within `divisions`, starting at 1
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::time::Time;
+    /// let t = Time::new(3, 12, 10);
+    /// assert_eq!((t.bar(), t.divisions(), t.position()), (3, 4, 4));
+    /// ```
+    pub fn position(&self) -> u32 {
+        self.whole_notes.fract().numer() + 1
     }
 
     /// Converts a `Time` to seconds as `f64`.
     ///
-    /// The conversion needs to have a given beats per minutes `bpm` value
-    /// and the number of beats per bar `bpb` in order to compute time in seconds.
+    /// The conversion needs a given beats per minutes `bpm` value, a beat being a quarter note.
     ///
     /// # Examples
     ///
@@ -48,12 +114,63 @@ impl Time {
     ///
     /// ```
     /// use mumuse::music::time::Time;
-    /// let t = Time::new(1, 4, 1);
-    /// let t_s = t.to_seconds(120.0, 4);
+    /// let t = Time::new(2, 4, 1);
+    /// assert_eq!(t.to_seconds(120.0), 2.);
     /// ```
-    pub fn to_seconds(&self, bpm: f64, bpb: u32) -> f64 {
-        let bar_duration = (bpb as f64) * 60. / bpm;
-        bar_duration * ((self.bar - 1) as f64 + (self.position - 1) as f64 / self.divisions as f64)
+    pub fn to_seconds(&self, bpm: f64) -> f64 {
+        self.to_f64() * 4. * 60. / bpm
+    }
+
+    /// Converts a `Time` to MIDI ticks, `ppq` ticks (pulses per quarter) lasting a quarter note.
+    ///
+    /// Positions falling between two ticks are rounded to the nearest one,
+    /// any other `Time` being converted exactly. Times beyond `u32::MAX` ticks
+    /// saturate to it.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::time::Time;
+    /// let t = Time::new(2, 4, 2);
+    /// assert_eq!(t.to_ticks(480), 2400);
+    /// assert_eq!(Time::new(1, 3, 2).to_ticks(24), 32); // MIDI clock
+    /// ```
+    pub fn to_ticks(&self, ppq: u16) -> u32 {
+        let (numer, denom) = (
+            *self.whole_notes.numer() as u64,
+            *self.whole_notes.denom() as u64,
+        );
+        let ticks = Ratio::new(numer * 4 * ppq as u64, denom);
+        u32::try_from(ticks.round().to_integer()).unwrap_or(u32::MAX)
+    }
+
+    /// Creates a `Time` from MIDI ticks, `ppq` ticks lasting a quarter note.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ppq` is zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::time::Time;
+    /// let t = Time::from_ticks(2400, 480);
+    /// assert_eq!(t, Time::new(2, 4, 2));
+    /// assert_eq!(t.to_ticks(480), 2400);
+    /// ```
+    pub fn from_ticks(ticks: u32, ppq: u16) -> Self {
+        Time::from_whole_notes(Ratio::new(ticks, 4 * ppq as u32))
+    }
+}
+
+impl Time {
+    /// Whole notes elapsed since the start, as `f64`
+    pub(crate) fn to_f64(self) -> f64 {
+        *self.whole_notes.numer() as f64 / *self.whole_notes.denom() as f64
     }
 }
 
@@ -61,9 +178,6 @@ impl ops::Add<Duration> for Time {
     type Output = Time;
     /// Add `Duration` to `Time`
     ///
-    /// The addition works by computing the least common multiple between
-    /// `Duration` and `Time` divisions and computing the addition in the new divisions.
-    ///
     /// # Examples
     ///
     /// Basic usage:
@@ -72,28 +186,65 @@ impl ops::Add<Duration> for Time {
     /// use mumuse::music::time::Time;
     /// use mumuse::music::duration::Duration;
     /// let t = Time::new(1, 4, 1);
-    /// let d = Duration::new(3, 1); // Triplet
-    /// let new_t = t + d;
+    /// let d = Duration::new(12, 1); // Triplet
+    /// assert_eq!(t + d, Time::new(1, 12, 2));
     /// ```
     fn add(self, rhs: Duration) -> Time {
-        let new_div = lcm(self.divisions, rhs.divisions);
-        let self_new_position: u32;
+        Time::from_whole_notes(self.whole_notes + rhs.to_ratio())
+    }
+}
 
-        // Positions start to 1 because we count beats as 1, 2, 3, ... until beats per nar.
-        if self.position != 1 {
-            self_new_position = self.position * new_div / self.divisions;
-        } else {
-            self_new_position = 1;
-        }
-        let mut new_pos = self_new_position + rhs.length * new_div / rhs.divisions;
-        let mut new_bar = self.bar;
+impl ops::AddAssign<Duration> for Time {
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
 
-        // Wrap up of time when end of bar is reached
-        if new_pos > new_div {
-            new_bar += new_pos / new_div;
-            new_pos %= new_div;
+impl ops::Sub<Duration> for Time {
+    type Output = Time;
+    /// Subtract `Duration` from `Time`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result would be before the start.
+    fn sub(self, rhs: Duration) -> Time {
+        let whole_notes = self.whole_notes.checked_sub(&rhs.to_ratio());
+        Time::from_whole_notes(whole_notes.expect("time before the start"))
+    }
+}
+
+impl ops::Sub<Time> for Time {
+    type Output = Duration;
+    /// Duration elapsed from `rhs` to `self`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rhs` is later than `self`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::time::Time;
+    /// let d = Time::new(2, 4, 2) - Time::new(1, 2, 2);
+    /// assert_eq!((d.divisions, d.length), (4, 3));
+    /// ```
+    fn sub(self, rhs: Time) -> Duration {
+        let whole_notes = self.whole_notes.checked_sub(&rhs.whole_notes);
+        Duration::from_ratio(whole_notes.expect("negative duration"))
+    }
+}
+
+/// Displays a `Time` as its bar followed by the whole notes elapsed in the bar, `3 + 3/8`
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let offset = self.whole_notes.fract();
+        if offset == Ratio::from_integer(0) {
+            write!(f, "{}", self.bar())
+        } else {
+            write!(f, "{} + {}", self.bar(), offset)
         }
-        Time::new(new_bar, new_div, new_pos)
     }
 }
 
@@ -104,7 +255,37 @@ mod tests {
     #[test]
     fn construct() {
         let time: Time = Time::new(1, 16, 1);
-        assert_eq!(time.divisions, 16);
+        assert_eq!(time.divisions(), 1);
+        assert_eq!(Time::new(1, 16, 3).divisions(), 8);
+        assert_eq!(Time::new(2, 3, 1).to_string(), "2");
+        assert_eq!(Time::new(3, 8, 4).to_string(), "3 + 3/8");
+        assert_eq!(Time::checked_new(1, 4, 0), None);
+        assert_eq!(Time::checked_new(u32::MAX, 4, u32::MAX), None);
+        assert!(Time::checked_new(u32::MAX, 4, 1).is_some());
+    }
+
+    #[test]
+    fn ordering() {
+        let mut times = vec![Time::new(2, 3, 1), Time::new(1, 4, 4), Time::new(1, 3, 3)];
+        times.sort();
+        assert_eq!(
+            times,
+            vec![Time::new(1, 3, 3), Time::new(1, 4, 4), Time::new(2, 1, 1)]
+        );
+        assert!(Time::new(1, 7, 2) > Time::new(1, 8, 2));
+    }
+
+    #[test]
+    fn to_ticks() {
+        assert_eq!(Time::new(1, 4, 1).to_ticks(96), 0);
+        assert_eq!(Time::new(1, 16, 2).to_ticks(96), 24);
+        assert_eq!(Time::new(3, 3, 2).to_ticks(96), 2 * 384 + 128);
+        assert_eq!(Time::new(1, 5, 2).to_ticks(96), 77);
+        // Every tick converts back to the same time
+        for ticks in 0..2000 {
+            assert_eq!(Time::from_ticks(ticks, 96).to_ticks(96), ticks);
+        }
+        assert_eq!(Time::new(u32::MAX, 1, 1).to_ticks(96), u32::MAX);
     }
 
     #[test]
@@ -112,9 +293,9 @@ mod tests {
         let time: Time = Time::new(1, 4, 1);
         let duration: Duration = Duration::new(4, 2);
         let sum = time + duration;
-        assert_eq!(sum.divisions, 4);
-        assert_eq!(sum.position, 3);
-        assert_eq!(sum.bar, 1);
+        assert_eq!(sum.divisions(), 2);
+        assert_eq!(sum.position(), 2);
+        assert_eq!(sum.bar(), 1);
     }
 
     #[test]
@@ -122,9 +303,12 @@ mod tests {
         let time: Time = Time::new(1, 4, 2);
         let duration: Duration = Duration::new(4, 1);
         let sum = time + duration;
-        assert_eq!(sum.divisions, 4);
-        assert_eq!(sum.position, 3);
-        assert_eq!(sum.bar, 1);
+        assert_eq!(sum, Time::new(1, 4, 3));
+        // Across different divisions
+        assert_eq!(
+            Time::new(1, 4, 2) + Duration::new(3, 1),
+            Time::new(1, 12, 8)
+        );
     }
 
     #[test]
@@ -132,9 +316,9 @@ mod tests {
         let time: Time = Time::new(1, 4, 1);
         let duration: Duration = Duration::new(3, 1);
         let sum = time + duration;
-        assert_eq!(sum.divisions, 12);
-        assert_eq!(sum.position, 5);
-        assert_eq!(sum.bar, 1);
+        assert_eq!(sum.divisions(), 3);
+        assert_eq!(sum.position(), 2);
+        assert_eq!(sum.bar(), 1);
     }
 
     #[test]
@@ -142,8 +326,19 @@ mod tests {
         let time: Time = Time::new(1, 4, 1);
         let duration: Duration = Duration::new(3, 3);
         let sum = time + duration;
-        assert_eq!(sum.divisions, 12);
-        assert_eq!(sum.position, 1);
-        assert_eq!(sum.bar, 2);
+        assert_eq!(sum, Time::new(2, 1, 1));
+        let mut time = Time::new(1, 4, 4);
+        time += Duration::new(1, 1);
+        assert_eq!(time, Time::new(2, 4, 4));
+    }
+
+    #[test]
+    fn time_difference() {
+        let start = Time::new(1, 8, 3);
+        let end = Time::new(3, 6, 2);
+        let duration = end - start;
+        assert_eq!((duration.divisions, duration.length), (12, 23));
+        assert_eq!(start + duration, end);
+        assert_eq!(end - duration, start);
     }
 }
@@ -0,0 +1,748 @@
+//! Pitch reference, tuning systems and frequencies
+
+use crate::error::{Error, Result};
+use crate::sysex::{MtsFrequency, Universal, MAX_TUNING_CHANGES};
+use itertools::Itertools;
+use std::fs;
+use std::path::Path;
+
+/// Semitones from C0 to A4
+pub(crate) const A4_SEMITONES: i32 = 57;
+
+/// Concert pitch reference, given by the frequency of A4 in Hz
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tuning {
+    pub a4: f64,
+}
+
+impl Tuning {
+    /// ISO 16 concert pitch, A4 = 440 Hz
+    pub const STANDARD: Tuning = Tuning { a4: 440.0 };
+
+    /// Construct Tuning from the frequency of A4 in Hz.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tuning::Tuning;
+    /// let baroque = Tuning::new(415.0);
+    /// ```
+    pub fn new(a4: f64) -> Self {
+        Tuning { a4 }
+    }
+
+    /// Frequency in Hz of a pitch `semitones` away from A4, fractions being allowed.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tuning::Tuning;
+    /// assert_eq!(Tuning::STANDARD.frequency(12.0), 880.0);
+    /// ```
+    pub fn frequency(&self, semitones: f64) -> f64 {
+        self.a4 * 2f64.powf(semitones / 12.0)
+    }
+
+    /// Distance in semitones from A4 to `frequency` in Hz, inverse of `frequency`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tuning::Tuning;
+    /// assert_eq!(Tuning::new(442.0).semitones(221.0), -12.0);
+    /// ```
+    pub fn semitones(&self, frequency: f64) -> f64 {
+        12.0 * (frequency / self.a4).log2()
+    }
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning::STANDARD
+    }
+}
+
+/// Distance in cents from frequency `from` to frequency `to`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mumuse::music::tuning::cents;
+/// assert_eq!(cents(440.0, 880.0), 1200.0);
+/// ```
+pub fn cents(from: f64, to: f64) -> f64 {
+    1200.0 * (to / from).log2()
+}
+
+/// Cents of a just perfect fifth, 3/2
+const PURE_FIFTH: f64 = 701.955_000_865_387_4;
+
+/// Cents of the syntonic comma, 81/80
+const SYNTONIC_COMMA: f64 = 21.506_289_596_021_46;
+
+/// Pitches of a periodic scale, as in a Scala `.scl` file
+///
+/// Degree 0 is the implicit 1/1, `pitches` holding the cents of the next degrees,
+/// the last one being the period (usually the octave).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Temperament {
+    pub description: String,
+    pub pitches: Vec<f64>,
+}
+
+impl Temperament {
+    /// Equal division of the octave in `divisions` steps.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tuning::Temperament;
+    /// let edo = Temperament::equal(19).unwrap();
+    /// assert_eq!(edo.len(), 19);
+    /// assert_eq!(edo.period(), 1200.0);
+    /// assert!(Temperament::equal(0).is_err());
+    /// ```
+    pub fn equal(divisions: u16) -> Result<Self> {
+        let description = format!("{} equal divisions of the octave", divisions);
+        if divisions == 0 {
+            return Err(Error::InvalidTemperament(description));
+        }
+        Ok(Temperament {
+            description,
+            pitches: (1..=divisions)
+                .map(|i| 1200.0 * i as f64 / divisions as f64)
+                .collect(),
+        })
+    }
+
+    /// Twelve tone five-limit just intonation from the 1/1.
+    pub fn just() -> Self {
+        let ratios = [
+            (16, 15),
+            (9, 8),
+            (6, 5),
+            (5, 4),
+            (4, 3),
+            (45, 32),
+            (3, 2),
+            (8, 5),
+            (5, 3),
+            (9, 5),
+            (15, 8),
+            (2, 1),
+        ];
+        Temperament {
+            description: "5-limit just intonation".to_string(),
+            pitches: ratios.iter().map(|(n, d)| ratio_cents(*n, *d)).collect(),
+        }
+    }
+
+    /// Twelve tone Pythagorean tuning, a chain of pure fifths from Eb to G#.
+    pub fn pythagorean() -> Self {
+        Temperament {
+            description: "Pythagorean tuning".to_string(),
+            ..Temperament::meantone(0.0)
+        }
+    }
+
+    /// Twelve tone meantone, a chain of fifths from Eb to G# each narrowed by
+    /// `comma_fraction` of a syntonic comma.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tuning::Temperament;
+    /// let quarter_comma = Temperament::meantone(0.25);
+    /// let major_third = quarter_comma.cents(4);
+    /// assert!((major_third - 386.3137).abs() < 1e-4); // Pure 5/4
+    /// ```
+    pub fn meantone(comma_fraction: f64) -> Self {
+        let fifth = PURE_FIFTH - comma_fraction * SYNTONIC_COMMA;
+        let mut pitches = [0.0; 12];
+        for i in -3..=8i32 {
+            pitches[(7 * i).rem_euclid(12) as usize] = (i as f64 * fifth).rem_euclid(1200.0);
+        }
+        Temperament {
+            description: format!("{} comma meantone", comma_fraction),
+            pitches: pitches[1..].iter().copied().chain([1200.0]).collect(),
+        }
+    }
+
+    /// Parses the content of a Scala `.scl` file.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tuning::Temperament;
+    /// let scl = "! pelog.scl\n!\nPelog approximation\n 5\n!\n 120.\n 270.0\n 540.\n 670.\n 2/1\n";
+    /// let pelog = Temperament::from_scl(scl).unwrap();
+    /// assert_eq!(pelog.pitches, vec![120.0, 270.0, 540.0, 670.0, 1200.0]);
+    /// ```
+    pub fn from_scl(s: &str) -> Result<Self> {
+        let mut lines = s.lines().filter(|line| !line.starts_with('!'));
+        let description = lines
+            .next()
+            .ok_or_else(|| Error::parse(s, s.len(), "description"))?
+            .trim()
+            .to_string();
+        let count_line = lines
+            .next()
+            .ok_or_else(|| Error::parse(s, s.len(), "number of notes"))?;
+        let (position, count) = first_token(count_line);
+        let count: usize = count
+            .parse()
+            .ok()
+            .filter(|count| *count > 0)
+            .ok_or_else(|| Error::parse(count_line, position, "number of notes"))?;
+        let pitches = lines
+            .take(count)
+            .map(parse_pitch)
+            .collect::<Result<Vec<f64>>>()?;
+        if pitches.len() < count {
+            return Err(Error::parse(s, s.len(), "pitch value"));
+        }
+        Ok(Temperament {
+            description,
+            pitches,
+        })
+    }
+
+    /// Loads a Scala `.scl` file.
+    pub fn load_scl<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Temperament::from_scl(&fs::read_to_string(path)?)
+    }
+
+    /// Number of degrees in a period
+    pub fn len(&self) -> usize {
+        self.pitches.len()
+    }
+
+    /// Always false, a temperament having at least its period
+    pub fn is_empty(&self) -> bool {
+        self.pitches.is_empty()
+    }
+
+    /// Interval in cents repeating the scale, usually the octave
+    pub fn period(&self) -> f64 {
+        self.pitches[self.pitches.len() - 1]
+    }
+
+    /// Cents from the 1/1 to any `degree`, repeating the scale every period.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tuning::Temperament;
+    /// let just = Temperament::just();
+    /// assert_eq!(just.cents(-12), -1200.0);
+    /// ```
+    pub fn cents(&self, degree: i32) -> f64 {
+        let len = self.len() as i32;
+        let index = degree.rem_euclid(len) as usize;
+        let base = if index == 0 {
+            0.0
+        } else {
+            self.pitches[index - 1]
+        };
+        degree.div_euclid(len) as f64 * self.period() + base
+    }
+}
+
+/// Assignment of temperament degrees to key numbers, as in a Scala `.kbm` file
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyboardMapping {
+    /// Lowest key to retune
+    pub first_key: u8,
+    /// Highest key to retune
+    pub last_key: u8,
+    /// Key where degree 0 is mapped
+    pub middle_key: u8,
+    /// Key tuned to `reference_frequency`
+    pub reference_key: u8,
+    /// Frequency in Hz of `reference_key`
+    pub reference_frequency: f64,
+    /// Degree of the formal octave between repetitions of `degrees`, 0 meaning the period
+    pub octave_degree: usize,
+    /// Degrees of consecutive keys from `middle_key`, `None` for unmapped keys.
+    /// When empty, every key is mapped to the next degree.
+    pub degrees: Vec<Option<usize>>,
+}
+
+impl KeyboardMapping {
+    /// Linear mapping of every key, degree 0 being on `middle_key`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tuning::KeyboardMapping;
+    /// let mapping = KeyboardMapping::linear(60, 69, 440.0);
+    /// assert_eq!(mapping.degree(62, 12), Some(2));
+    /// ```
+    pub fn linear(middle_key: u8, reference_key: u8, reference_frequency: f64) -> Self {
+        KeyboardMapping {
+            first_key: 0,
+            last_key: 127,
+            middle_key,
+            reference_key,
+            reference_frequency,
+            octave_degree: 0,
+            degrees: vec![],
+        }
+    }
+
+    /// Parses the content of a Scala `.kbm` file.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tuning::KeyboardMapping;
+    /// // White keys only, for a 7 notes scale
+    /// let kbm = "12\n0\n127\n60\n69\n440.0\n7\n0\nx\n1\nx\n2\n3\nx\n4\nx\n5\nx\n6\n";
+    /// let mapping = KeyboardMapping::from_kbm(kbm).unwrap();
+    /// assert_eq!(mapping.degree(72, 7), Some(7));
+    /// assert_eq!(mapping.degree(61, 7), None);
+    /// ```
+    pub fn from_kbm(s: &str) -> Result<Self> {
+        let lines = s
+            .lines()
+            .filter(|line| !line.starts_with('!') && !line.trim().is_empty())
+            .collect_vec();
+        let field = |index: usize, expected| -> Result<(&str, usize, &str)> {
+            let line = lines
+                .get(index)
+                .ok_or_else(|| Error::parse(s, s.len(), expected))?;
+            let (position, token) = first_token(line);
+            Ok((line, position, token))
+        };
+        let number = |index, expected| -> Result<usize> {
+            let (line, position, token) = field(index, expected)?;
+            token
+                .parse()
+                .map_err(|_| Error::parse(line, position, expected))
+        };
+        let key = |index, expected| -> Result<u8> {
+            let (line, position, token) = field(index, expected)?;
+            token
+                .parse()
+                .ok()
+                .filter(|key| *key < 128)
+                .ok_or_else(|| Error::parse(line, position, expected))
+        };
+        let size = number(0, "map size")?;
+        let first_key = key(1, "first key")?;
+        let last_key = key(2, "last key")?;
+        let middle_key = key(3, "middle key")?;
+        let reference_key = key(4, "reference key")?;
+        let (line, position, token) = field(5, "reference frequency")?;
+        let reference_frequency: f64 = token
+            .parse()
+            .ok()
+            .filter(|f: &f64| f.is_finite() && *f > 0.0)
+            .ok_or_else(|| Error::parse(line, position, "reference frequency"))?;
+        let octave_degree = number(6, "octave degree")?;
+        let degrees = (0..size)
+            .map(|i| match lines.get(7 + i).map(|line| first_token(line)) {
+                Some((_, "x")) => Ok(None),
+                Some((position, token)) => token
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| Error::parse(lines[7 + i], position, "scale degree")),
+                // Trailing unmapped keys may be left out
+                None => Ok(None),
+            })
+            .collect::<Result<Vec<Option<usize>>>>()?;
+        Ok(KeyboardMapping {
+            first_key,
+            last_key,
+            middle_key,
+            reference_key,
+            reference_frequency,
+            octave_degree,
+            degrees,
+        })
+    }
+
+    /// Loads a Scala `.kbm` file.
+    pub fn load_kbm<P: AsRef<Path>>(path: P) -> Result<Self> {
+        KeyboardMapping::from_kbm(&fs::read_to_string(path)?)
+    }
+
+    /// Degree mapped to `key` for a temperament of `len` degrees, `None` if unmapped.
+    pub fn degree(&self, key: u8, len: usize) -> Option<i32> {
+        if key < self.first_key || key > self.last_key {
+            return None;
+        }
+        self.mapped_degree(key, len)
+    }
+
+    /// Degree of `key` regardless of the retuned key range
+    fn mapped_degree(&self, key: u8, len: usize) -> Option<i32> {
+        let offset = key as i32 - self.middle_key as i32;
+        if self.degrees.is_empty() {
+            return Some(offset);
+        }
+        let size = self.degrees.len() as i32;
+        let octave = if self.octave_degree == 0 {
+            len
+        } else {
+            self.octave_degree
+        };
+        self.degrees[offset.rem_euclid(size) as usize]
+            .map(|degree| degree as i32 + offset.div_euclid(size) * octave as i32)
+    }
+}
+
+/// Frequencies of key numbers, from a `Temperament` laid out by a `KeyboardMapping`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TuningSystem {
+    pub temperament: Temperament,
+    pub mapping: KeyboardMapping,
+}
+
+impl TuningSystem {
+    /// Construct TuningSystem from a `Temperament` and a `KeyboardMapping`.
+    pub fn new(temperament: Temperament, mapping: KeyboardMapping) -> Self {
+        TuningSystem {
+            temperament,
+            mapping,
+        }
+    }
+
+    /// Linear mapping of `temperament` from C4 (key 60), A4 (key 69) being tuned to `tuning`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tuning::{Temperament, Tuning, TuningSystem};
+    /// let just = TuningSystem::with_reference(Temperament::just(), Tuning::STANDARD);
+    /// assert_eq!(just.frequency(69), Some(440.0));
+    /// assert!((just.frequency(60).unwrap() - 264.0).abs() < 1e-9);
+    /// ```
+    pub fn with_reference(temperament: Temperament, tuning: Tuning) -> Self {
+        TuningSystem::new(temperament, KeyboardMapping::linear(60, 69, tuning.a4))
+    }
+
+    /// Frequency in Hz of `key`, `None` if the key is not mapped.
+    pub fn frequency(&self, key: u8) -> Option<f64> {
+        let len = self.temperament.len();
+        let degree = self.mapping.degree(key, len)?;
+        let reference = self
+            .mapping
+            .mapped_degree(self.mapping.reference_key, len)?;
+        let cents = self.temperament.cents(degree) - self.temperament.cents(reference);
+        Some(self.mapping.reference_frequency * 2f64.powf(cents / 1200.0))
+    }
+
+    /// Nearest equal tempered key (A4 = 440 Hz) and pitch bend value to play `key`,
+    /// the bend spanning `bend_range` semitones each way.
+    ///
+    /// Returns `None` if `key` is not mapped or falls outside of the key range.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::messages::Midi;
+    /// use mumuse::music::tuning::{Temperament, Tuning, TuningSystem};
+    /// let just = TuningSystem::with_reference(Temperament::just(), Tuning::STANDARD);
+    /// let (key, bend) = just.pitch_bend(60, 2.0).unwrap();
+    /// let messages = [Midi::pitch_bend(0, bend), Midi::note_on(0, key, 100)];
+    /// ```
+    pub fn pitch_bend(&self, key: u8, bend_range: f64) -> Option<(u8, i16)> {
+        let semitones = Tuning::STANDARD.semitones(self.frequency(key)?) + 69.0;
+        let nearest = semitones.round();
+        if !(0.0..128.0).contains(&nearest) {
+            return None;
+        }
+        let bend = ((semitones - nearest) / bend_range * 8192.0).round();
+        Some((nearest as u8, bend.clamp(-8192.0, 8191.0) as i16))
+    }
+
+    /// MIDI Tuning Standard bulk dump of the 128 keys, unmapped keys being left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tuning::{Temperament, Tuning, TuningSystem};
+    /// use mumuse::sysex::ALL_CALL;
+    /// let edo = TuningSystem::with_reference(Temperament::equal(19).unwrap(), Tuning::STANDARD);
+    /// let dump = edo.to_tuning_dump(ALL_CALL, 0, "19-EDO").to_sysex();
+    /// ```
+    pub fn to_tuning_dump(&self, device: u8, program: u8, name: &str) -> Universal {
+        Universal::TuningDump {
+            device,
+            program,
+            name: name.to_string(),
+            frequencies: (0..128).map(|key| self.mts_frequency(key)).collect(),
+        }
+    }
+
+    /// MIDI Tuning Standard real time retuning of the mapped keys.
+    ///
+    /// Changes are split in messages of at most `MAX_TUNING_CHANGES` keys.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::tuning::TuningSystem;
+    /// let messages = TuningSystem::default().to_single_note_tuning(0x7F, 0);
+    /// assert_eq!(messages.len(), 2);
+    /// ```
+    pub fn to_single_note_tuning(&self, device: u8, program: u8) -> Vec<Universal> {
+        let changes = (0..128)
+            .map(|key| (key, self.mts_frequency(key)))
+            .filter(|(_, frequency)| *frequency != MtsFrequency::NO_CHANGE)
+            .collect_vec();
+        changes
+            .chunks(MAX_TUNING_CHANGES)
+            .map(|changes| Universal::SingleNoteTuning {
+                device,
+                program,
+                changes: changes.to_vec(),
+            })
+            .collect()
+    }
+
+    fn mts_frequency(&self, key: u8) -> MtsFrequency {
+        self.frequency(key)
+            .and_then(MtsFrequency::from_frequency)
+            .unwrap_or(MtsFrequency::NO_CHANGE)
+    }
+}
+
+impl Default for TuningSystem {
+    /// Twelve tone equal temperament at standard pitch
+    fn default() -> Self {
+        TuningSystem::with_reference(Temperament::equal(12).unwrap(), Tuning::STANDARD)
+    }
+}
+
+/// Cents of the ratio `numerator`/`denominator`
+fn ratio_cents(numerator: u64, denominator: u64) -> f64 {
+    1200.0 * (numerator as f64 / denominator as f64).log2()
+}
+
+/// First whitespace separated token of `line` and its position
+fn first_token(line: &str) -> (usize, &str) {
+    let trimmed = line.trim_start();
+    let position = line.len() - trimmed.len();
+    (position, trimmed.split_whitespace().next().unwrap_or(""))
+}
+
+/// Parses a Scala pitch line, in cents if it holds a period, as a ratio otherwise
+fn parse_pitch(line: &str) -> Result<f64> {
+    let (position, token) = first_token(line);
+    let error = || Error::parse(line, position, "pitch value");
+    if token.contains('.') {
+        return token.parse().map_err(|_| error());
+    }
+    let (numerator, denominator) = token.split_once('/').unwrap_or((token, "1"));
+    match (numerator.parse(), denominator.parse()) {
+        (Ok(n), Ok(d)) if n > 0 && d > 0 => Ok(ratio_cents(n, d)),
+        _ => Err(error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frequency_semitones_inverse() {
+        for a4 in [432.0, 440.0, 442.0] {
+            let tuning = Tuning::new(a4);
+            assert_eq!(tuning.frequency(0.0), a4);
+            for semitones in -60..60 {
+                let frequency = tuning.frequency(semitones as f64);
+                assert!((tuning.semitones(frequency) - semitones as f64).abs() < 1e-9);
+            }
+        }
+        assert!((cents(440.0, 442.0) - 7.851).abs() < 1e-3);
+    }
+
+    #[test]
+    fn equal_temperament_matches_notes() {
+        use crate::music::note::Note;
+        let system = TuningSystem::default();
+        for key in 0..128 {
+            let expected = Note::from_midi(key).unwrap().frequency(Tuning::STANDARD);
+            assert!((system.frequency(key).unwrap() - expected).abs() < 1e-9);
+            let (nearest, bend) = system.pitch_bend(key, 2.0).unwrap();
+            assert_eq!((nearest, bend), (key, 0));
+        }
+        assert!(matches!(
+            Temperament::equal(0),
+            Err(Error::InvalidTemperament(_))
+        ));
+    }
+
+    #[test]
+    fn historical_temperaments() {
+        let fifth = |t: &Temperament| t.cents(7);
+        let third = |t: &Temperament| t.cents(4);
+        assert!((fifth(&Temperament::pythagorean()) - PURE_FIFTH).abs() < 1e-9);
+        assert!((third(&Temperament::pythagorean()) - ratio_cents(81, 64)).abs() < 1e-9);
+        assert!((third(&Temperament::meantone(0.25)) - ratio_cents(5, 4)).abs() < 1e-9);
+        assert!((third(&Temperament::just()) - ratio_cents(5, 4)).abs() < 1e-9);
+        // Wolf fifth between G# and Eb
+        let pythagorean = Temperament::pythagorean();
+        let wolf = pythagorean.cents(15) - pythagorean.cents(8);
+        assert!((wolf - 678.495).abs() < 1e-3);
+        for temperament in [Temperament::pythagorean(), Temperament::meantone(1.0 / 3.0)] {
+            assert_eq!(temperament.len(), 12);
+            assert!(temperament.pitches.windows(2).all(|w| w[0] < w[1]));
+        }
+    }
+
+    #[test]
+    fn scala_files() {
+        let scl = "! meanquar.scl\n!\n1/4-comma meantone scale. Pietro Aaron's temperament (1523)\n 12\n!\n 76.04900\n 193.15686\n 310.26471\n 5/4\n 503.42157\n 579.47057\n 696.57843\n 25/16\n 889.73529\n 1006.84314\n 1082.89214\n 2/1 octave\n";
+        let scala = Temperament::from_scl(scl).unwrap();
+        let quarter_comma = Temperament::meantone(0.25);
+        assert_eq!(scala.len(), 12);
+        for degree in 0..12 {
+            assert!((scala.cents(degree) - quarter_comma.cents(degree)).abs() < 1e-4);
+        }
+
+        for (scl, position) in [
+            ("", 0),
+            ("desc\n", 5),
+            ("desc\n three\n", 1),
+            ("desc\n 2\n 100.0\n", 15),
+            ("desc\n 2\n 100.0\n -3/2\n", 1),
+            ("desc\n 1\n 3/0\n", 1),
+        ] {
+            match Temperament::from_scl(scl) {
+                Err(Error::Parse { position: p, .. }) => assert_eq!(p, position, "{:?}", scl),
+                other => panic!("{:?} parsed as {:?}", scl, other),
+            }
+        }
+    }
+
+    #[test]
+    fn keyboard_mappings() {
+        let kbm =
+            "! white.kbm\n12\n21\n108\n60\n69\n432.0\n7\n0\nx\n1\nx\n2\n3\nx\n4\nx\n5\nx\n6\n";
+        let mapping = KeyboardMapping::from_kbm(kbm).unwrap();
+        assert_eq!(mapping.degrees.len(), 12);
+        let system = TuningSystem::new(Temperament::just(), mapping);
+        assert_eq!(system.frequency(69), Some(432.0));
+        assert_eq!(system.frequency(20), None);
+        assert_eq!(system.frequency(61), None);
+        // Next white key is degree 1 of the just scale, a 16/15 above degree 0
+        let ratio = system.frequency(62).unwrap() / system.frequency(60).unwrap();
+        assert!((ratio - 16.0 / 15.0).abs() < 1e-9);
+        assert!(
+            (system.frequency(72).unwrap() / system.frequency(60).unwrap() - 3.0 / 2.0).abs()
+                < 1e-9
+        );
+
+        let truncated = KeyboardMapping::from_kbm("4\n0\n127\n60\n69\n440\n2\n0\n1\n").unwrap();
+        assert_eq!(truncated.degrees, vec![Some(0), Some(1), None, None]);
+        for kbm in [
+            "",
+            "0\n0\n128\n60\n69\n440\n0\n",
+            "0\n0\n127\n60\n69\n-440\n0\n",
+        ] {
+            assert!(matches!(
+                KeyboardMapping::from_kbm(kbm),
+                Err(Error::Parse { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn tuning_messages() {
+        let edo = TuningSystem::with_reference(Temperament::equal(24).unwrap(), Tuning::STANDARD);
+        // Quarter tone above A4, as close to A4 as to Bb4
+        let (key, bend) = edo.pitch_bend(70, 2.0).unwrap();
+        assert!((key, bend) == (69, 2048) || (key, bend) == (70, -2048));
+        assert_eq!(edo.pitch_bend(71, 2.0), Some((70, 0)));
+        let whole_tones =
+            TuningSystem::with_reference(Temperament::equal(6).unwrap(), Tuning::STANDARD);
+        assert_eq!(whole_tones.pitch_bend(127, 2.0), None);
+        assert_eq!(whole_tones.pitch_bend(0, 2.0), None);
+
+        match edo.to_tuning_dump(0x7F, 1, "24-EDO") {
+            Universal::TuningDump { frequencies, .. } => {
+                assert_eq!(frequencies.len(), 128);
+                assert_eq!(
+                    frequencies[69],
+                    MtsFrequency {
+                        semitone: 69,
+                        fraction: 0
+                    }
+                );
+                assert_eq!(
+                    frequencies[70],
+                    MtsFrequency {
+                        semitone: 69,
+                        fraction: 8192
+                    }
+                );
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match whole_tones.to_tuning_dump(0x7F, 1, "6-EDO") {
+            Universal::TuningDump { frequencies, .. } => {
+                assert_eq!(frequencies[127], MtsFrequency::NO_CHANGE);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match &whole_tones.to_single_note_tuning(0x7F, 1)[..] {
+            [Universal::SingleNoteTuning { changes, .. }] => {
+                assert!(changes.iter().all(|(_, f)| *f != MtsFrequency::NO_CHANGE));
+                assert!(changes.len() < 128);
+                assert!(changes.iter().any(|(key, _)| *key == 69));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn single_note_tuning_bytes() {
+        use crate::sysex::SysEx;
+        let messages = TuningSystem::default().to_single_note_tuning(0x7F, 0);
+        let mut keys = vec![];
+        for message in messages {
+            let bytes = message.to_sysex().to_bytes();
+            let sysex = SysEx::parse(&bytes[1..bytes.len() - 1]).unwrap();
+            match sysex.universal().unwrap().unwrap() {
+                Universal::SingleNoteTuning { changes, .. } => {
+                    assert_eq!(bytes[6] as usize, changes.len());
+                    assert_eq!(bytes.len(), 8 + 4 * changes.len());
+                    keys.extend(changes.iter().map(|(key, _)| *key));
+                }
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        assert_eq!(keys, (0..128).collect_vec());
+    }
+}
@@ -0,0 +1,351 @@
+//! Voice leading of chord progressions
+
+use crate::music::chord::Chord;
+use crate::music::common::Spelling;
+use crate::music::note::Note;
+use itertools::Itertools;
+
+/// Penalties of the common practice rules, against semitones of movement
+const PARALLEL_PENALTY: u32 = 100;
+const LEADING_TONE_PENALTY: u32 = 50;
+const OVERLAP_PENALTY: u32 = 10;
+
+/// Voicings of a chord kept for the search, the closest to the middle of the range
+const MAX_VOICINGS: usize = 256;
+
+/// Voices a chord progression with the smallest movement of the voices
+#[derive(Debug, Clone)]
+pub struct VoiceLeading {
+    /// Number of voices, chord tones being doubled or the fifth left out as needed
+    pub voices: usize,
+    /// Lowest note of the bass
+    pub lowest: Note,
+    /// Highest note of the upper voice
+    pub highest: Note,
+    /// Follows common practice rules: no parallel fifths and octaves,
+    /// no overlapping voices and leading tones resolved
+    pub rules: bool,
+}
+
+impl VoiceLeading {
+    /// Creates a `VoiceLeading` of `voices` between `lowest` and `highest`, without rules
+    pub fn new(voices: usize, lowest: Note, highest: Note) -> Self {
+        VoiceLeading {
+            voices,
+            lowest,
+            highest,
+            rules: false,
+        }
+    }
+
+    /// Voices `chords`, choosing inversions and octaves minimizing the total movement.
+    ///
+    /// The first note of each chord is taken as its root. Voices never cross and adjacent
+    /// upper voices stay within an octave. The first chord is placed around the middle
+    /// of the range. Chords that can't be voiced in the range are left empty.
+    ///
+    /// With fewer voices than chord tones only the fifth is left out, so a seventh chord
+    /// needs three voices. The search is quadratic in the voicings of consecutive chords:
+    /// only the 256 voicings of a chord closest to the middle of the range are kept.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::music::{key::Key, note::Note, voice_leading::VoiceLeading};
+    /// let key = Key::try_from("C major").unwrap();
+    /// let scale = key.scale(4);
+    /// let chords = [scale.two(4), scale.five(4), scale.one(4)];
+    /// let (lowest, highest) = (Note::try_from("C3").unwrap(), Note::try_from("G5").unwrap());
+    /// let mut leading = VoiceLeading::new(4, lowest, highest);
+    /// leading.rules = true;
+    /// let voiced = leading.lead(&chords);
+    /// assert_eq!(voiced[2].to_string(), "Chord(Note(G3),Note(C4),Note(E4),Note(B4))");
+    /// ```
+    pub fn lead(&self, chords: &[Chord]) -> Vec<Chord> {
+        let voicings = chords.iter().map(|c| self.voicings(c)).collect_vec();
+        let center = (self.lowest.semitones() + self.highest.semitones()) / 2;
+
+        // Cost of the best path to each voicing, along with the previous voicing
+        let mut paths: Vec<Vec<(u32, usize)>> = vec![];
+        for (index, candidates) in voicings.iter().enumerate() {
+            let previous = index
+                .checked_sub(1)
+                .map(|i| (&voicings[i], &paths[i], &chords[i]))
+                .filter(|(voicings, _, _)| !voicings.is_empty());
+            let costs = candidates
+                .iter()
+                .map(|voicing| match previous {
+                    None => {
+                        let spread = voicing.iter().map(|n| (n.semitones() - center).abs());
+                        (spread.sum::<i32>() as u32, 0)
+                    }
+                    Some((from, costs, chord)) => from
+                        .iter()
+                        .zip(costs.iter())
+                        .enumerate()
+                        .map(|(i, (from, (cost, _)))| {
+                            let motion = self.motion(from, voicing, chord, &chords[index]);
+                            (cost + motion, i)
+                        })
+                        .min()
+                        .unwrap(),
+                })
+                .collect();
+            paths.push(costs);
+        }
+
+        // Walks back the cheapest path
+        let mut voiced = vec![Chord::default(); chords.len()];
+        let mut best = None;
+        for index in (0..chords.len()).rev() {
+            let costs = &paths[index];
+            let choice = best.or_else(|| costs.iter().position_min());
+            best = None;
+            if let Some(choice) = choice {
+                voiced[index] = Chord::new(voicings[index][choice].clone());
+                best = Some(costs[choice].1).filter(|_| index > 0 && !paths[index - 1].is_empty());
+            }
+        }
+        voiced
+    }
+
+    /// Ascending voicings of the chord tones in the range, the closest to its middle
+    fn voicings(&self, chord: &Chord) -> Vec<Vec<Note>> {
+        let spellings = chord
+            .notes
+            .iter()
+            .map(|n| n.spelling())
+            .unique()
+            .collect_vec();
+        let (lowest, highest) = (self.lowest.semitones(), self.highest.semitones());
+        let pitches = spellings
+            .iter()
+            .flat_map(|spelling| (-1..=10).map(move |octave| Note::spelled(*spelling, octave)))
+            .filter(|n| (lowest..=highest).contains(&n.semitones()))
+            .sorted_by_key(|n| n.semitones())
+            .collect_vec();
+        // Only the fifth above the root may be left out
+        let required = spellings
+            .iter()
+            .filter(|spelling| {
+                let steps = spelling.step as i32 - spellings[0].step as i32;
+                self.voices >= spellings.len() || steps.rem_euclid(7) != 4
+            })
+            .collect_vec();
+        let center = (lowest + highest) / 2;
+        let mut voicings = vec![];
+        self.extend(&pitches, &required, &mut vec![], 0, &mut voicings);
+        voicings
+            .into_iter()
+            .enumerate()
+            .sorted_by_cached_key(|(_, voicing)| {
+                let spread = voicing.iter().map(|n| (n.semitones() - center).abs());
+                spread.sum::<i32>()
+            })
+            .take(MAX_VOICINGS)
+            .sorted_by_key(|(index, _)| *index)
+            .map(|(_, voicing)| voicing)
+            .collect()
+    }
+
+    /// Adds the ascending voicings completing `voicing` with `pitches` from `start`,
+    /// dropping partial voicings that can't stay close or cover the required tones
+    fn extend(
+        &self,
+        pitches: &[Note],
+        required: &[&Spelling],
+        voicing: &mut Vec<Note>,
+        start: usize,
+        voicings: &mut Vec<Vec<Note>>,
+    ) {
+        let remaining = self.voices - voicing.len();
+        let missing = required
+            .iter()
+            .filter(|spelling| !voicing.iter().any(|n| n.spelling() == ***spelling))
+            .count();
+        if missing > remaining {
+            return;
+        }
+        if remaining == 0 {
+            voicings.push(voicing.clone());
+            return;
+        }
+        for (index, note) in pitches.iter().enumerate().skip(start) {
+            if pitches.len() - index < remaining {
+                break;
+            }
+            // Adjacent upper voices stay within an octave
+            if let [_, .., last] = voicing.as_slice() {
+                if note.semitones() - last.semitones() > 12 {
+                    break;
+                }
+            }
+            voicing.push(*note);
+            self.extend(pitches, required, voicing, index + 1, voicings);
+            voicing.pop();
+        }
+    }
+
+    /// Cost of moving the voices from one voicing to the next
+    fn motion(&self, from: &[Note], to: &[Note], from_chord: &Chord, to_chord: &Chord) -> u32 {
+        let pitches = |voicing: &[Note]| voicing.iter().map(|n| n.semitones()).collect_vec();
+        let (from, to) = (pitches(from), pitches(to));
+        let mut cost = from
+            .iter()
+            .zip(&to)
+            .map(|(a, b)| (a - b).unsigned_abs())
+            .sum();
+        if !self.rules {
+            return cost;
+        }
+
+        for (i, j) in (0..from.len()).tuple_combinations() {
+            let (before, after) = (from[j] - from[i], to[j] - to[i]);
+            let perfect = |interval: i32| interval % 12 == 0 || interval % 12 == 7;
+            let (moves_i, moves_j) = (to[i] - from[i], to[j] - from[j]);
+            if perfect(before)
+                && before % 12 == after % 12
+                && moves_i != 0
+                && moves_i.signum() == moves_j.signum()
+            {
+                cost += PARALLEL_PENALTY;
+            }
+        }
+        for i in 1..from.len() {
+            if to[i] < from[i - 1] || to[i - 1] > from[i] {
+                cost += OVERLAP_PENALTY;
+            }
+        }
+
+        // The major third of a chord resolves up to the root a fifth below
+        let root = |chord: &Chord| chord.notes.first().map(|n| n.semitones());
+        if let (Some(from_root), Some(to_root)) = (root(from_chord), root(to_chord)) {
+            if (to_root - from_root).rem_euclid(12) == 5 {
+                let leading_tone = (from_root + 4).rem_euclid(12);
+                for (a, b) in from.iter().zip(&to) {
+                    if a.rem_euclid(12) == leading_tone && b - a != 1 {
+                        cost += LEADING_TONE_PENALTY;
+                    }
+                }
+            }
+        }
+        cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::key::Key;
+
+    fn note(s: &str) -> Note {
+        Note::try_from(s).unwrap()
+    }
+
+    /// Pairs of voices moving in parallel fifths or octaves
+    fn parallels(voiced: &[Chord]) -> usize {
+        voiced
+            .windows(2)
+            .map(|w| {
+                let (from, to) = (&w[0].notes, &w[1].notes);
+                (0..from.len())
+                    .tuple_combinations()
+                    .filter(|(i, j)| {
+                        let before = from[*j].semitones() - from[*i].semitones();
+                        let after = to[*j].semitones() - to[*i].semitones();
+                        (before % 12 == 0 || before % 12 == 7)
+                            && before % 12 == after % 12
+                            && from[*i] != to[*i]
+                    })
+                    .count()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn smallest_movement() {
+        let scale = Key::try_from("F major").unwrap().scale(3);
+        let chords = [scale.one(3), scale.four(3), scale.five(4), scale.one(3)];
+        let leading = VoiceLeading::new(4, note("F2"), note("F5"));
+        let voiced = leading.lead(&chords);
+        for (chord, voicing) in chords.iter().zip(&voiced) {
+            assert_eq!(voicing.notes.len(), 4);
+            assert!(voicing
+                .notes
+                .windows(2)
+                .all(|w| w[0].semitones() < w[1].semitones()));
+            // Every chord tone is there, the fifth of the seventh chord possibly left out
+            let tones = voicing.notes.iter().map(|n| n.spelling()).unique().count();
+            assert!(tones >= chord.notes.len().min(4) - 1);
+        }
+        // Voices move by step or common tone
+        for w in voiced.windows(2) {
+            for (a, b) in w[0].notes.iter().zip(&w[1].notes) {
+                assert!((a.semitones() - b.semitones()).abs() <= 5);
+            }
+        }
+        assert!(leading.lead(&[]).is_empty());
+    }
+
+    #[test]
+    fn common_practice() {
+        let scale = Key::try_from("C major").unwrap().scale(3);
+        let chords = [
+            scale.one(3),
+            scale.two(3),
+            scale.five(3),
+            scale.six(3),
+            scale.four(3),
+            scale.five(3),
+            scale.one(3),
+        ];
+        let mut leading = VoiceLeading::new(4, note("E2"), note("A5"));
+        leading.rules = true;
+        let voiced = leading.lead(&chords);
+        assert_eq!(parallels(&voiced), 0);
+
+        // Leading tone of the final dominant goes up to the tonic
+        let (dominant, tonic) = (&voiced[5].notes, &voiced[6].notes);
+        let b = dominant
+            .iter()
+            .position(|n| n.spelling().to_string() == "B")
+            .unwrap();
+        assert_eq!(tonic[b].spelling().to_string(), "C");
+        assert_eq!(tonic[b].semitones() - dominant[b].semitones(), 1);
+    }
+
+    #[test]
+    fn out_of_range() {
+        let chords = [Chord::from(vec!["C4", "E4", "G4"])];
+        let leading = VoiceLeading::new(3, note("C4"), note("D4"));
+        assert!(leading.lead(&chords)[0].notes.is_empty());
+        let leading = VoiceLeading::new(2, note("C4"), note("C5"));
+        let voiced = leading.lead(&chords);
+        assert_eq!(voiced[0].notes.len(), 2);
+        assert!(voiced[0].notes.contains(&note("C4")));
+    }
+
+    #[test]
+    fn fifth_left_out() {
+        let dominant = [Chord::from(vec!["G3", "B3", "D4", "F4"])];
+        let leading = VoiceLeading::new(3, note("C3"), note("C5"));
+        let voiced = leading.lead(&dominant);
+        let tones = voiced[0]
+            .notes
+            .iter()
+            .map(|n| n.spelling().to_string())
+            .sorted()
+            .collect_vec();
+        assert_eq!(tones, vec!["B", "F", "G"]);
+        let leading = VoiceLeading::new(2, note("C3"), note("C5"));
+        assert!(leading.lead(&dominant)[0].notes.is_empty());
+
+        // Wide ranges with many voices keep a bounded number of voicings
+        let scale = Key::try_from("C major").unwrap().scale(3);
+        let leading = VoiceLeading::new(6, note("C2"), note("C6"));
+        let voiced = leading.lead(&[scale.one(3), scale.five(3), scale.one(3)]);
+        assert!(voiced.iter().all(|chord| chord.notes.len() == 6));
+    }
+}
@@ -0,0 +1,757 @@
+//! Standard MIDI File (SMF) representation, reading and writing
+
+use crate::messages;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Default resolution in pulses (ticks) per quarter note
+pub const DEFAULT_PPQ: u16 = 480;
+
+/// Velocity used for notes that do not carry one
+pub const DEFAULT_VELOCITY: u8 = 64;
+
+/// Sequence number meta event type
+pub const META_SEQUENCE_NUMBER: u8 = 0x00;
+/// Text meta event type
+pub const META_TEXT: u8 = 0x01;
+/// Track name meta event type
+pub const META_TRACK_NAME: u8 = 0x03;
+/// End of track meta event type
+pub const META_END_OF_TRACK: u8 = 0x2F;
+/// Set tempo meta event type
+pub const META_TEMPO: u8 = 0x51;
+/// Time signature meta event type
+pub const META_TIME_SIGNATURE: u8 = 0x58;
+/// Key signature meta event type
+pub const META_KEY_SIGNATURE: u8 = 0x59;
+
+/// Organisation of the tracks of a file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Format 0, a single multi-channel track
+    SingleTrack = 0,
+    /// Format 1, simultaneous tracks sharing the tempo map of the first one
+    MultiTrack = 1,
+    /// Format 2, independent single-track patterns
+    MultiSequence = 2,
+}
+
+/// Standard MIDI File contents
+#[derive(Clone, Debug, PartialEq)]
+pub struct Smf {
+    pub format: Format,
+    /// Pulses per quarter note
+    pub ppq: u16,
+    pub tracks: Vec<Track>,
+}
+
+/// Sequence of delta-timed events
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Track {
+    pub events: Vec<TrackEvent>,
+}
+
+/// Event preceded by the number of ticks since the previous one
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackEvent {
+    pub delta: u32,
+    pub kind: EventKind,
+}
+
+/// Content of a track event
+#[derive(Clone, Debug, PartialEq)]
+pub enum EventKind {
+    /// Channel message bytes, status byte included
+    Midi(Vec<u8>),
+    /// System exclusive bytes following the 0xF0 marker
+    SysEx(Vec<u8>),
+    /// Bytes following the 0xF7 marker, SysEx continuation or escaped messages
+    Escape(Vec<u8>),
+    /// Meta event type and data
+    Meta(u8, Vec<u8>),
+}
+
+/// Error raised while reading a Standard MIDI File
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Data ended while `expected` was being read at byte `position`
+    UnexpectedEnd {
+        position: usize,
+        expected: &'static str,
+    },
+    /// Chunk at byte `position` is not of the expected type
+    InvalidChunk {
+        position: usize,
+        expected: &'static str,
+    },
+    /// Header chunk declares an unknown format
+    UnsupportedFormat(u16),
+    /// Header chunk uses SMPTE time division instead of pulses per quarter note
+    SmpteDivision,
+    /// Header chunk declares zero pulses per quarter note
+    ZeroDivision,
+    /// Variable-length quantity at byte `position` is longer than 4 bytes
+    VariableLengthOverflow { position: usize },
+    /// Data byte found at `position` with no running status to apply
+    MissingRunningStatus { position: usize },
+    /// Status byte found at `position` where a data byte is expected
+    UnexpectedStatus { position: usize, byte: u8 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnexpectedEnd { position, expected } => {
+                write!(
+                    f,
+                    "unexpected end of data at byte {} while reading {}",
+                    position, expected
+                )
+            }
+            Error::InvalidChunk { position, expected } => {
+                write!(f, "expected {} chunk at byte {}", expected, position)
+            }
+            Error::UnsupportedFormat(format) => write!(f, "unsupported file format {}", format),
+            Error::SmpteDivision => write!(f, "SMPTE time division is not supported"),
+            Error::ZeroDivision => write!(f, "time division of zero pulses per quarter note"),
+            Error::VariableLengthOverflow { position } => {
+                write!(
+                    f,
+                    "variable-length quantity at byte {} exceeds 4 bytes",
+                    position
+                )
+            }
+            Error::MissingRunningStatus { position } => {
+                write!(f, "data byte at byte {} without running status", position)
+            }
+            Error::UnexpectedStatus { position, byte } => {
+                write!(
+                    f,
+                    "unexpected status byte {:#04x} at byte {}",
+                    byte, position
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Smf {
+    /// Creates an empty `Smf` with a given `format` and resolution.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::smf::{Format, Smf};
+    /// let smf = Smf::new(Format::SingleTrack, 480);
+    /// ```
+    pub fn new(format: Format, ppq: u16) -> Self {
+        Smf {
+            format,
+            ppq,
+            tracks: vec![],
+        }
+    }
+
+    /// Encodes the file into bytes, header chunk followed by track chunks.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::smf::{Format, Smf, Track};
+    /// let mut smf = Smf::new(Format::SingleTrack, 480);
+    /// smf.tracks.push(Track::new());
+    /// let bytes = smf.to_bytes();
+    /// assert_eq!(&bytes[0..4], b"MThd");
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"MThd");
+        bytes.extend_from_slice(&6u32.to_be_bytes());
+        bytes.extend_from_slice(&(self.format as u16).to_be_bytes());
+        bytes.extend_from_slice(&(self.tracks.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&self.ppq.to_be_bytes());
+        for track in &self.tracks {
+            bytes.extend(track.to_bytes());
+        }
+        bytes
+    }
+
+    /// Writes the encoded file to `writer`.
+    pub fn write<W: Write>(&self, writer: &mut W) -> crate::Result<()> {
+        Ok(writer.write_all(&self.to_bytes())?)
+    }
+
+    /// Writes the encoded file at `path`, creating or truncating it.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use mumuse::smf::{Format, Smf};
+    /// let smf = Smf::new(Format::SingleTrack, 480);
+    /// smf.save("empty.mid").unwrap();
+    /// ```
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        let mut file = File::create(path)?;
+        self.write(&mut file)
+    }
+
+    /// Parses a file from its bytes.
+    ///
+    /// Unknown chunks are skipped, running status is expanded so that every
+    /// `EventKind::Midi` holds its status byte.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::smf::{Format, Smf, Track};
+    /// let mut smf = Smf::new(Format::SingleTrack, 480);
+    /// smf.tracks.push(Track::new());
+    /// let parsed = Smf::parse(&smf.to_bytes()).unwrap();
+    /// assert_eq!(parsed.ppq, 480);
+    /// assert!(Smf::parse(b"MThd").is_err());
+    /// ```
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes, 0);
+
+        // Header chunk
+        let header_position = reader.position;
+        if reader.take(4, "header chunk")? != b"MThd" {
+            return Err(Error::InvalidChunk {
+                position: header_position,
+                expected: "MThd",
+            });
+        }
+        let header_len = reader.u32("header length")? as usize;
+        let header = reader.take(header_len, "header chunk")?;
+        if header.len() < 6 {
+            return Err(Error::UnexpectedEnd {
+                position: header_position + 8 + header.len(),
+                expected: "header chunk",
+            });
+        }
+        let format = match u16::from_be_bytes([header[0], header[1]]) {
+            0 => Format::SingleTrack,
+            1 => Format::MultiTrack,
+            2 => Format::MultiSequence,
+            f => return Err(Error::UnsupportedFormat(f)),
+        };
+        let n_tracks = u16::from_be_bytes([header[2], header[3]]);
+        let division = u16::from_be_bytes([header[4], header[5]]);
+        if division & 0x8000 != 0 {
+            return Err(Error::SmpteDivision);
+        }
+        if division == 0 {
+            return Err(Error::ZeroDivision);
+        }
+
+        // Track chunks, alien chunks are ignored
+        let mut smf = Smf::new(format, division);
+        while smf.tracks.len() < n_tracks as usize {
+            let chunk_position = reader.position;
+            let kind = reader.take(4, "track chunk")?;
+            let len = reader.u32("track length")? as usize;
+            let data_position = reader.position;
+            let data = reader.take(len, "track chunk")?;
+            if kind == b"MTrk" {
+                smf.tracks.push(Track::parse(data, data_position)?);
+            } else if !kind.iter().all(|b| b.is_ascii_alphanumeric()) {
+                return Err(Error::InvalidChunk {
+                    position: chunk_position,
+                    expected: "MTrk",
+                });
+            }
+        }
+        Ok(smf)
+    }
+
+    /// Reads and parses the file at `path`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use mumuse::smf::Smf;
+    /// let smf = Smf::load("song.mid").unwrap();
+    /// ```
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        let mut bytes = vec![];
+        File::open(path)?.read_to_end(&mut bytes)?;
+        Ok(Smf::parse(&bytes)?)
+    }
+
+    /// Tempo changes as absolute ticks and beats (quarter notes) per minute.
+    ///
+    /// For format 2 files, ticks are counted from the start of each track.
+    /// A default tempo of 120 bpm is assumed until the first tempo event.
+    pub fn tempo_map(&self) -> Vec<(u32, f64)> {
+        let mut tempos: Vec<(u32, f64)> = self
+            .meta_events(META_TEMPO)
+            .into_iter()
+            .filter(|(_, data)| data.len() == 3)
+            .map(|(tick, data)| {
+                let us = u32::from_be_bytes([0, data[0], data[1], data[2]]).max(1);
+                (tick, 60_000_000.0 / us as f64)
+            })
+            .collect();
+        if tempos.first().map_or(true, |(tick, _)| *tick != 0) {
+            tempos.insert(0, (0, 120.0));
+        }
+        tempos
+    }
+
+    /// Time signature changes as absolute ticks, numerator and denominator.
+    ///
+    /// A default 4/4 signature is assumed until the first time signature event.
+    pub fn time_signatures(&self) -> Vec<(u32, u8, u8)> {
+        let mut signatures: Vec<(u32, u8, u8)> = self
+            .meta_events(META_TIME_SIGNATURE)
+            .into_iter()
+            .filter(|(_, data)| data.len() >= 2 && data[1] < 8)
+            .map(|(tick, data)| (tick, data[0], 1 << data[1]))
+            .collect();
+        if signatures.first().map_or(true, |(tick, _, _)| *tick != 0) {
+            signatures.insert(0, (0, 4, 4));
+        }
+        signatures
+    }
+
+    /// Meta events of a given type from all tracks, sorted by absolute tick
+    fn meta_events(&self, meta: u8) -> Vec<(u32, &[u8])> {
+        let mut events = vec![];
+        for track in &self.tracks {
+            for (tick, kind) in track.absolute() {
+                if let EventKind::Meta(kind, data) = kind {
+                    if *kind == meta {
+                        events.push((tick, &data[..]));
+                    }
+                }
+            }
+        }
+        events.sort_by_key(|(tick, _)| *tick);
+        events
+    }
+}
+
+impl Track {
+    /// Creates an empty `Track`.
+    pub fn new() -> Self {
+        Track { events: vec![] }
+    }
+
+    /// Appends an event `delta` ticks after the previous one.
+    pub fn push(&mut self, delta: u32, kind: EventKind) {
+        self.events.push(TrackEvent { delta, kind });
+    }
+
+    /// Builds a track from events located at absolute ticks.
+    ///
+    /// Events are sorted by tick, keeping insertion order for equal ticks.
+    pub fn from_absolute(mut events: Vec<(u32, EventKind)>) -> Self {
+        events.sort_by_key(|(tick, _)| *tick);
+        let mut track = Track::new();
+        let mut last_tick = 0;
+        for (tick, kind) in events {
+            track.push(tick - last_tick, kind);
+            last_tick = tick;
+        }
+        track
+    }
+
+    /// Events located at absolute ticks from the start of the track.
+    pub fn absolute(&self) -> Vec<(u32, &EventKind)> {
+        let mut tick: u32 = 0;
+        self.events
+            .iter()
+            .map(|event| {
+                tick = tick.saturating_add(event.delta);
+                (tick, &event.kind)
+            })
+            .collect()
+    }
+
+    /// Parses the content of a track chunk starting at byte `offset` of the file
+    fn parse(data: &[u8], offset: usize) -> Result<Self, Error> {
+        let mut reader = Reader::new(data, offset);
+        let mut track = Track::new();
+        let mut running_status: Option<u8> = None;
+
+        while reader.position < data.len() {
+            let delta = reader.variable_length()?;
+            let position = reader.offset + reader.position;
+            let byte = reader.u8("event")?;
+            let kind = match byte {
+                0xFF => {
+                    running_status = None;
+                    let meta = reader.u8("meta event type")?;
+                    let len = reader.variable_length()? as usize;
+                    EventKind::Meta(meta, reader.take(len, "meta event")?.to_vec())
+                }
+                0xF0 | 0xF7 => {
+                    running_status = None;
+                    let len = reader.variable_length()? as usize;
+                    let bytes = reader.take(len, "system exclusive event")?.to_vec();
+                    if byte == 0xF0 {
+                        EventKind::SysEx(bytes)
+                    } else {
+                        EventKind::Escape(bytes)
+                    }
+                }
+                0x80..=0xEF => {
+                    running_status = Some(byte);
+                    EventKind::Midi(reader.channel_message(byte, None)?)
+                }
+                0xF1..=0xFE => return Err(Error::UnexpectedStatus { position, byte }),
+                _ => match running_status {
+                    Some(status) => EventKind::Midi(reader.channel_message(status, Some(byte))?),
+                    None => return Err(Error::MissingRunningStatus { position }),
+                },
+            };
+            let end = matches!(kind, EventKind::Meta(META_END_OF_TRACK, _));
+            track.push(delta, kind);
+            if end {
+                break;
+            }
+        }
+        Ok(track)
+    }
+
+    /// Encodes the track chunk, appending an end of track event if missing.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        for event in &self.events {
+            write_variable_length(event.delta, &mut data);
+            match &event.kind {
+                EventKind::Midi(bytes) => data.extend_from_slice(bytes),
+                EventKind::SysEx(bytes) | EventKind::Escape(bytes) => {
+                    let marker = match event.kind {
+                        EventKind::SysEx(_) => 0xF0,
+                        _ => 0xF7,
+                    };
+                    data.push(marker);
+                    write_variable_length(bytes.len() as u32, &mut data);
+                    data.extend_from_slice(bytes);
+                }
+                EventKind::Meta(kind, bytes) => {
+                    data.push(0xFF);
+                    data.push(*kind);
+                    write_variable_length(bytes.len() as u32, &mut data);
+                    data.extend_from_slice(bytes);
+                }
+            }
+        }
+        let terminated = matches!(
+            self.events.last(),
+            Some(TrackEvent {
+                kind: EventKind::Meta(META_END_OF_TRACK, _),
+                ..
+            })
+        );
+        if !terminated {
+            data.extend_from_slice(&[0x00, 0xFF, META_END_OF_TRACK, 0x00]);
+        }
+
+        let mut bytes = Vec::with_capacity(data.len() + 8);
+        bytes.extend_from_slice(b"MTrk");
+        bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        bytes.extend(data);
+        bytes
+    }
+}
+
+impl EventKind {
+    /// Set tempo meta event from beats per minute (quarter note beats)
+    ///
+    /// The tempo is clamped to the 24 bits of the event, from about 3.58 bpm
+    /// to 60 000 000 bpm, the fastest tempo standing for negative or NaN `bpm`.
+    pub fn tempo(bpm: f64) -> Self {
+        let us_per_quarter = (60_000_000.0 / bpm).round().clamp(1.0, 0xFF_FFFF as f64);
+        // A NaN tempo is cast to 0
+        let us_per_quarter = (us_per_quarter as u32).max(1);
+        EventKind::Meta(META_TEMPO, us_per_quarter.to_be_bytes()[1..].to_vec())
+    }
+
+    /// Time signature meta event, `denominator` being a power of two
+    pub fn time_signature(numerator: u8, denominator: u8) -> Self {
+        let denominator_power = denominator.trailing_zeros() as u8;
+        EventKind::Meta(
+            META_TIME_SIGNATURE,
+            vec![numerator, denominator_power, 24, 8],
+        )
+    }
+
+    /// Track name meta event
+    pub fn track_name(name: &str) -> Self {
+        EventKind::Meta(META_TRACK_NAME, name.as_bytes().to_vec())
+    }
+}
+
+/// Byte cursor keeping track of its position for error reporting
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    /// Position of `bytes` in the file
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], offset: usize) -> Self {
+        Reader {
+            bytes,
+            position: 0,
+            offset,
+        }
+    }
+
+    fn take(&mut self, len: usize, expected: &'static str) -> Result<&'a [u8], Error> {
+        if self.bytes.len() - self.position < len {
+            return Err(Error::UnexpectedEnd {
+                position: self.offset + self.bytes.len(),
+                expected,
+            });
+        }
+        let slice = &self.bytes[self.position..self.position + len];
+        self.position += len;
+        Ok(slice)
+    }
+
+    fn u8(&mut self, expected: &'static str) -> Result<u8, Error> {
+        Ok(self.take(1, expected)?[0])
+    }
+
+    fn u32(&mut self, expected: &'static str) -> Result<u32, Error> {
+        let b = self.take(4, expected)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Reads a variable-length quantity
+    fn variable_length(&mut self) -> Result<u32, Error> {
+        let start = self.offset + self.position;
+        let mut value: u32 = 0;
+        for _ in 0..4 {
+            let byte = self.u8("variable-length quantity")?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Error::VariableLengthOverflow { position: start })
+    }
+
+    /// Reads the data bytes of a channel message, `first` being an already read data byte
+    fn channel_message(&mut self, status: u8, first: Option<u8>) -> Result<Vec<u8>, Error> {
+        let mut message = vec![status];
+        message.extend(first);
+        while message.len() < 1 + messages::data_length(status).unwrap_or(0) {
+            let position = self.offset + self.position;
+            let byte = self.u8("channel message")?;
+            if byte & 0x80 != 0 {
+                return Err(Error::UnexpectedStatus { position, byte });
+            }
+            message.push(byte);
+        }
+        Ok(message)
+    }
+}
+
+/// Appends `value` encoded as a variable-length quantity (7 bits per byte, at most 4 bytes)
+///
+/// Values above 0x0FFFFFFF, the largest quantity, are saturated to it.
+pub fn write_variable_length(value: u32, bytes: &mut Vec<u8>) {
+    let value = value.min(0x0FFF_FFFF);
+    let mut buffer = [0u8; 4];
+    let mut len = 0;
+    let mut rest = value;
+    loop {
+        buffer[len] = (rest & 0x7F) as u8;
+        len += 1;
+        rest >>= 7;
+        if rest == 0 {
+            break;
+        }
+    }
+    for i in (0..len).rev() {
+        let continuation = if i == 0 { 0x00 } else { 0x80 };
+        bytes.push(buffer[i] | continuation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variable_length() {
+        let cases: [(u32, &[u8]); 9] = [
+            (0x00, &[0x00]),
+            (0x40, &[0x40]),
+            (0x7F, &[0x7F]),
+            (0x80, &[0x81, 0x00]),
+            (0x2000, &[0xC0, 0x00]),
+            (0x3FFF, &[0xFF, 0x7F]),
+            (0x4000, &[0x81, 0x80, 0x00]),
+            (0x0FFF_FFFF, &[0xFF, 0xFF, 0xFF, 0x7F]),
+            (0x1000_0000, &[0xFF, 0xFF, 0xFF, 0x7F]),
+        ];
+        for (value, expected) in cases {
+            let mut bytes = vec![];
+            write_variable_length(value, &mut bytes);
+            assert_eq!(bytes, expected);
+        }
+    }
+
+    #[test]
+    fn header_chunk() {
+        let mut smf = Smf::new(Format::MultiTrack, 96);
+        smf.tracks.push(Track::new());
+        smf.tracks.push(Track::new());
+        let bytes = smf.to_bytes();
+        assert_eq!(
+            &bytes[0..14],
+            &[b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 1, 0, 2, 0, 96]
+        );
+    }
+
+    #[test]
+    fn track_chunk_terminated() {
+        let mut track = Track::new();
+        track.push(0, EventKind::Midi(vec![0x90, 60, 64]));
+        track.push(480, EventKind::Midi(vec![0x80, 60, 64]));
+        let bytes = track.to_bytes();
+        assert_eq!(&bytes[0..4], b"MTrk");
+        assert_eq!(&bytes[4..8], &[0, 0, 0, 13]);
+        assert_eq!(
+            &bytes[8..],
+            &[0x00, 0x90, 60, 64, 0x83, 0x60, 0x80, 60, 64, 0x00, 0xFF, 0x2F, 0x00]
+        );
+    }
+
+    #[test]
+    fn parse_round_trip() {
+        let mut smf = Smf::new(Format::MultiTrack, 96);
+        let mut conductor = Track::new();
+        conductor.push(0, EventKind::tempo(100.0));
+        conductor.push(0, EventKind::time_signature(6, 8));
+        conductor.push(0, EventKind::Meta(META_END_OF_TRACK, vec![]));
+        let mut notes = Track::new();
+        notes.push(0, EventKind::SysEx(vec![0x7E, 0x7F, 0x09, 0x01, 0xF7]));
+        notes.push(10, EventKind::Midi(vec![0x91, 60, 100]));
+        notes.push(200, EventKind::Midi(vec![0xC1, 5]));
+        notes.push(0, EventKind::Meta(META_END_OF_TRACK, vec![]));
+        smf.tracks = vec![conductor, notes];
+
+        let parsed = Smf::parse(&smf.to_bytes()).unwrap();
+        assert_eq!(parsed, smf);
+        assert_eq!(parsed.tempo_map(), vec![(0, 100.0)]);
+        assert_eq!(parsed.time_signatures(), vec![(0, 6, 8)]);
+    }
+
+    #[test]
+    fn parse_running_status() {
+        let mut bytes = Smf::new(Format::SingleTrack, 96).to_bytes();
+        bytes[11] = 1;
+        bytes.extend_from_slice(b"MTrk");
+        let events = [
+            0x00, 0x90, 60, 64, 0x10, 62, 64, 0x10, 60, 0, 0x00, 0xFF, 0x2F, 0x00,
+        ];
+        bytes.extend_from_slice(&(events.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&events);
+
+        let smf = Smf::parse(&bytes).unwrap();
+        let track = &smf.tracks[0];
+        assert_eq!(track.events.len(), 4);
+        assert_eq!(track.events[1].kind, EventKind::Midi(vec![0x90, 62, 64]));
+        assert_eq!(track.events[2].kind, EventKind::Midi(vec![0x90, 60, 0]));
+        assert_eq!(track.absolute()[2].0, 32);
+    }
+
+    #[test]
+    fn parse_malformed() {
+        let mut smf = Smf::new(Format::SingleTrack, 96);
+        let mut track = Track::new();
+        track.push(0, EventKind::Midi(vec![0x90, 60, 64]));
+        smf.tracks.push(track);
+        let bytes = smf.to_bytes();
+
+        // Truncated file
+        for len in 0..bytes.len() {
+            assert!(Smf::parse(&bytes[..len]).is_err());
+        }
+
+        // Data byte without running status
+        let mut no_status = bytes.clone();
+        no_status[23] = 0x3C;
+        assert!(matches!(
+            Smf::parse(&no_status),
+            Err(Error::MissingRunningStatus { position: 23 })
+        ));
+
+        // Null time division
+        let mut zero_division = bytes.clone();
+        zero_division[12..14].copy_from_slice(&[0, 0]);
+        assert_eq!(Smf::parse(&zero_division), Err(Error::ZeroDivision));
+
+        // Truncated meta and system exclusive events, located in the file
+        for (event, expected) in [
+            (&[0x00, 0xFF, 0x01, 0x05, b'a', b'b'][..], "meta event"),
+            (&[0x00, 0xF0, 0x04, 0x7E, 0x7F], "system exclusive event"),
+            (&[0x00, 0xFF], "meta event type"),
+        ] {
+            let mut truncated = bytes[..14].to_vec();
+            truncated.extend_from_slice(b"MTrk");
+            truncated.extend_from_slice(&(event.len() as u32).to_be_bytes());
+            truncated.extend_from_slice(event);
+            assert_eq!(
+                Smf::parse(&truncated),
+                Err(Error::UnexpectedEnd {
+                    position: truncated.len(),
+                    expected
+                })
+            );
+        }
+
+        // Bad header
+        let mut bad_header = bytes;
+        bad_header[0] = b'X';
+        assert!(matches!(
+            Smf::parse(&bad_header),
+            Err(Error::InvalidChunk { position: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn tempo_meta() {
+        assert_eq!(
+            EventKind::tempo(120.0),
+            EventKind::Meta(META_TEMPO, vec![0x07, 0xA1, 0x20])
+        );
+        for slow in [1.0, 0.0] {
+            assert_eq!(
+                EventKind::tempo(slow),
+                EventKind::Meta(META_TEMPO, vec![0xFF, 0xFF, 0xFF])
+            );
+        }
+        for fast in [f64::INFINITY, -60.0, f64::NAN] {
+            assert_eq!(
+                EventKind::tempo(fast),
+                EventKind::Meta(META_TEMPO, vec![0x00, 0x00, 0x01])
+            );
+        }
+        assert_eq!(
+            EventKind::time_signature(6, 8),
+            EventKind::Meta(META_TIME_SIGNATURE, vec![6, 3, 24, 8])
+        );
+    }
+}
@@ -0,0 +1,609 @@
+//! System Exclusive messages, manufacturer identifiers and universal messages
+
+use crate::messages::{Data, DecodeError, Midi, Status};
+use std::iter;
+
+/// Device ID addressing every device
+pub const ALL_CALL: u8 = 0x7F;
+
+/// Most changes a single note tuning message holds, its count being a data byte
+pub const MAX_TUNING_CHANGES: usize = 127;
+
+/// Manufacturer identifier, first bytes of a System Exclusive payload
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Manufacturer {
+    /// One byte identifier, from 0x01 to 0x7C
+    Standard(u8),
+    /// Three bytes identifier 0x00 followed by two bytes
+    Extended(u8, u8),
+    /// Non-commercial identifier 0x7D, for research and education
+    NonCommercial,
+    /// Universal Non-Real Time identifier 0x7E
+    UniversalNonRealTime,
+    /// Universal Real Time identifier 0x7F
+    UniversalRealTime,
+}
+
+impl Manufacturer {
+    pub const SEQUENTIAL: Manufacturer = Manufacturer::Standard(0x01);
+    pub const MOOG: Manufacturer = Manufacturer::Standard(0x04);
+    pub const OBERHEIM: Manufacturer = Manufacturer::Standard(0x10);
+    pub const KAWAI: Manufacturer = Manufacturer::Standard(0x40);
+    pub const ROLAND: Manufacturer = Manufacturer::Standard(0x41);
+    pub const KORG: Manufacturer = Manufacturer::Standard(0x42);
+    pub const YAMAHA: Manufacturer = Manufacturer::Standard(0x43);
+    pub const CASIO: Manufacturer = Manufacturer::Standard(0x44);
+    pub const AKAI: Manufacturer = Manufacturer::Standard(0x47);
+    pub const ELEKTRON: Manufacturer = Manufacturer::Extended(0x20, 0x3C);
+    pub const NOVATION: Manufacturer = Manufacturer::Extended(0x20, 0x29);
+    pub const ARTURIA: Manufacturer = Manufacturer::Extended(0x20, 0x6B);
+
+    /// Reads a manufacturer identifier at the start of `bytes`.
+    ///
+    /// Returns the identifier and its length in bytes.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::sysex::Manufacturer;
+    /// assert_eq!(Manufacturer::parse(&[0x41, 0x10]).unwrap(), (Manufacturer::ROLAND, 1));
+    /// assert_eq!(Manufacturer::parse(&[0x00, 0x20, 0x29]).unwrap(), (Manufacturer::NOVATION, 3));
+    /// ```
+    pub fn parse(bytes: &[u8]) -> Result<(Self, usize), DecodeError> {
+        match bytes {
+            [0x00, a, b, ..] => Ok((Manufacturer::Extended(*a, *b), 3)),
+            [0x00, ..] | [] => Err(DecodeError::MalformedExclusive {
+                position: bytes.len(),
+                expected: "manufacturer identifier",
+            }),
+            [0x7D, ..] => Ok((Manufacturer::NonCommercial, 1)),
+            [0x7E, ..] => Ok((Manufacturer::UniversalNonRealTime, 1)),
+            [0x7F, ..] => Ok((Manufacturer::UniversalRealTime, 1)),
+            [id, ..] => Ok((Manufacturer::Standard(*id), 1)),
+        }
+    }
+
+    /// Identifier bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match *self {
+            Manufacturer::Standard(id) => vec![id],
+            Manufacturer::Extended(a, b) => vec![0x00, a, b],
+            Manufacturer::NonCommercial => vec![0x7D],
+            Manufacturer::UniversalNonRealTime => vec![0x7E],
+            Manufacturer::UniversalRealTime => vec![0x7F],
+        }
+    }
+}
+
+/// System Exclusive message, a manufacturer and its data
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SysEx {
+    pub manufacturer: Manufacturer,
+    /// Bytes following the manufacturer identifier
+    pub data: Vec<u8>,
+}
+
+impl SysEx {
+    /// Creates a `SysEx` from a manufacturer and data bytes.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::sysex::{Manufacturer, SysEx};
+    /// let dump_request = SysEx::new(Manufacturer::KORG, vec![0x30, 0x00, 0x01, 0x10]);
+    /// ```
+    pub fn new(manufacturer: Manufacturer, data: Vec<u8>) -> Self {
+        SysEx { manufacturer, data }
+    }
+
+    /// Parses a payload, the bytes between 0xF0 and 0xF7.
+    pub fn parse(payload: &[u8]) -> Result<Self, DecodeError> {
+        let (manufacturer, len) = Manufacturer::parse(payload)?;
+        Ok(SysEx::new(manufacturer, payload[len..].to_vec()))
+    }
+
+    /// Payload bytes, without the 0xF0 and 0xF7 markers
+    pub fn to_payload(&self) -> Vec<u8> {
+        let mut payload = self.manufacturer.to_bytes();
+        payload.extend(self.data.iter().map(|b| b & 0x7F));
+        payload
+    }
+
+    /// Complete message bytes, 0xF0 and 0xF7 markers included
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_midi().to_bytes()
+    }
+
+    /// Converts into a `Status::SystemExclusive` message
+    pub fn to_midi(&self) -> Midi {
+        Midi::system_exclusive(self.to_payload())
+    }
+
+    /// Decodes a universal message, for universal manufacturer identifiers only.
+    pub fn universal(&self) -> Option<Result<Universal, DecodeError>> {
+        match self.manufacturer {
+            Manufacturer::UniversalNonRealTime | Manufacturer::UniversalRealTime => {
+                Some(Universal::parse(self))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<&Midi> for SysEx {
+    type Error = DecodeError;
+    /// Conversion from a `Status::SystemExclusive` message.
+    fn try_from(midi: &Midi) -> Result<Self, Self::Error> {
+        match (midi.status(), &midi.data()[0]) {
+            (Status::SystemExclusive, Data::Exclusive(payload)) => SysEx::parse(payload),
+            _ => Err(DecodeError::MalformedExclusive {
+                position: 0,
+                expected: "system exclusive status",
+            }),
+        }
+    }
+}
+
+/// Frequency in MIDI Tuning Standard format, a semitone and a fraction of semitone
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MtsFrequency {
+    /// Key number of the nearest equal tempered semitone below
+    pub semitone: u8,
+    /// Fraction of semitone above `semitone`, in 1/16384 units
+    pub fraction: u16,
+}
+
+impl MtsFrequency {
+    /// Reserved value meaning the tuning of the key must not change
+    pub const NO_CHANGE: MtsFrequency = MtsFrequency {
+        semitone: 0x7F,
+        fraction: 0x3FFF,
+    };
+
+    /// Closest representation of `frequency` in Hz, A4 (key 69) being 440 Hz.
+    ///
+    /// Returns `None` for frequencies outside of the key range.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::sysex::MtsFrequency;
+    /// let a = MtsFrequency::from_frequency(440.0).unwrap();
+    /// assert_eq!((a.semitone, a.fraction), (69, 0));
+    /// ```
+    pub fn from_frequency(frequency: f64) -> Option<Self> {
+        if frequency <= 0.0 {
+            return None;
+        }
+        let key = 69.0 + 12.0 * (frequency / 440.0).log2();
+        let units = (key * 16384.0).round();
+        if !(0.0..(127.0 * 16384.0 + 16383.0)).contains(&units) {
+            return None;
+        }
+        let units = units as u32;
+        Some(MtsFrequency {
+            semitone: (units >> 14) as u8,
+            fraction: (units & 0x3FFF) as u16,
+        })
+    }
+
+    /// Frequency in Hz, A4 (key 69) being 440 Hz
+    pub fn to_frequency(&self) -> f64 {
+        let key = self.semitone as f64 + self.fraction as f64 / 16384.0;
+        440.0 * 2f64.powf((key - 69.0) / 12.0)
+    }
+
+    /// Three data bytes of the frequency
+    pub fn to_bytes(&self) -> [u8; 3] {
+        [
+            self.semitone & 0x7F,
+            ((self.fraction >> 7) & 0x7F) as u8,
+            (self.fraction & 0x7F) as u8,
+        ]
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        MtsFrequency {
+            semitone: bytes[0],
+            fraction: ((bytes[1] as u16) << 7) | bytes[2] as u16,
+        }
+    }
+}
+
+/// Universal System Exclusive message, Real Time or Non-Real Time
+#[derive(Clone, Debug, PartialEq)]
+pub enum Universal {
+    /// Identity request (non-real time 06 01)
+    DeviceInquiryRequest { device: u8 },
+    /// Identity reply (non-real time 06 02)
+    DeviceInquiryReply {
+        device: u8,
+        manufacturer: Manufacturer,
+        family: u16,
+        member: u16,
+        version: [u8; 4],
+    },
+    /// General MIDI 1 system on (non-real time 09 01)
+    GeneralMidiOn { device: u8 },
+    /// General MIDI system off (non-real time 09 02)
+    GeneralMidiOff { device: u8 },
+    /// General MIDI 2 system on (non-real time 09 03)
+    GeneralMidi2On { device: u8 },
+    /// Master volume on 14 bits (real time 04 01)
+    MasterVolume { device: u8, volume: u16 },
+    /// MIDI Tuning Standard bulk dump request (non-real time 08 00)
+    TuningDumpRequest { device: u8, program: u8 },
+    /// MIDI Tuning Standard bulk dump of the 128 keys (non-real time 08 01)
+    TuningDump {
+        device: u8,
+        program: u8,
+        name: String,
+        frequencies: Vec<MtsFrequency>,
+    },
+    /// MIDI Tuning Standard single note tuning change (real time 08 02)
+    ///
+    /// Only the first `MAX_TUNING_CHANGES` changes are encoded.
+    SingleNoteTuning {
+        device: u8,
+        program: u8,
+        changes: Vec<(u8, MtsFrequency)>,
+    },
+    /// Any other universal message
+    Other {
+        real_time: bool,
+        device: u8,
+        sub_id: u8,
+        sub_id2: u8,
+        data: Vec<u8>,
+    },
+}
+
+impl Universal {
+    /// Decodes a universal message from a `SysEx`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::messages::Midi;
+    /// use mumuse::sysex::{SysEx, Universal};
+    /// let midi = Midi::system_exclusive(vec![0x7E, 0x7F, 0x06, 0x01]);
+    /// let sysex = SysEx::try_from(&midi).unwrap();
+    /// let inquiry = Universal::parse(&sysex).unwrap();
+    /// assert_eq!(inquiry, Universal::DeviceInquiryRequest { device: 0x7F });
+    /// ```
+    pub fn parse(sysex: &SysEx) -> Result<Self, DecodeError> {
+        let real_time = match sysex.manufacturer {
+            Manufacturer::UniversalRealTime => true,
+            Manufacturer::UniversalNonRealTime => false,
+            _ => {
+                return Err(DecodeError::MalformedExclusive {
+                    position: 0,
+                    expected: "universal identifier",
+                })
+            }
+        };
+        let data = &sysex.data[..];
+        let malformed = |expected| DecodeError::MalformedExclusive {
+            position: data.len() + 1,
+            expected,
+        };
+        let (device, sub_id, sub_id2, body) = match data {
+            [device, sub_id, sub_id2, body @ ..] => (*device, *sub_id, *sub_id2, body),
+            _ => return Err(malformed("sub identifiers")),
+        };
+
+        let universal = match (real_time, sub_id, sub_id2) {
+            (false, 0x06, 0x01) => Universal::DeviceInquiryRequest { device },
+            (false, 0x06, 0x02) => {
+                let (manufacturer, len) = Manufacturer::parse(body)?;
+                let rest = &body[len..];
+                if rest.len() < 8 {
+                    return Err(malformed("identity reply"));
+                }
+                Universal::DeviceInquiryReply {
+                    device,
+                    manufacturer,
+                    family: rest[0] as u16 | (rest[1] as u16) << 7,
+                    member: rest[2] as u16 | (rest[3] as u16) << 7,
+                    version: [rest[4], rest[5], rest[6], rest[7]],
+                }
+            }
+            (false, 0x09, 0x01) => Universal::GeneralMidiOn { device },
+            (false, 0x09, 0x02) => Universal::GeneralMidiOff { device },
+            (false, 0x09, 0x03) => Universal::GeneralMidi2On { device },
+            (true, 0x04, 0x01) => match body {
+                [lsb, msb, ..] => Universal::MasterVolume {
+                    device,
+                    volume: *lsb as u16 | (*msb as u16) << 7,
+                },
+                _ => return Err(malformed("master volume")),
+            },
+            (false, 0x08, 0x00) => match body {
+                [program, ..] => Universal::TuningDumpRequest {
+                    device,
+                    program: *program,
+                },
+                _ => return Err(malformed("tuning program")),
+            },
+            (false, 0x08, 0x01) => {
+                if body.len() != 1 + 16 + 128 * 3 + 1 {
+                    return Err(malformed("tuning dump"));
+                }
+                let (checksum, body) = body.split_last().unwrap();
+                let sum = [0x7E, device, 0x08, 0x01]
+                    .iter()
+                    .chain(body.iter())
+                    .fold(0, |sum, b| sum ^ b);
+                if sum & 0x7F != *checksum {
+                    return Err(malformed("tuning dump checksum"));
+                }
+                let name = body[1..17].iter().map(|b| *b as char).collect::<String>();
+                Universal::TuningDump {
+                    device,
+                    program: body[0],
+                    name: name.trim_end().to_string(),
+                    frequencies: body[17..17 + 128 * 3]
+                        .chunks(3)
+                        .map(MtsFrequency::from_bytes)
+                        .collect(),
+                }
+            }
+            (true, 0x08, 0x02) => {
+                let (program, count) = match body {
+                    [program, count, ..] => (*program, *count as usize),
+                    _ => return Err(malformed("tuning change count")),
+                };
+                if body.len() < 2 + count * 4 {
+                    return Err(malformed("tuning change"));
+                }
+                Universal::SingleNoteTuning {
+                    device,
+                    program,
+                    changes: body[2..2 + count * 4]
+                        .chunks(4)
+                        .map(|c| (c[0], MtsFrequency::from_bytes(&c[1..])))
+                        .collect(),
+                }
+            }
+            _ => Universal::Other {
+                real_time,
+                device,
+                sub_id,
+                sub_id2,
+                data: body.to_vec(),
+            },
+        };
+        Ok(universal)
+    }
+
+    /// Encodes into a `SysEx`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mumuse::sysex::{Universal, ALL_CALL};
+    /// let volume = Universal::MasterVolume { device: ALL_CALL, volume: 0x3FFF };
+    /// let bytes = volume.to_sysex().to_bytes();
+    /// assert_eq!(bytes, vec![0xF0, 0x7F, 0x7F, 0x04, 0x01, 0x7F, 0x7F, 0xF7]);
+    /// ```
+    pub fn to_sysex(&self) -> SysEx {
+        let (real_time, device, sub_id, sub_id2, body) = match self {
+            Universal::DeviceInquiryRequest { device } => (false, *device, 0x06, 0x01, vec![]),
+            Universal::DeviceInquiryReply {
+                device,
+                manufacturer,
+                family,
+                member,
+                version,
+            } => {
+                let mut body = manufacturer.to_bytes();
+                body.extend([
+                    (family & 0x7F) as u8,
+                    (family >> 7) as u8,
+                    (member & 0x7F) as u8,
+                    (member >> 7) as u8,
+                ]);
+                body.extend(version);
+                (false, *device, 0x06, 0x02, body)
+            }
+            Universal::GeneralMidiOn { device } => (false, *device, 0x09, 0x01, vec![]),
+            Universal::GeneralMidiOff { device } => (false, *device, 0x09, 0x02, vec![]),
+            Universal::GeneralMidi2On { device } => (false, *device, 0x09, 0x03, vec![]),
+            Universal::MasterVolume { device, volume } => (
+                true,
+                *device,
+                0x04,
+                0x01,
+                vec![(volume & 0x7F) as u8, (volume >> 7) as u8],
+            ),
+            Universal::TuningDumpRequest { device, program } => {
+                (false, *device, 0x08, 0x00, vec![*program])
+            }
+            Universal::TuningDump {
+                device,
+                program,
+                name,
+                frequencies,
+            } => {
+                let mut body = vec![*program];
+                // The name is exactly 16 ASCII bytes, non-ASCII characters are dropped
+                let name = name.bytes().filter(u8::is_ascii).chain(iter::repeat(b' '));
+                body.extend(name.take(16));
+                for i in 0..128 {
+                    let frequency = frequencies.get(i).unwrap_or(&MtsFrequency::NO_CHANGE);
+                    body.extend(frequency.to_bytes());
+                }
+                // Checksum is the XOR of every byte from the universal identifier on
+                let checksum = [0x7E, *device, 0x08, 0x01]
+                    .iter()
+                    .chain(body.iter())
+                    .fold(0, |sum, b| sum ^ b);
+                body.push(checksum & 0x7F);
+                (false, *device, 0x08, 0x01, body)
+            }
+            Universal::SingleNoteTuning {
+                device,
+                program,
+                changes,
+            } => {
+                let changes = &changes[..changes.len().min(MAX_TUNING_CHANGES)];
+                let mut body = vec![*program, changes.len() as u8];
+                for (key, frequency) in changes {
+                    body.push(*key);
+                    body.extend(frequency.to_bytes());
+                }
+                (true, *device, 0x08, 0x02, body)
+            }
+            Universal::Other {
+                real_time,
+                device,
+                sub_id,
+                sub_id2,
+                data,
+            } => (*real_time, *device, *sub_id, *sub_id2, data.clone()),
+        };
+        let manufacturer = if real_time {
+            Manufacturer::UniversalRealTime
+        } else {
+            Manufacturer::UniversalNonRealTime
+        };
+        let mut data = vec![device, sub_id, sub_id2];
+        data.extend(body);
+        SysEx::new(manufacturer, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manufacturer_ids() {
+        let sysex = SysEx::parse(&[0x00, 0x20, 0x3C, 0x01, 0x02]).unwrap();
+        assert_eq!(sysex.manufacturer, Manufacturer::ELEKTRON);
+        assert_eq!(sysex.data, vec![0x01, 0x02]);
+        assert_eq!(sysex.to_payload(), vec![0x00, 0x20, 0x3C, 0x01, 0x02]);
+        assert!(sysex.universal().is_none());
+        assert!(SysEx::parse(&[0x00, 0x20]).is_err());
+        assert!(SysEx::parse(&[]).is_err());
+    }
+
+    #[test]
+    fn universal_round_trip() {
+        let messages = vec![
+            Universal::DeviceInquiryRequest { device: ALL_CALL },
+            Universal::DeviceInquiryReply {
+                device: 0x10,
+                manufacturer: Manufacturer::ROLAND,
+                family: 0x0123,
+                member: 0x0042,
+                version: [1, 2, 3, 4],
+            },
+            Universal::GeneralMidiOn { device: ALL_CALL },
+            Universal::GeneralMidiOff { device: 0x01 },
+            Universal::MasterVolume {
+                device: ALL_CALL,
+                volume: 0x2000,
+            },
+            Universal::TuningDumpRequest {
+                device: 0x00,
+                program: 3,
+            },
+            Universal::TuningDump {
+                device: 0x00,
+                program: 3,
+                name: "Werckmeister".to_string(),
+                frequencies: (0..128)
+                    .map(|k| MtsFrequency {
+                        semitone: k,
+                        fraction: 100,
+                    })
+                    .collect(),
+            },
+            Universal::SingleNoteTuning {
+                device: 0x00,
+                program: 0,
+                changes: vec![(60, MtsFrequency::from_frequency(262.0).unwrap())],
+            },
+        ];
+        for message in messages {
+            let midi = message.to_sysex().to_midi();
+            let sysex = SysEx::try_from(&midi).unwrap();
+            assert_eq!(sysex.universal().unwrap().unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn tuning_dump_name_and_checksum() {
+        let dump = Universal::TuningDump {
+            device: 0x01,
+            program: 0,
+            name: "Just intonation für Orgel und Cembalo".to_string(),
+            frequencies: vec![],
+        };
+        let payload = dump.to_sysex().to_payload();
+        assert_eq!(payload.len(), 1 + 3 + 1 + 16 + 128 * 3 + 1);
+        assert_eq!(&payload[5..21], b"Just intonation ");
+        let sysex = SysEx::parse(&payload).unwrap();
+        match sysex.universal().unwrap().unwrap() {
+            Universal::TuningDump {
+                name, frequencies, ..
+            } => {
+                assert_eq!(name, "Just intonation");
+                assert_eq!(frequencies.len(), 128);
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+
+        let umlaut = Universal::TuningDump {
+            device: 0x01,
+            program: 0,
+            name: "Kirnberger Ä".to_string(),
+            frequencies: vec![],
+        };
+        let payload = umlaut.to_sysex().to_payload();
+        assert_eq!(&payload[5..21], b"Kirnberger      ");
+
+        let mut corrupted = payload.clone();
+        corrupted[30] ^= 0x01;
+        assert!(SysEx::parse(&corrupted)
+            .unwrap()
+            .universal()
+            .unwrap()
+            .is_err());
+        let truncated = &payload[..payload.len() - 1];
+        assert!(SysEx::parse(truncated)
+            .unwrap()
+            .universal()
+            .unwrap()
+            .is_err());
+        let mut extended = payload;
+        extended.push(0x00);
+        assert!(SysEx::parse(&extended)
+            .unwrap()
+            .universal()
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn mts_frequency() {
+        let middle_c = MtsFrequency::from_frequency(261.6256).unwrap();
+        assert_eq!(middle_c.semitone, 60);
+        assert!(middle_c.fraction < 2 || middle_c.fraction > 16382);
+        let quarter_tone = MtsFrequency::from_frequency(440.0 * 2f64.powf(0.5 / 12.0)).unwrap();
+        assert_eq!((quarter_tone.semitone, quarter_tone.fraction), (69, 8192));
+        assert!((quarter_tone.to_frequency() - 452.893).abs() < 1e-3);
+        assert!(MtsFrequency::from_frequency(20000.0).is_none());
+    }
+}
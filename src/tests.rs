@@ -7,13 +7,14 @@ fn from_key_number_to_note() {
     use crate::music::common::KEYBOARD;
     use crate::music::note::Note;
 
-    for kn in 21..127 {
+    for kn in 0..128 {
         let data_kn = messages::Data::KeyNumber(kn);
         let note = match Note::try_from(&data_kn) {
             Ok(note) => note,
-            Err(()) => panic!("Keynumber invalid."),
+            Err(e) => panic!("Keynumber invalid: {}", e),
         };
-        assert_eq!(note.letter, KEYBOARD[((kn - 21) % 12) as usize]);
-        assert_eq!(note.octave, (kn as i8 - 21) / 12);
+        assert_eq!(note.letter, KEYBOARD[(kn % 12) as usize]);
+        assert_eq!(note.octave, (kn / 12) as i8 - 1);
+        assert_eq!(messages::from_note(&note).unwrap(), data_kn);
     }
 }